@@ -0,0 +1,148 @@
+use super::App;
+
+use crate::gnc::{Gnc, ProgramAnalysis};
+use crate::types::Location;
+use crate::ui::types::ProgramInfo;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    thread,
+};
+
+/// result of a worker thread
+#[derive(Debug)]
+struct Analyzed {
+    program_name: String,
+    modified: u64,
+    /// None if the program could not be read
+    hash: Option<String>,
+    /// None if a program with the same content is analyzed already
+    analysis: Option<ProgramAnalysis>,
+}
+
+/// analyses by sha256 of the program, a program with the same content is not analyzed again
+#[derive(Debug)]
+pub struct ProgramAnalyses {
+    by_hash: HashMap<String, ProgramAnalysis>,
+    /// hash of the program files and their modification time when they were analyzed
+    files: HashMap<String, (u64, String)>,
+    /// programs a worker thread analyzes now
+    running: HashSet<String>,
+    sender: Sender<Analyzed>,
+    receiver: Receiver<Analyzed>,
+}
+
+impl Default for ProgramAnalyses {
+    fn default() -> Self {
+        let (sender, receiver) = unbounded();
+        ProgramAnalyses {
+            by_hash: HashMap::new(),
+            files: HashMap::new(),
+            running: HashSet::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl App {
+    /// the listed program with its analysis, if the file did not change since
+    pub fn program_info(&self, program_name: String) -> ProgramInfo {
+        let mut info = ProgramInfo::from_string(program_name);
+        info.analysis = self
+            .analyses
+            .files
+            .get(&info.name)
+            .filter(|(modified, _)| *modified == info.modified_date_ts)
+            .and_then(|(_, hash)| self.analyses.by_hash.get(hash))
+            .cloned();
+        if let Some(analysis) = info.analysis.as_ref() {
+            info.lines_of_code = analysis.lines_of_code;
+        }
+        info
+    }
+    /// analyze the selected program in the background, unless the file did not change since
+    pub fn analyze_program(&mut self, program_name: &str, path: PathBuf) {
+        let modified = ProgramInfo::from_string(program_name.to_owned()).modified_date_ts;
+        let analyzed = self
+            .analyses
+            .files
+            .get(program_name)
+            .is_some_and(|(at, _)| *at == modified);
+        if analyzed || !self.analyses.running.insert(program_name.to_owned()) {
+            return;
+        }
+        let known = self
+            .analyses
+            .by_hash
+            .keys()
+            .cloned()
+            .collect::<HashSet<String>>();
+        let motors = [
+            self.settings.motor_x.clone(),
+            self.settings.motor_y.clone(),
+            self.settings.motor_z.clone(),
+        ];
+        let arc_tolerance = self.settings.arc_tolerance;
+        let sender = self.analyses.sender.clone();
+        let program_name = program_name.to_owned();
+        thread::spawn(move || {
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(_) => {
+                    let _ = sender.send(Analyzed {
+                        program_name,
+                        modified,
+                        hash: None,
+                        analysis: None,
+                    });
+                    return;
+                }
+            };
+            // the program as written, the scale and invert z of a job are not part of the hash
+            let prog = Gnc::from_content(
+                content,
+                5.0,
+                50.0,
+                1.0,
+                Location::default(),
+                0.0,
+                false,
+                arc_tolerance,
+            );
+            let hash = prog.hash();
+            let analysis = if known.contains(&hash) {
+                None
+            } else {
+                Some(prog.analyze([&motors[0], &motors[1], &motors[2]]))
+            };
+            let _ = sender.send(Analyzed {
+                program_name,
+                modified,
+                hash: Some(hash),
+                analysis,
+            });
+        });
+    }
+    /// take the results of the worker threads and send the programs with them
+    pub fn receive_analyses(&mut self) {
+        let mut changed = false;
+        while let Ok(done) = self.analyses.receiver.try_recv() {
+            self.analyses.running.remove(&done.program_name);
+            if let Some(hash) = done.hash {
+                if let Some(analysis) = done.analysis {
+                    self.analyses.by_hash.insert(hash.clone(), analysis);
+                }
+                self.analyses
+                    .files
+                    .insert(done.program_name, (done.modified, hash));
+                changed = true;
+            }
+        }
+        if changed {
+            self.send_available_program_msg();
+        }
+    }
+}
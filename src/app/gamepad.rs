@@ -0,0 +1,177 @@
+use gilrs::{Axis, Button};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ButtonAction {
+    /// terminate the controller
+    Quit,
+    /// run the selected program
+    StartProgram,
+    /// cancel a running program or calibration
+    Cancel,
+    /// reset x and y, or all axis if x and y are already 0
+    ResetPosition,
+    /// calibrate z with the contact pin
+    Calibrate,
+    /// move the program cursor up
+    PreviousProgram,
+    /// move the program cursor down
+    NextProgram,
+    /// select the program under the cursor
+    SelectProgram,
+    /// toggle slow manual moves
+    ToggleSlow,
+    /// switch between stick (velocity) control and incremental jog
+    ToggleJog,
+    /// exact relative move by the jog step size
+    JogXPlus,
+    JogXMinus,
+    JogYPlus,
+    JogYMinus,
+    JogZPlus,
+    JogZMinus,
+    /// select the next larger / smaller jog step size
+    JogStepUp,
+    JogStepDown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AxisAction {
+    MoveX,
+    MoveY,
+    MoveZ,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ButtonBinding {
+    pub button: Button,
+    pub action: ButtonAction,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AxisBinding {
+    pub axis: Axis,
+    pub action: AxisAction,
+    // stick values within the deadzone are ignored [0.0 - 1.0]
+    #[serde(default = "default_deadzone")]
+    pub deadzone: f64,
+    // exponent of the response curve. 1.0 is linear, higher values give finer control near the center
+    #[serde(default = "default_curve")]
+    pub curve: f64,
+    #[serde(default)]
+    pub invert: bool,
+}
+
+fn default_deadzone() -> f64 {
+    0.15f64
+}
+fn default_curve() -> f64 {
+    1.0f64
+}
+
+impl AxisBinding {
+    fn new(axis: Axis, action: AxisAction, invert: bool) -> Self {
+        AxisBinding {
+            axis,
+            action,
+            deadzone: default_deadzone(),
+            curve: default_curve(),
+            invert,
+        }
+    }
+    /**
+     * map a raw stick value to a speed factor
+     *
+     * @return -1.0 to 1.0, 0.0 within the deadzone
+     */
+    pub fn apply(&self, value: f32) -> f64 {
+        let value = value as f64;
+        if value.abs() <= self.deadzone {
+            return 0.0;
+        }
+        let range = (1.0 - self.deadzone).max(f64::EPSILON);
+        let factor = ((value.abs() - self.deadzone) / range)
+            .min(1.0)
+            .powf(self.curve)
+            .copysign(value);
+        if self.invert {
+            -factor
+        } else {
+            factor
+        }
+    }
+}
+
+/// binds gilrs buttons and axis to named actions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GamepadMapping {
+    pub buttons: Vec<ButtonBinding>,
+    pub axes: Vec<AxisBinding>,
+    // bindings in jog mode. They take precedence over the buttons
+    #[serde(default = "default_jog_buttons")]
+    pub jog_buttons: Vec<ButtonBinding>,
+}
+
+fn default_jog_buttons() -> Vec<ButtonBinding> {
+    let button = |button, action| ButtonBinding { button, action };
+    vec![
+        button(Button::DPadRight, ButtonAction::JogXPlus),
+        button(Button::DPadLeft, ButtonAction::JogXMinus),
+        button(Button::DPadUp, ButtonAction::JogYPlus),
+        button(Button::DPadDown, ButtonAction::JogYMinus),
+        button(Button::RightTrigger, ButtonAction::JogZPlus),
+        button(Button::LeftTrigger, ButtonAction::JogZMinus),
+        button(Button::RightTrigger2, ButtonAction::JogStepUp),
+        button(Button::LeftTrigger2, ButtonAction::JogStepDown),
+    ]
+}
+
+impl Default for GamepadMapping {
+    fn default() -> Self {
+        let button = |button, action| ButtonBinding { button, action };
+        GamepadMapping {
+            buttons: vec![
+                button(Button::Select, ButtonAction::Cancel),
+                button(Button::Start, ButtonAction::StartProgram),
+                button(Button::Mode, ButtonAction::StartProgram),
+                button(Button::North, ButtonAction::ResetPosition),
+                button(Button::East, ButtonAction::Calibrate),
+                button(Button::South, ButtonAction::SelectProgram),
+                button(Button::DPadUp, ButtonAction::PreviousProgram),
+                button(Button::DPadDown, ButtonAction::NextProgram),
+                button(Button::West, ButtonAction::ToggleJog),
+            ],
+            axes: vec![
+                AxisBinding::new(Axis::LeftStickX, AxisAction::MoveX, true),
+                AxisBinding::new(Axis::LeftStickY, AxisAction::MoveY, false),
+                AxisBinding::new(Axis::RightStickY, AxisAction::MoveZ, false),
+            ],
+            jog_buttons: default_jog_buttons(),
+        }
+    }
+}
+
+impl GamepadMapping {
+    pub fn button_action(&self, button: Button) -> Option<ButtonAction> {
+        self.buttons
+            .iter()
+            .find(|b| b.button == button)
+            .map(|b| b.action)
+    }
+    /// action of the button in jog mode, falls back to the normal binding
+    pub fn jog_button_action(&self, button: Button) -> Option<ButtonAction> {
+        self.jog_buttons
+            .iter()
+            .find(|b| b.button == button)
+            .map(|b| b.action)
+            .or_else(|| self.button_action(button))
+    }
+    pub fn axis_bindings(&self, axis: Axis) -> impl Iterator<Item = &AxisBinding> {
+        self.axes.iter().filter(move |b| b.axis == axis)
+    }
+}
@@ -0,0 +1,346 @@
+use super::{App, SETTINGS_PATH};
+
+use crate::gnc::{Gnc, NextInstruction};
+use crate::grbl::{Grbl, GrblCommand, GrblJog, GrblRequest, GrblResult, GrblState, AUTH_FAILED};
+use crate::motor::MotorSettings;
+use crate::types::MachineState;
+use crate::ui::auth::Role;
+use crate::ui::types::Mode;
+
+use crossbeam_channel::Sender;
+use std::collections::VecDeque;
+
+/// streamed lines wait while this many tasks are queued in the motor controller
+const PLANNER_BLOCKS: i64 = 15;
+
+/// connects the GRBL senders to the app loop
+pub struct GrblBridge {
+    grbl: Grbl,
+    /// requests waiting for the machine (full planner, homing)
+    pending: VecDeque<GrblRequest>,
+    /// modal state (position, G90 / G91, feed) of the streamed lines
+    stream: Option<Gnc>,
+    /// `$H` is answered when the homing is done
+    homing: Option<Sender<GrblResult>>,
+    /// the program mode of a stream lasts until M2 / M30, a reset or a disconnect
+    streaming: bool,
+}
+
+impl GrblBridge {
+    pub fn new(grbl: Grbl) -> Self {
+        GrblBridge {
+            grbl,
+            pending: VecDeque::new(),
+            stream: None,
+            homing: None,
+            streaming: false,
+        }
+    }
+}
+
+impl App {
+    /// execute the requests of the GRBL senders and update their status report
+    pub fn handle_grbl_requests(&mut self) {
+        let mut bridge = match self.grbl.take() {
+            Some(bridge) => bridge,
+            None => return,
+        };
+
+        while let Ok(request) = bridge.grbl.receiver.try_recv() {
+            match request.command {
+                GrblCommand::FeedHold => self.cnc.pause(true),
+                GrblCommand::CycleStart => self.cnc.pause(false),
+                GrblCommand::JogCancel => {
+                    if self.current_mode == Mode::Manual {
+                        self.cnc.manual_move(0.0, 0.0, 0.0, 0.0);
+                    }
+                }
+                GrblCommand::Disconnected => bridge.streaming = false,
+                GrblCommand::Reset => {
+                    bridge.pending.clear();
+                    bridge.stream = None;
+                    bridge.homing = None;
+                    bridge.streaming = false;
+                    if self.current_mode == Mode::Manual {
+                        self.cnc.pause(false);
+                        self.cnc.manual_move(0.0, 0.0, 0.0, 0.0);
+                    } else {
+                        self.cancel_program();
+                    }
+                }
+                _ => bridge.pending.push_back(request),
+            }
+        }
+
+        if self.current_mode != Mode::Calibrate {
+            if let Some(reply) = bridge.homing.take() {
+                let _ = reply.send(Ok(vec![]));
+            }
+        }
+
+        while let Some(request) = bridge.pending.front() {
+            if !self.grbl_ready(&request.command) {
+                break;
+            }
+            let request = bridge.pending.pop_front().unwrap();
+            if let Some(result) = self.grbl_execute(&mut bridge, request.command) {
+                if let Some(reply) = request.reply {
+                    let _ = reply.send(result);
+                }
+            } else {
+                bridge.homing = request.reply;
+            }
+        }
+
+        {
+            let mut status = bridge.grbl.status.write().unwrap();
+            status.pos = self.cnc.get_pos();
+            status.a = self.cnc.get_pos_a();
+            status.state = if self.cnc.is_paused() {
+                GrblState::Hold
+            } else {
+                match (&self.current_mode, self.cnc.get_state()) {
+                    (Mode::Calibrate, _) => GrblState::Home,
+                    (Mode::Program, _) | (_, MachineState::ProgramTask) => GrblState::Run,
+                    (_, MachineState::ManualTask) => GrblState::Jog,
+                    _ => GrblState::Idle,
+                }
+            };
+        }
+
+        self.grbl = Some(bridge);
+    }
+    /// a program streamed by a GRBL sender has no loaded program
+    fn grbl_streaming(&self) -> bool {
+        self.current_mode == Mode::Program && self.prog.is_none()
+    }
+    /// the machine stays in the program mode between the lines of a stream
+    pub fn grbl_stream_open(&self) -> bool {
+        self.grbl.as_ref().is_some_and(|bridge| bridge.streaming)
+    }
+    /// lines of a cancelled stream start a new one
+    pub fn grbl_close_stream(&mut self) {
+        if let Some(bridge) = self.grbl.as_mut() {
+            bridge.streaming = false;
+        }
+    }
+    fn grbl_ready(&self, command: &GrblCommand) -> bool {
+        match command {
+            _ if self.current_mode == Mode::Calibrate => false,
+            GrblCommand::Line(_) if self.grbl_streaming() => self.cnc.get_queued() < PLANNER_BLOCKS,
+            _ => true,
+        }
+    }
+    /**
+     * execute a GRBL command
+     *
+     * @return the reply, None if it is sent later (homing)
+     */
+    fn grbl_execute(
+        &mut self,
+        bridge: &mut GrblBridge,
+        command: GrblCommand,
+    ) -> Option<GrblResult> {
+        let result = match command {
+            GrblCommand::Line(line) => self.grbl_line(bridge, &line),
+            GrblCommand::Jog(jog) => self.grbl_jog(bridge, jog),
+            GrblCommand::Home if self.current_mode == Mode::Manual => {
+                self.home();
+                return None;
+            }
+            GrblCommand::ReadSettings => Ok(self
+                .grbl_settings()
+                .iter()
+                .map(|(number, value)| format!("${}={:.3}", number, value))
+                .collect()),
+            GrblCommand::WriteSetting(number, value, role) => {
+                self.set_grbl_setting(number, value, role)
+            }
+            GrblCommand::ParserState => {
+                let relative = bridge.stream.as_ref().is_some_and(|s| s.is_relative());
+                Ok(vec![format!(
+                    "[GC:G0 G54 G17 G21 {} G94 {} M9 T0 F0 S0]",
+                    if relative { "G91" } else { "G90" },
+                    if self.cnc.is_switched_on() {
+                        "M3"
+                    } else {
+                        "M5"
+                    }
+                )])
+            }
+            // not idle
+            _ => Err(8),
+        };
+        Some(result)
+    }
+    /// queue a g-code line. The first line of a stream switches to the program mode
+    fn grbl_line(&mut self, bridge: &mut GrblBridge, line: &str) -> GrblResult {
+        if self.current_mode == Mode::Program && !self.grbl_streaming() {
+            // locked by a program of the UI
+            return Err(9);
+        }
+        let start = self.current_mode == Mode::Manual;
+        let pos = self.cnc.get_pos();
+        let a = self.cnc.get_pos_a().unwrap_or(0.0);
+        let arc_tolerance = self.settings.arc_tolerance;
+        let stream = bridge.stream.get_or_insert_with(|| {
+            Gnc::from_content(
+                String::new(),
+                5.0,
+                50.0,
+                1.0,
+                pos.clone(),
+                a,
+                false,
+                arc_tolerance,
+            )
+        });
+        if start {
+            // the machine could be moved in between the streams
+            stream.set_position(pos, a);
+        }
+        stream.push_line(line);
+
+        let mut result = Ok(vec![]);
+        let mut queued = false;
+        for next_instruction in stream {
+            match next_instruction {
+                NextInstruction::Movement(next_movement) => {
+                    self.cnc.query_g_task(next_movement);
+                    queued = true;
+                }
+                NextInstruction::Miscellaneous(next_miscellaneous) => {
+                    self.cnc.query_m_task(next_miscellaneous);
+                    queued = true;
+                }
+                // unsupported command
+                NextInstruction::NotSupported(_) => result = Err(20),
+                _ => (),
+            }
+        }
+        if queued && start {
+            self.prog = None;
+            self.set_current_mode(Mode::Program);
+            self.in_opp = true;
+            bridge.streaming = true;
+        }
+        if ends_program(line) {
+            bridge.streaming = false;
+        }
+        result
+    }
+    fn grbl_jog(&mut self, bridge: &GrblBridge, jog: GrblJog) -> GrblResult {
+        if self.current_mode != Mode::Manual {
+            return Err(8);
+        }
+        let relative = jog
+            .relative
+            .unwrap_or_else(|| bridge.stream.as_ref().is_some_and(|s| s.is_relative()));
+        let delta = |target: Option<f64>, current: f64| match target {
+            Some(target) if relative => target,
+            Some(target) => target - current,
+            None => 0.0,
+        };
+        let pos = self.cnc.get_pos();
+        let delta_a = match (jog.a, self.cnc.get_pos_a()) {
+            (Some(_), None) => return Err(20),
+            (target, a) => delta(target, a.unwrap_or(0.0)),
+        };
+        self.cnc.manual_step(
+            delta(jog.x, pos.x),
+            delta(jog.y, pos.y),
+            delta(jog.z, pos.z),
+            delta_a,
+            jog.feed,
+        );
+        Ok(vec![])
+    }
+    /// GRBL settings mapped to the settings of the controller
+    fn grbl_settings(&self) -> Vec<(u32, f64)> {
+        let steps_per_mm = |m: &MotorSettings| 1.0 / m.step_size;
+        let max_rate = |m: &MotorSettings| m.max_step_speed as f64 * m.step_size * 60.0;
+        let s = &self.settings;
+        let mut settings = vec![
+            (0, 10.0),
+            (1, 25.0),
+            (10, 1.0),
+            (12, s.arc_tolerance),
+            (13, 0.0),
+            (20, 0.0),
+            (21, 0.0),
+            (22, 1.0),
+            (100, steps_per_mm(&s.motor_x)),
+            (101, steps_per_mm(&s.motor_y)),
+            (102, steps_per_mm(&s.motor_z)),
+            (110, max_rate(&s.motor_x)),
+            (111, max_rate(&s.motor_y)),
+            (112, max_rate(&s.motor_z)),
+        ];
+        if let Some(motor_a) = s.motor_a.as_ref() {
+            settings.push((103, steps_per_mm(motor_a)));
+            settings.push((113, max_rate(motor_a)));
+        }
+        settings.sort_by_key(|(number, _)| *number);
+        settings
+    }
+    /// write a mapped GRBL setting. Steps and rates are used after a restart
+    fn set_grbl_setting(&mut self, number: u32, value: f64, role: Role) -> GrblResult {
+        // fixed by the controller, accepted for the setup wizards of the senders
+        if [0, 1, 10, 13, 20, 21, 22].contains(&number) {
+            return Ok(vec![]);
+        }
+        // the settings file is admin only, like the system settings of the UI
+        if role < Role::Admin {
+            return Err(AUTH_FAILED);
+        }
+        let s = &mut self.settings;
+        match number {
+            12 if value > 0.0 => s.arc_tolerance = value,
+            100..=103 | 110..=113 if value > 0.0 => {
+                let motor = match number % 10 {
+                    0 => Some(&mut s.motor_x),
+                    1 => Some(&mut s.motor_y),
+                    2 => Some(&mut s.motor_z),
+                    _ => s.motor_a.as_mut(),
+                }
+                .ok_or(3u8)?;
+                if number < 110 {
+                    motor.step_size = 1.0 / value;
+                } else {
+                    motor.max_step_speed = (value / 60.0 / motor.step_size).round() as u32;
+                }
+                self.warning("GRBL changed the motor settings, restart the controller".to_string());
+            }
+            _ => return Err(3),
+        }
+        self.settings.write_to_file(SETTINGS_PATH).map_err(|e| {
+            self.error(format!("GRBL settings are not saved: {}", e));
+            // settings write failed
+            7u8
+        })?;
+        Ok(vec![])
+    }
+}
+
+/// true if the line has a M2 or M30 word
+fn ends_program(line: &str) -> bool {
+    // comments in parentheses and after a semicolon
+    let mut code = String::new();
+    let mut comment = false;
+    for c in line.chars() {
+        match c {
+            ';' => break,
+            '(' => comment = true,
+            ')' => comment = false,
+            _ if !comment && !c.is_whitespace() => code.push(c.to_ascii_uppercase()),
+            _ => (),
+        }
+    }
+    code.split('M').skip(1).any(|word| {
+        let number: String = word
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        matches!(number.parse::<f64>(), Ok(n) if n == 2.0 || n == 30.0)
+    })
+}
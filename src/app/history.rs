@@ -0,0 +1,155 @@
+use super::{App, HISTORY_PATH};
+
+use crate::types::Location;
+use crate::ui::types::{JobOutcome, JobRecord, WorkOffset, WsMessages, WsReplyMessage};
+use std::{
+    fs,
+    time::{Duration, Instant, SystemTime},
+};
+use uuid::Uuid;
+
+/// older jobs are dropped from the history file
+const HISTORY_SIZE: usize = 1000;
+/// the line reached is written this often, to resume a job after a power loss
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+fn now_ts() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// started programs and how they ended, the newest last
+#[derive(Debug)]
+pub struct JobHistory {
+    jobs: Vec<JobRecord>,
+    checkpoint: Instant,
+}
+
+impl JobHistory {
+    /// a job still running in the file was interrupted by a stop of the controller
+    pub fn from_file(file_path: &str) -> JobHistory {
+        let mut jobs: Vec<JobRecord> = fs::read_to_string(file_path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        for job in jobs.iter_mut() {
+            if job.outcome == JobOutcome::Running {
+                job.outcome = JobOutcome::Alarm;
+            }
+        }
+        JobHistory {
+            jobs,
+            checkpoint: Instant::now(),
+        }
+    }
+    pub fn write_to_file(&self, file_path: &str) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(&self.jobs).unwrap();
+        fs::write(file_path, data).map_err(|e| format!("{:?}", e))
+    }
+    pub fn get_jobs(&self) -> Vec<JobRecord> {
+        self.jobs.clone()
+    }
+    /// the job that runs now
+    pub fn running(&mut self) -> Option<&mut JobRecord> {
+        self.jobs
+            .last_mut()
+            .filter(|job| job.outcome == JobOutcome::Running)
+    }
+    fn start(
+        &mut self,
+        program_name: &str,
+        hash: String,
+        scale: f64,
+        invert_z: bool,
+        offset: WorkOffset,
+    ) {
+        // a program started over a running one replaces it
+        if let Some(job) = self.running() {
+            job.ended_ts = Some(now_ts());
+            job.outcome = JobOutcome::Cancelled;
+        }
+        if self.jobs.len() >= HISTORY_SIZE {
+            self.jobs.drain(..=self.jobs.len() - HISTORY_SIZE);
+        }
+        self.jobs.push(JobRecord {
+            program_name: program_name.to_owned(),
+            hash,
+            started_ts: now_ts(),
+            ended_ts: None,
+            outcome: JobOutcome::Running,
+            line: 0,
+            scale,
+            invert_z,
+            offset,
+        });
+    }
+}
+
+impl App {
+    /// log the program that was just loaded into `prog`
+    pub fn history_start(
+        &mut self,
+        program_name: &str,
+        scale: f64,
+        invert_z: bool,
+        offset: &Location<f64>,
+    ) {
+        if let Some(hash) = self.prog.as_ref().map(|prog| prog.hash()) {
+            let offset = WorkOffset {
+                x: offset.x,
+                y: offset.y,
+                z: offset.z,
+            };
+            self.history
+                .start(program_name, hash, scale, invert_z, offset);
+            self.history_changed();
+        }
+    }
+    /// remember the line of the task the motor controller works on
+    pub fn history_track_line(&mut self) {
+        let line = self
+            .prog_lines
+            .get(self.cnc.get_steps_done() as usize)
+            .copied();
+        let mut changed = false;
+        if let (Some(job), Some(line)) = (self.history.running(), line) {
+            if line > job.line {
+                job.line = line;
+                changed = true;
+            }
+        }
+        if changed && self.history.checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+            self.history.checkpoint = Instant::now();
+            self.history_changed();
+        }
+    }
+    /// the program of the current job ended (or was canceled)
+    pub fn end_job(&mut self, outcome: JobOutcome) {
+        if let Some(job) = self.history.running() {
+            job.ended_ts = Some(now_ts());
+            job.outcome = outcome.clone();
+            self.history_changed();
+        }
+        if outcome == JobOutcome::Completed {
+            self.queue_job_done();
+        } else {
+            self.queue_stop();
+        }
+    }
+    fn history_changed(&self) {
+        if let Err(e) = self.history.write_to_file(HISTORY_PATH) {
+            self.error(format!("couldn't write the job history: {}", e));
+        }
+    }
+    pub fn send_history_reply_message(&self, to: Uuid) {
+        self.ui_data_sender
+            .send(WsMessages::Reply {
+                to,
+                msg: WsReplyMessage::History {
+                    jobs: self.history.get_jobs(),
+                },
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+use super::App;
+
+use crate::motor::MotorMetrics;
+use crate::ui::types::Mode;
+
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// counters of the metrics endpoint, written by the app loop and the motor controller thread
+#[derive(Debug)]
+pub struct Metrics {
+    motors: Vec<(String, Arc<MotorMetrics>)>,
+    spindle_on: AtomicBool,
+    spindle_on_us: AtomicU64,
+    program_running: AtomicBool,
+    jobs: AtomicU64,
+    job_ms_sum: AtomicU64,
+    last_job_ms: AtomicU64,
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn seconds(ms: &AtomicU64) -> f64 {
+    ms.load(Relaxed) as f64 / 1000.0
+}
+
+impl Metrics {
+    pub fn new(motors: Vec<(String, Arc<MotorMetrics>)>) -> Self {
+        Metrics {
+            motors,
+            spindle_on: AtomicBool::new(false),
+            spindle_on_us: AtomicU64::new(0),
+            program_running: AtomicBool::new(false),
+            jobs: AtomicU64::new(0),
+            job_ms_sum: AtomicU64::new(0),
+            last_job_ms: AtomicU64::new(0),
+        }
+    }
+    /// Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        metric(
+            &mut out,
+            "cnc_spindle_on",
+            "gauge",
+            "1 if the spindle is switched on",
+        );
+        let _ = writeln!(
+            out,
+            "cnc_spindle_on {}",
+            self.spindle_on.load(Relaxed) as u8
+        );
+        metric(
+            &mut out,
+            "cnc_spindle_on_seconds_total",
+            "counter",
+            "time the spindle was switched on since the start",
+        );
+        let _ = writeln!(
+            out,
+            "cnc_spindle_on_seconds_total {:.3}",
+            self.spindle_on_us.load(Relaxed) as f64 / 1e6
+        );
+        metric(
+            &mut out,
+            "cnc_program_running",
+            "gauge",
+            "1 while a program runs",
+        );
+        let _ = writeln!(
+            out,
+            "cnc_program_running {}",
+            self.program_running.load(Relaxed) as u8
+        );
+        metric(
+            &mut out,
+            "cnc_job_duration_seconds",
+            "summary",
+            "duration of the finished and canceled jobs",
+        );
+        let _ = writeln!(
+            out,
+            "cnc_job_duration_seconds_sum {:.3}",
+            seconds(&self.job_ms_sum)
+        );
+        let _ = writeln!(
+            out,
+            "cnc_job_duration_seconds_count {}",
+            self.jobs.load(Relaxed)
+        );
+        metric(
+            &mut out,
+            "cnc_last_job_duration_seconds",
+            "gauge",
+            "duration of the last job",
+        );
+        let _ = writeln!(
+            out,
+            "cnc_last_job_duration_seconds {:.3}",
+            seconds(&self.last_job_ms)
+        );
+
+        metric(
+            &mut out,
+            "cnc_motor_steps_total",
+            "counter",
+            "steps done by the motor, including the backlash compensation",
+        );
+        for (axis, m) in self.motors.iter() {
+            let _ = writeln!(
+                out,
+                "cnc_motor_steps_total{{axis=\"{}\"}} {}",
+                axis,
+                m.steps.load(Relaxed)
+            );
+        }
+        metric(
+            &mut out,
+            "cnc_motor_blocked_steps_total",
+            "counter",
+            "steps refused by the driver (end switch)",
+        );
+        for (axis, m) in self.motors.iter() {
+            let _ = writeln!(
+                out,
+                "cnc_motor_blocked_steps_total{{axis=\"{}\"}} {}",
+                axis,
+                m.blocked.load(Relaxed)
+            );
+        }
+        metric(
+            &mut out,
+            "cnc_motor_max_jitter_seconds",
+            "gauge",
+            "largest delay of a step behind its planned time",
+        );
+        for (axis, m) in self.motors.iter() {
+            let _ = writeln!(
+                out,
+                "cnc_motor_max_jitter_seconds{{axis=\"{}\"}} {:.9}",
+                axis,
+                m.max_jitter_ns.load(Relaxed) as f64 / 1e9
+            );
+        }
+        out
+    }
+}
+
+impl App {
+    /// called on every loop
+    pub fn update_metrics(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.metrics_updated);
+        self.metrics_updated = now;
+
+        let spindle_on = self.cnc.is_switched_on();
+        self.metrics.spindle_on.store(spindle_on, Relaxed);
+        if spindle_on {
+            self.metrics
+                .spindle_on_us
+                .fetch_add(elapsed.as_micros() as u64, Relaxed);
+        }
+        self.metrics
+            .program_running
+            .store(self.current_mode == Mode::Program, Relaxed);
+    }
+    /// a job runs from entering the program mode until leaving it
+    pub fn track_job(&mut self, mode: &Mode) {
+        match (&self.current_mode, mode) {
+            (Mode::Program, Mode::Program) => (),
+            (_, Mode::Program) => self.job_started = Some(Instant::now()),
+            (Mode::Program, _) => {
+                if let Some(started) = self.job_started.take() {
+                    let ms = started.elapsed().as_millis() as u64;
+                    self.metrics.jobs.fetch_add(1, Relaxed);
+                    self.metrics.job_ms_sum.fetch_add(ms, Relaxed);
+                    self.metrics.last_job_ms.store(ms, Relaxed);
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -1,37 +1,78 @@
+mod analysis;
+pub mod gamepad;
+mod grbl;
+mod history;
+pub mod metrics;
+mod queue;
 mod run;
 mod setters;
 pub mod settings;
+pub mod simulation;
+mod terminal;
 mod ui_communication;
 
 use crate::gnc::Gnc;
-use crate::io::{Actor, Switch};
+use crate::io::{
+    handwheel::{HandwheelSettings, HandwheelSource},
+    Actor, Switch,
+};
 use crate::motor::{
     motor_controller::{ExternalInput, ExternalInputRequest, MotorController},
-    Driver, MockMotor, Motor, StepMotor,
+    Driver, MockMotor, Motor, MotorSettings, PulseBackendType, PulseDriver, SlavedDriver,
+    StepMotor, ThreadedPulseBackend,
 };
 use crate::types::Location;
+use crate::ui::auth::Auth;
 use crate::ui::types::{Mode, WsCommandsFrom, WsMessages};
 
+use analysis::ProgramAnalyses;
+use grbl::GrblBridge;
+use history::JobHistory;
+use metrics::Metrics;
+use queue::JobQueue;
 use settings::Settings;
+use simulation::Simulation;
+use terminal::TerminalUi;
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use futures::executor::ThreadPool;
 use gilrs::Gilrs;
 use notify::{raw_watcher, RawEvent, RecursiveMode, Watcher};
-use std::{boxed::Box, fs, sync::mpsc, thread, time::Duration};
+use std::{
+    boxed::Box,
+    fs,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
 
 const SETTINGS_PATH: &str = "./settings.yaml";
+const QUEUE_PATH: &str = "./queue.yaml";
+const HISTORY_PATH: &str = "./history.json";
+/// file extensions listed as programs
+pub const PROGRAM_EXTENSIONS: [&str; 3] = ["gcode", "ngc", "nc"];
 
 pub struct App {
     pub available_progs: Vec<String>,
     pub pool: ThreadPool,
     pub settings: Settings,
-    pub gilrs: Gilrs,
+    pub gilrs: Option<Gilrs>,
+    pub gamepad_connected: bool,
+    pub handwheel: Option<Box<dyn HandwheelSource>>,
+    terminal: Option<TerminalUi>,
+    grbl: Option<GrblBridge>,
     pub in_opp: bool,
     pub cnc: MotorController,
     pub current_mode: Mode,
     pub prog: Option<Gnc>,
+    // line of every task of the program queued at the motor controller
+    prog_lines: Vec<usize>,
+    pub queue: JobQueue,
+    pub history: JobHistory,
+    analyses: ProgramAnalyses,
     pub calibrated: bool,
+    pub calibrate_z: bool,
     pub selected_program: Option<String>,
     ui_data_sender: Sender<WsMessages>,
     ui_cmd_receiver: Receiver<WsCommandsFrom>,
@@ -39,15 +80,87 @@ pub struct App {
     pub program_select_cursor: i32,
     pub input_reduce: u32,
     pub last_control: Location<f64>,
+    // virtual joystick of the UI, dropped after the jog_timeout
+    pub ui_control: Option<Location<f64>>,
+    pub ui_control_received: Instant,
     pub freeze_x: bool,
     pub freeze_y: bool,
     pub slow_control: bool,
+    pub jog: bool,
+    pub jog_step: f64,
     pub display_counter: u32,
     pub steps_todo: i64,
     pub steps_done: i64,
+    // the motor controller thread fails on a closed channel
+    #[allow(dead_code)]
     pub external_input_enabled: bool,
+    #[allow(dead_code)]
     pub external_input_sender: mpsc::Sender<ExternalInput>,
+    #[allow(dead_code)]
     pub external_input_request_receiver: mpsc::Receiver<ExternalInputRequest>,
+    pub metrics: Arc<Metrics>,
+    metrics_updated: Instant,
+    pub simulation: Arc<Simulation>,
+    /// logins of the UI, shared with the GRBL senders
+    pub auth: Arc<Auth>,
+    job_started: Option<Instant>,
+}
+
+/// the file has one of the program extensions
+pub fn is_program(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| PROGRAM_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/**
+ * resolve a program name of a client inside one of the input directories
+ *
+ * @return the resolved path, or why the name is rejected
+ */
+pub fn program_path(input_dir: &[String], program_name: &str) -> Result<PathBuf, String> {
+    if !is_program(program_name) {
+        return Err(format!(
+            "{} is no program ({})",
+            program_name,
+            PROGRAM_EXTENSIONS.join(", ")
+        ));
+    }
+    let path = Path::new(program_name);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("{} has no file name", program_name))?;
+    // a saved program may not exist yet, its directory has to
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let dir = fs::canonicalize(parent)
+        .map_err(|_| format!("directory of {} does not exist", program_name))?;
+    let resolved = dir.join(file_name);
+    // an existing file could be a link out of the input directories. A dangling link
+    // has no target to check, a save would create the file wherever it points to
+    let target = match fs::symlink_metadata(&resolved) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(&resolved)
+            .map_err(|_| format!("{} is a link to no file", program_name))?,
+        _ => fs::canonicalize(&resolved).unwrap_or_else(|_| resolved.clone()),
+    };
+
+    let roots = input_dir
+        .iter()
+        .filter_map(|root| fs::canonicalize(root).ok())
+        .collect::<Vec<PathBuf>>();
+    if roots.iter().any(|root| dir.starts_with(root))
+        && roots.iter().any(|root| target.starts_with(root))
+    {
+        Ok(resolved)
+    } else {
+        Err(format!(
+            "{} is outside of the input directories",
+            program_name
+        ))
+    }
 }
 
 impl App {
@@ -55,53 +168,86 @@ impl App {
         let pool = ThreadPool::new().expect("Failed to build pool");
         let settings = Settings::from_file(SETTINGS_PATH);
 
+        // the gamepad is optional. It can be connected any time later
         let gilrs = Gilrs::new()
-            .map_err(|_| "gamepad not valid")
-            .expect("controller is missing");
-
-        if !App::gamepad_connected(&gilrs) {
-            panic!("no gamepad connected");
+            .map_err(|e| println!("gamepad support is not available: {:?}", e))
+            .ok();
+        let gamepad_connected = gilrs.as_ref().is_some_and(App::gamepad_connected);
+        if !gamepad_connected {
+            println!("no gamepad connected");
         }
 
         // init UI connection channel
         let (ui_data_sender, ui_data_receiver) = unbounded::<WsMessages>();
         let (ui_cmd_sender, ui_cmd_receiver) = unbounded::<WsCommandsFrom>();
+        // the MQTT bridge publishes the messages for the UI on their way
+        let ui_data_sender =
+            crate::mqtt::start(&settings.mqtt, ui_data_sender, ui_cmd_sender.clone());
 
         // init external_input channel
         let (external_input_sender, external_input_receiver) = mpsc::channel::<ExternalInput>();
         let (external_input_request_sender, external_input_request_receiver) =
             mpsc::channel::<ExternalInputRequest>();
 
+        let handwheel = App::create_handwheel(&settings.handwheel);
+        let terminal = if settings.terminal_ui {
+            TerminalUi::start()
+        } else {
+            None
+        };
+        let auth = Arc::new(Auth::new(settings.auth.clone()));
+        let grbl = crate::grbl::start(&settings.grbl, auth.clone()).map(GrblBridge::new);
+        let cnc = App::create_cnc_from_settings(
+            &settings,
+            external_input_receiver,
+            external_input_request_sender,
+        );
+        let metrics = Arc::new(Metrics::new(cnc.get_motor_metrics()));
+
         // return tuple with app and ui channel
         let mut app = App {
             available_progs: App::read_available_progs(&settings.input_dir),
             external_input_enabled: settings.external_input_enabled,
-            cnc: App::create_cnc_from_settings(
-                &settings,
-                external_input_receiver,
-                external_input_request_sender,
-            ),
+            cnc,
             pool,
             settings,
             gilrs,
+            gamepad_connected,
+            handwheel,
+            terminal,
+            grbl,
             in_opp: false,
             current_mode: Mode::Manual,
             prog: None,
+            prog_lines: Vec::new(),
+            queue: JobQueue::from_file(QUEUE_PATH),
+            history: JobHistory::from_file(HISTORY_PATH),
+            analyses: ProgramAnalyses::default(),
             calibrated: false,
+            calibrate_z: false,
             selected_program: None,
             ui_data_sender,
             ui_cmd_receiver,
             program_select_cursor: 0,
             input_reduce: 0,
             last_control: Location::default(),
+            ui_control: None,
+            ui_control_received: Instant::now(),
             display_counter: 0,
             steps_todo: 0,
             steps_done: 0,
             freeze_x: false,
             freeze_y: false,
             slow_control: false,
+            jog: false,
+            jog_step: 1.0,
             external_input_sender,
             external_input_request_receiver,
+            metrics,
+            metrics_updated: Instant::now(),
+            simulation: Arc::new(Simulation::default()),
+            auth,
+            job_started: None,
         };
         app.run(ui_data_receiver, ui_cmd_sender);
     }
@@ -115,46 +261,23 @@ impl App {
             .map(|pin| Actor::new(pin, false, false));
         let z_calibrate = settings.calibrate_z_gpio.map(|pin| Switch::new(pin, false));
 
-        let driver_x: Box<dyn Driver + Send> = if settings.dev_mode {
-            Box::new(MockMotor::new(settings.motor_x.step_size))
-        } else {
-            Box::new(StepMotor::from_settings(settings.motor_x.clone()))
-        };
-        let driver_y: Box<dyn Driver + Send> = if settings.dev_mode {
-            Box::new(MockMotor::new(settings.motor_y.step_size))
-        } else {
-            Box::new(StepMotor::from_settings(settings.motor_y.clone()))
-        };
-        let driver_z: Box<dyn Driver + Send> = if settings.dev_mode {
-            Box::new(MockMotor::new(settings.motor_z.step_size))
-        } else {
-            Box::new(StepMotor::from_settings(settings.motor_z.clone()))
-        };
+        let driver_x =
+            App::create_driver(settings, &settings.motor_x, settings.motor_x_slave.as_ref());
+        let driver_y =
+            App::create_driver(settings, &settings.motor_y, settings.motor_y_slave.as_ref());
+        let driver_z = App::create_driver(settings, &settings.motor_z, None);
+        let driver_a = settings
+            .motor_a
+            .as_ref()
+            .map(|m| App::create_driver(settings, m, None));
 
-        let motor_x = Motor::new(
-            "x".to_string(),
-            settings.motor_x.max_step_speed.into(),
-            settings.motor_x.acceleration,
-            settings.motor_x.acceleration_damping,
-            settings.motor_x.free_step_speed,
-            driver_x,
-        );
-        let motor_y = Motor::new(
-            "y".to_string(),
-            settings.motor_y.max_step_speed.into(),
-            settings.motor_y.acceleration,
-            settings.motor_y.acceleration_damping,
-            settings.motor_y.free_step_speed,
-            driver_y,
-        );
-        let motor_z = Motor::new(
-            "z".to_string(),
-            settings.motor_z.max_step_speed.into(),
-            settings.motor_z.acceleration,
-            settings.motor_z.acceleration_damping,
-            settings.motor_z.free_step_speed,
-            driver_z,
-        );
+        let motor_x = App::create_motor("x", &settings.motor_x, driver_x);
+        let motor_y = App::create_motor("y", &settings.motor_y, driver_y);
+        let motor_z = App::create_motor("z", &settings.motor_z, driver_z);
+        let motor_a = match (settings.motor_a.as_ref(), driver_a) {
+            (Some(motor_a), Some(driver_a)) => Some(App::create_motor("a", motor_a, driver_a)),
+            _ => None,
+        };
 
         // create cnc MotorController
         MotorController::new(
@@ -163,12 +286,76 @@ impl App {
             motor_x,
             motor_y,
             motor_z,
+            motor_a,
             z_calibrate,
             settings.external_input_enabled,
             external_input_receiver,
             external_input_request_sender,
         )
     }
+    fn create_motor(name: &str, motor: &MotorSettings, driver: Box<dyn Driver + Send>) -> Motor {
+        Motor::new(
+            name.to_string(),
+            motor.max_step_speed.into(),
+            motor.acceleration,
+            motor.acceleration_damping,
+            motor.free_step_speed,
+            motor.backlash,
+            driver,
+        )
+    }
+    fn create_handwheel(settings: &HandwheelSettings) -> Option<Box<dyn HandwheelSource>> {
+        if !settings.enabled {
+            return None;
+        }
+        #[cfg(feature = "handwheel")]
+        {
+            Some(Box::new(crate::io::handwheel::XhcHandwheel::new(
+                settings.vendor_id,
+                settings.product_id,
+            )))
+        }
+        #[cfg(not(feature = "handwheel"))]
+        {
+            println!(
+                "handwheel is enabled, but the controller is built without the handwheel feature"
+            );
+            None
+        }
+    }
+    fn create_driver(
+        settings: &Settings,
+        motor: &MotorSettings,
+        slave: Option<&MotorSettings>,
+    ) -> Box<dyn Driver + Send> {
+        let hardware = |m: &MotorSettings| -> Box<dyn Driver + Send> {
+            if settings.dev_mode {
+                Box::new(MockMotor::new(m.step_size))
+            } else {
+                Box::new(StepMotor::from_settings(m.clone()))
+            }
+        };
+        let driver = match slave {
+            Some(slave) => Box::new(SlavedDriver::new(hardware(motor), hardware(slave))),
+            None => hardware(motor),
+        };
+
+        match settings.pulse_backend {
+            PulseBackendType::Direct => driver,
+            PulseBackendType::Threaded => {
+                let backend = if settings.dev_mode {
+                    ThreadedPulseBackend::simulation(driver)
+                } else {
+                    ThreadedPulseBackend::new(driver)
+                };
+                Box::new(PulseDriver::new(
+                    Box::new(backend),
+                    motor.step_size,
+                    Duration::from_secs_f64(settings.pulse_latency),
+                ))
+            }
+        }
+    }
     fn gamepad_connected(gilrs: &Gilrs) -> bool {
         let mut gamepad_found = false;
         for (_id, gamepad) in gilrs.gamepads() {
@@ -184,9 +371,7 @@ impl App {
                 fs::read_dir(path)
                     .unwrap()
                     .map(|res| res.expect("ok").path().to_str().unwrap().to_owned())
-                    .filter(|name| {
-                        name.ends_with(".gcode") || name.ends_with(".ngc") || name.ends_with(".nc")
-                    })
+                    .filter(|name| is_program(name))
             })
             .collect::<Vec<String>>()
     }
@@ -264,3 +449,76 @@ impl App {
         (send_path_changed, receiver_new_progs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use uuid::Uuid;
+
+    /// input directory `progs` and a directory `outside` next to it
+    struct Dirs(PathBuf);
+
+    impl Dirs {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("cnc-program-path-{}", Uuid::new_v4()));
+            fs::create_dir_all(root.join("progs")).unwrap();
+            fs::create_dir_all(root.join("outside")).unwrap();
+            fs::write(root.join("outside/b.nc"), "G0 X1\n").unwrap();
+            fs::write(root.join("progs/c.nc"), "G0 X1\n").unwrap();
+            Dirs(root)
+        }
+        fn input_dir(&self) -> Vec<String> {
+            vec![self.path("progs")]
+        }
+        fn path(&self, name: &str) -> String {
+            self.0.join(name).to_string_lossy().to_string()
+        }
+        fn resolve(&self, name: &str) -> Result<PathBuf, String> {
+            program_path(&self.input_dir(), &self.path(name))
+        }
+    }
+
+    impl Drop for Dirs {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn names_inside_the_input_directory_resolve() {
+        let dirs = Dirs::new();
+        assert!(dirs.resolve("progs/a.nc").is_ok());
+        assert!(dirs.resolve("progs/c.nc").is_ok());
+        assert!(dirs.resolve("progs/../progs/a.nc").is_ok());
+        assert!(dirs.resolve("progs/a.txt").is_err());
+    }
+
+    #[test]
+    fn parent_and_absolute_paths_can_not_leave_the_input_directory() {
+        let dirs = Dirs::new();
+        assert!(dirs.resolve("progs/../outside/a.nc").is_err());
+        assert!(dirs.resolve("progs/../outside/b.nc").is_err());
+        assert!(dirs.resolve("outside/b.nc").is_err());
+        assert!(program_path(&dirs.input_dir(), "/etc/passwd.nc").is_err());
+        // relative to the working directory, which is no input directory
+        assert!(program_path(&dirs.input_dir(), "../a.nc").is_err());
+        // a directory that does not exist
+        assert!(dirs.resolve("progs/new/a.nc").is_err());
+    }
+
+    #[test]
+    fn links_out_of_the_input_directory_are_rejected() {
+        let dirs = Dirs::new();
+        symlink(dirs.path("outside/b.nc"), dirs.path("progs/link.nc")).unwrap();
+        symlink(dirs.path("outside/gone.nc"), dirs.path("progs/dangling.nc")).unwrap();
+        symlink(dirs.path("progs/c.nc"), dirs.path("progs/inside.nc")).unwrap();
+        symlink(dirs.path("outside"), dirs.path("progs/dir")).unwrap();
+
+        assert!(dirs.resolve("progs/link.nc").is_err());
+        assert!(dirs.resolve("progs/dangling.nc").is_err());
+        assert!(dirs.resolve("progs/dir/b.nc").is_err());
+        assert!(dirs.resolve("progs/dir/new.nc").is_err());
+        assert!(dirs.resolve("progs/inside.nc").is_ok());
+    }
+}
@@ -0,0 +1,213 @@
+use super::{program_path, App, QUEUE_PATH};
+
+use crate::types::Location;
+use crate::ui::types::{
+    Mode, QueueState, QueuedJob, WorkOffset, WsCommandQueue, WsMessages, WsQueueMessage,
+    WsReplyMessage,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use uuid::Uuid;
+
+/// programs run one after the other. The jobs are kept over a restart, the queue starts stopped
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobQueue {
+    next_id: u32,
+    jobs: Vec<QueuedJob>,
+    #[serde(skip)]
+    state: QueueState,
+    /// id of the job started from the queue
+    #[serde(skip)]
+    running: Option<u32>,
+}
+
+impl JobQueue {
+    pub fn from_file(file_path: &str) -> JobQueue {
+        fs::read_to_string(file_path)
+            .ok()
+            .and_then(|data| serde_yaml::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+    pub fn write_to_file(&self, file_path: &str) -> Result<(), String> {
+        let data = serde_yaml::to_string(self).unwrap();
+        fs::write(file_path, data).map_err(|e| format!("{:?}", e))
+    }
+    pub fn get_msg(&self) -> WsQueueMessage {
+        WsQueueMessage {
+            state: self.state.clone(),
+            jobs: self.jobs.clone(),
+        }
+    }
+    fn add(&mut self, program_name: String, invert_z: bool, scale: f64, offset: WorkOffset) {
+        self.next_id += 1;
+        self.jobs.push(QueuedJob {
+            id: self.next_id,
+            program_name,
+            invert_z,
+            scale,
+            offset,
+        });
+    }
+    /// index of the job, the running job (always the first) can't be changed
+    fn position(&self, id: u32) -> Option<usize> {
+        if self.running == Some(id) {
+            return None;
+        }
+        self.jobs.iter().position(|job| job.id == id)
+    }
+    fn remove(&mut self, id: u32) -> bool {
+        self.position(id).map(|i| self.jobs.remove(i)).is_some()
+    }
+    fn move_to(&mut self, id: u32, index: usize) -> bool {
+        let first = self.running.map_or(0, |_| 1);
+        match self.position(id) {
+            Some(i) => {
+                let job = self.jobs.remove(i);
+                let index = index.max(first).min(self.jobs.len());
+                self.jobs.insert(index, job);
+                true
+            }
+            None => false,
+        }
+    }
+    fn clear(&mut self) {
+        let running = self.running;
+        self.jobs.retain(|job| Some(job.id) == running);
+        if self.jobs.is_empty() {
+            self.state = QueueState::Stopped;
+        }
+    }
+}
+
+impl App {
+    pub fn handle_queue_command(&mut self, uuid: Uuid, cmd: WsCommandQueue) {
+        match cmd {
+            WsCommandQueue::Get => self.send_queue_reply_message(uuid),
+            WsCommandQueue::Add {
+                program_name,
+                invert_z,
+                scale,
+                offset,
+            } => match program_path(&self.settings.input_dir, &program_name) {
+                Ok(_) => {
+                    self.queue.add(program_name, invert_z, scale, offset);
+                    self.queue_changed();
+                }
+                Err(reason) => self.send_program_rejected_message(uuid, program_name, reason),
+            },
+            WsCommandQueue::Remove { id } => {
+                if self.queue.remove(id) {
+                    self.queue_changed();
+                } else {
+                    self.warning(format!("job {} is running or not queued", id));
+                }
+            }
+            WsCommandQueue::Move { id, index } => {
+                if self.queue.move_to(id, index) {
+                    self.queue_changed();
+                } else {
+                    self.warning(format!("job {} is running or not queued", id));
+                }
+            }
+            WsCommandQueue::Clear => {
+                self.queue.clear();
+                self.queue_changed();
+            }
+            WsCommandQueue::Start => {
+                if self.queue.running.is_some() || self.current_mode != Mode::Manual {
+                    self.warning("the queue can only be started in manual mode".to_string());
+                } else {
+                    self.start_queued_job();
+                }
+            }
+            WsCommandQueue::Confirm => {
+                if self.queue.state == QueueState::WaitingForConfirm
+                    && self.current_mode == Mode::Manual
+                {
+                    self.start_queued_job();
+                } else {
+                    self.warning("no job is waiting for confirmation".to_string());
+                }
+            }
+            WsCommandQueue::Stop => {
+                if self.queue.state != QueueState::Stopped {
+                    self.queue.state = QueueState::Stopped;
+                    self.queue_changed();
+                }
+                if self.queue.running.is_some() {
+                    self.info("the queue stops after the running job".to_string());
+                }
+            }
+        }
+    }
+    /// run the first job of the queue
+    fn start_queued_job(&mut self) {
+        let job = match self.queue.jobs.first() {
+            Some(job) => job.clone(),
+            None => {
+                self.warning("the queue is empty".to_string());
+                return;
+            }
+        };
+        let offset = Location::new(job.offset.x, job.offset.y, job.offset.z);
+        match self.start_program(&job.program_name, job.invert_z, job.scale, offset, None) {
+            Ok(()) => {
+                self.info(format!("start job {}: {}", job.id, job.program_name));
+                self.queue.state = QueueState::Running;
+                self.queue.running = Some(job.id);
+            }
+            Err(reason) => {
+                self.error(reason);
+                self.queue.state = QueueState::Stopped;
+            }
+        }
+        self.queue_changed();
+    }
+    /// the running program ended without cancel. The next job waits for the operator
+    pub fn queue_job_done(&mut self) {
+        let done = match self.queue.running.take() {
+            Some(_) => self.queue.jobs.remove(0),
+            None => return,
+        };
+        match self.queue.jobs.first().map(|job| job.program_name.clone()) {
+            Some(next) if self.queue.state == QueueState::Running => {
+                self.queue.state = QueueState::WaitingForConfirm;
+                self.info(format!(
+                    "job {} is done, confirm to start {}",
+                    done.program_name, next
+                ));
+            }
+            Some(_) => self.info(format!("job {} is done", done.program_name)),
+            None => {
+                self.queue.state = QueueState::Stopped;
+                self.info("all queued jobs are done".to_string());
+            }
+        }
+        self.queue_changed();
+    }
+    /// a canceled job stays in the queue to run it again
+    pub fn queue_stop(&mut self) {
+        if self.queue.state != QueueState::Stopped || self.queue.running.is_some() {
+            self.queue.state = QueueState::Stopped;
+            self.queue.running = None;
+            self.queue_changed();
+        }
+    }
+    fn queue_changed(&self) {
+        if let Err(e) = self.queue.write_to_file(QUEUE_PATH) {
+            self.error(format!("couldn't write the queue: {}", e));
+        }
+        self.ui_data_sender
+            .send(WsMessages::Queue(self.queue.get_msg()))
+            .unwrap();
+    }
+    pub fn send_queue_reply_message(&self, to: Uuid) {
+        self.ui_data_sender
+            .send(WsMessages::Reply {
+                to,
+                msg: WsReplyMessage::Queue(self.queue.get_msg()),
+            })
+            .unwrap();
+    }
+}
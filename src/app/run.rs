@@ -1,38 +1,67 @@
-use super::App;
+use super::{program_path, App};
 
+use super::gamepad::{AxisAction, ButtonAction};
+use super::terminal::TerminalStatus;
 use crate::gnc::{Gnc, NextInstruction};
 use crate::motor::task::CalibrateType;
 use crate::types::{Location, MachineState};
 use crate::ui::{
-    types::{Mode, WsCommandsFrom, WsControllerMessage, WsMessages, WsPositionMessage},
-    ui_main,
+    types::{JobOutcome, Mode, WsCommandsFrom, WsControllerMessage, WsMessages, WsPositionMessage},
+    ui_main, UiState,
 };
 
 use crossbeam_channel::{Receiver, Sender};
-use gilrs::{Axis, Button, Event, EventType};
+use gilrs::{Event, EventType};
 use std::{thread, time::Duration};
 
+/// selectable jog step sizes [mm]
+pub const JOG_STEPS: [f64; 4] = [0.01, 0.1, 1.0, 10.0];
+
 impl App {
     pub fn run(&mut self, data_receiver: Receiver<WsMessages>, cmd_sender: Sender<WsCommandsFrom>) {
         let (update_path, new_progs) = self.start_file_watcher();
 
         // create Http-Server for the UI
-        let pos_msg = WsPositionMessage::new(0.0f64, 0.0f64, 0.0f64);
+        let pos_msg = WsPositionMessage::new(0.0f64, 0.0f64, 0.0f64, self.cnc.get_pos_a());
         let status_msg = self.get_status_msg();
-        let controller_msg = WsControllerMessage::new(&Location::default(), false, false, false);
-        self.pool.spawn_ok(async {
-            ui_main(
-                cmd_sender,
-                data_receiver,
-                pos_msg,
-                status_msg,
-                controller_msg,
-            )
-            .expect("could not start WS-server");
-        });
+        let controller_msg = WsControllerMessage::new(
+            &Location::default(),
+            false,
+            false,
+            false,
+            self.gamepad_connected,
+            false,
+            1.0,
+        );
+        let http = &self.settings.http;
+        if http.enabled {
+            let settings = self.settings.clone();
+            let state = UiState {
+                sender: cmd_sender,
+                receiver: data_receiver,
+                position: pos_msg,
+                status: status_msg,
+                controller: controller_msg,
+                metrics: self.metrics.clone(),
+                simulation: self.simulation.clone(),
+                auth: self.auth.clone(),
+            };
+            self.pool.spawn_ok(async {
+                ui_main(state, settings).expect("could not start WS-server");
+            });
+        } else {
+            // nobody listens, the messages for the UI are dropped
+            thread::spawn(move || for _ in data_receiver.iter() {});
+        }
 
         // initial output
-        println!("rusty cnc controller started\n access the UI with http://localhost:1506");
+        println!("rusty cnc controller started");
+        let url = http.url(self.settings.tls.enabled);
+        match (http.enabled, http.ui) {
+            (true, true) => println!(" access the UI with {}", url),
+            (true, false) => println!(" REST API and WebSocket on {}", url),
+            _ => println!(" the web server is disabled"),
+        }
         if self.settings.show_console_output {
             println!("Found programs in you input_path:");
             for (i, p) in self.available_progs.iter().enumerate() {
@@ -41,19 +70,34 @@ impl App {
         }
 
         let mut last = Location::default();
+        let mut last_a = None;
         'running: loop {
             thread::sleep(Duration::new(0, 5_000_000));
+            self.update_metrics();
+            self.receive_analyses();
 
             // display position, or send it to the ws client
             self.display_counter += 1;
             if self.display_counter >= self.settings.console_pos_update_reduce {
                 let pos = self.cnc.get_pos();
-                if last != pos {
-                    self.send_pos_msg(&pos);
-                    if self.settings.show_console_output {
+                let a = self.cnc.get_pos_a();
+                if let Some(terminal) = self.terminal.as_ref() {
+                    terminal.render(TerminalStatus {
+                        pos: &pos,
+                        a,
+                        state: self.cnc.get_state(),
+                        mode: &self.current_mode,
+                        slow: self.slow_control,
+                        selected_program: self.selected_program.as_deref(),
+                    });
+                }
+                if last != pos || last_a != a {
+                    self.send_pos_msg(&pos, a);
+                    if self.settings.show_console_output && self.terminal.is_none() {
                         println!("  {{ x: {}, y: {}, z: {} }},", pos.x, pos.y, pos.z);
                     }
                     last = pos;
+                    last_a = a;
                 }
                 self.display_counter = 0;
             }
@@ -74,6 +118,7 @@ impl App {
             }
 
             self.handle_network_commands(&update_path);
+            self.handle_grbl_requests();
         }
     }
 
@@ -96,6 +141,37 @@ impl App {
             self.send_controller_msg();
         }
     }
+    /**
+     * next gamepad event. Connection changes are handled here and not returned.
+     *
+     * @return None if no event is pending or no gamepad support is available
+     */
+    fn next_gamepad_event(&mut self) -> Option<EventType> {
+        loop {
+            let Event { event, .. } = self.gilrs.as_mut()?.next_event()?;
+            match event {
+                EventType::Connected => {
+                    if !self.gamepad_connected {
+                        self.gamepad_connected = true;
+                        self.info("gamepad connected".to_string());
+                        self.send_controller_msg();
+                    }
+                }
+                EventType::Disconnected => {
+                    let connected = self
+                        .gilrs
+                        .as_ref()
+                        .is_some_and(|g| g.gamepads().any(|(_, pad)| pad.is_connected()));
+                    if self.gamepad_connected && !connected {
+                        self.gamepad_connected = false;
+                        self.warning("gamepad disconnected".to_string());
+                        self.send_controller_msg();
+                    }
+                }
+                event => return Some(event),
+            }
+        }
+    }
     pub fn manual_mode(&mut self) -> bool {
         // controller just every n-th tick
         self.input_reduce += 1;
@@ -106,144 +182,254 @@ impl App {
 
         let mut control = self.last_control.clone();
         // map GamePad events to update the manual program or start a program
-        while let Some(Event { event, .. }) = self.gilrs.next_event() {
+        while let Some(event) = self.next_gamepad_event() {
             match event {
-                EventType::ButtonReleased(Button::Select, _) => {
-                    // remove later to avoid killing the machine by mistake
-                    return false;
-                }
-                EventType::ButtonReleased(Button::Start, _)
-                | EventType::ButtonReleased(Button::Mode, _) => {
-                    if let Some(sel_prog) = self.selected_program.to_owned() {
-                        self.start_program(&sel_prog, false, 1.0);
+                EventType::ButtonPressed(button, _) => {
+                    let action = if self.jog {
+                        self.settings.gamepad.jog_button_action(button)
                     } else {
-                        self.error("No Program selected".to_string());
+                        self.settings.gamepad.button_action(button)
+                    };
+                    if let Some(action) = action {
+                        if !self.manual_button_action(action) {
+                            return false;
+                        }
                     }
                 }
-                EventType::AxisChanged(Axis::LeftStickX, value, _) => {
-                    if !self.freeze_x && value > 0.15 {
-                        control.x = (value as f64 - 0.15) / 8.5 * -10.0;
-                    } else if !self.freeze_x && value < -0.15 {
-                        control.x = (value as f64 + 0.15) / 8.5 * -10.0;
-                    } else {
-                        control.x = 0.0;
+                EventType::AxisChanged(axis, value, _) => {
+                    for binding in self.settings.gamepad.axis_bindings(axis) {
+                        let speed = binding.apply(value);
+                        match binding.action {
+                            AxisAction::MoveX => {
+                                control.x = if self.freeze_x { 0.0 } else { speed }
+                            }
+                            AxisAction::MoveY => {
+                                control.y = if self.freeze_y { 0.0 } else { speed }
+                            }
+                            AxisAction::MoveZ => control.z = speed,
+                        }
                     }
                 }
-                EventType::AxisChanged(Axis::LeftStickY, value, _) => {
-                    if !self.freeze_y && value > 0.15 {
-                        control.y = (value as f64 - 0.15) / 8.5 * 10.0;
-                    } else if !self.freeze_y && value < -0.15 {
-                        control.y = (value as f64 + 0.15) / 8.5 * 10.0;
-                    } else {
-                        control.y = 0.0;
+                _ => {}
+            }
+        }
+        self.handwheel_input();
+
+        // stop a stick move of a gamepad that was unplugged (or ran out of battery)
+        // the sticks are not used in jog mode
+        if !self.gamepad_connected || self.jog {
+            control = Location::default();
+        }
+
+        // keyboard jog of the terminal ui
+        if let Some(input) = self.terminal.as_mut().map(|t| t.poll()) {
+            if input.quit {
+                return false;
+            }
+            if input.toggle_slow {
+                self.slow_control = !self.slow_control;
+                self.send_controller_msg();
+            }
+            if let Some(terminal_control) = input.control {
+                control = terminal_control;
+            }
+        }
+        // the virtual joystick overrides the gamepad, a missing update stops the move (deadman)
+        if let Some(ui_control) = self.ui_control.clone() {
+            if self.ui_control_received.elapsed().as_secs_f64() > self.settings.jog_timeout {
+                self.ui_control = None;
+                control = Location::default();
+                if ui_control != Location::default() {
+                    self.warning("virtual joystick timed out, stop move".to_string());
+                }
+            } else {
+                control = Location {
+                    x: if self.freeze_x { 0.0 } else { ui_control.x },
+                    y: if self.freeze_y { 0.0 } else { ui_control.y },
+                    z: ui_control.z,
+                };
+            }
+        }
+
+        self.apply_control(control);
+
+        true
+    }
+    /**
+     * run a mapped gamepad button action in manual mode
+     *
+     * @return false to terminate the controller
+     */
+    fn manual_button_action(&mut self, action: ButtonAction) -> bool {
+        match action {
+            ButtonAction::Quit => return false,
+            ButtonAction::StartProgram => {
+                if let Some(sel_prog) = self.selected_program.to_owned() {
+                    if let Err(reason) =
+                        self.start_program(&sel_prog, false, 1.0, Location::default(), None)
+                    {
+                        self.error(reason);
                     }
+                } else {
+                    self.error("No Program selected".to_string());
                 }
-                EventType::AxisChanged(Axis::RightStickY, value, _) => {
-                    if value > 0.15 {
-                        control.z = (value as f64 - 0.15) / 8.5 * 10.0;
-                    } else if value < -0.15 {
-                        control.z = (value as f64 + 0.15) / 8.5 * 10.0;
+            }
+            ButtonAction::Cancel => (),
+            ButtonAction::PreviousProgram | ButtonAction::NextProgram => {
+                if action == ButtonAction::PreviousProgram {
+                    if self.program_select_cursor <= 0 {
+                        self.program_select_cursor = self.available_progs.len() as i32 - 1;
                     } else {
-                        control.z = 0.0;
+                        self.program_select_cursor -= 1;
                     }
-                }
-                // add cross to select a program
-                EventType::ButtonPressed(dir @ Button::DPadDown, _)
-                | EventType::ButtonPressed(dir @ Button::DPadUp, _) => {
-                    match dir {
-                        Button::DPadUp => {
-                            if self.program_select_cursor <= 0 {
-                                self.program_select_cursor = self.available_progs.len() as i32 - 1;
-                            } else {
-                                self.program_select_cursor -= 1;
-                            }
-                        }
-                        Button::DPadDown => {
-                            self.program_select_cursor += 1;
+                } else {
+                    self.program_select_cursor += 1;
 
-                            if self.program_select_cursor >= self.available_progs.len() as i32 {
-                                self.program_select_cursor = 0;
-                            }
-                        }
-                        _ => (),
-                    };
-                    if self.settings.show_console_output {
-                        for (i, p) in self.available_progs.iter().enumerate() {
-                            println!("{}: {}", i, p);
-                        }
-                        println!(
-                            "select {} {}",
-                            self.program_select_cursor,
-                            self.available_progs
-                                .get(
-                                    self.program_select_cursor
-                                        .min(self.available_progs.len() as i32)
-                                        .max(0) as usize
-                                )
-                                .unwrap()
-                        );
+                    if self.program_select_cursor >= self.available_progs.len() as i32 {
+                        self.program_select_cursor = 0;
                     }
                 }
-                EventType::ButtonPressed(Button::South, _) => {
-                    let selected = self
-                        .available_progs
-                        .get(
+                if self.settings.show_console_output {
+                    for (i, p) in self.available_progs.iter().enumerate() {
+                        println!("{}: {}", i, p);
+                    }
+                    println!(
+                        "select {} {:?}",
+                        self.program_select_cursor,
+                        self.available_progs.get(
                             self.program_select_cursor
                                 .min(self.available_progs.len() as i32)
-                                .max(0) as usize,
+                                .max(0) as usize
                         )
-                        .map(|p| p.to_owned());
-                    self.set_selected_program(selected);
-                    self.info(format!("select {:?}", self.selected_program));
-                }
-                EventType::ButtonPressed(Button::North, _) => {
-                    let pos = self.cnc.get_pos();
-                    if pos.x == 0.0 && pos.y == 0.0 {
-                        self.info("reset all (x, y, z)".to_string());
-                        self.cnc.reset();
-                    } else {
-                        self.info("reset only plane move (x, y) -- Reset again without moving to reset the z axis as well".to_string());
-                        self.cnc.set_pos(Location::new(0.0, 0.0, pos.z));
-                    }
-                }
-                EventType::ButtonPressed(Button::East, _) => {
-                    self.info("calibrate".to_string());
-                    self.cnc.calibrate(
-                        CalibrateType::None,
-                        CalibrateType::None,
-                        CalibrateType::ContactPin,
                     );
-                    self.set_current_mode(Mode::Calibrate);
-                    self.in_opp = true;
-                    thread::sleep(Duration::new(0, 10_000_000));
                 }
-                _ => {}
+            }
+            ButtonAction::SelectProgram => {
+                let selected = self
+                    .available_progs
+                    .get(
+                        self.program_select_cursor
+                            .min(self.available_progs.len() as i32)
+                            .max(0) as usize,
+                    )
+                    .map(|p| p.to_owned());
+                self.set_selected_program(selected);
+                self.info(format!("select {:?}", self.selected_program));
+            }
+            ButtonAction::ResetPosition => {
+                let pos = self.cnc.get_pos();
+                if pos.x == 0.0 && pos.y == 0.0 {
+                    self.info("reset all (x, y, z)".to_string());
+                    self.cnc.reset();
+                } else {
+                    self.info("reset only plane move (x, y) -- Reset again without moving to reset the z axis as well".to_string());
+                    self.cnc.set_pos(Location::new(0.0, 0.0, pos.z));
+                }
+            }
+            ButtonAction::Calibrate => {
+                self.info("calibrate".to_string());
+                self.calibrate(
+                    CalibrateType::None,
+                    CalibrateType::None,
+                    CalibrateType::ContactPin,
+                );
+            }
+            ButtonAction::ToggleSlow => {
+                self.slow_control = !self.slow_control;
+                self.send_controller_msg();
+            }
+            ButtonAction::ToggleJog => self.set_jog(!self.jog),
+            ButtonAction::JogXPlus => self.jog_move(1.0, 0.0, 0.0),
+            ButtonAction::JogXMinus => self.jog_move(-1.0, 0.0, 0.0),
+            ButtonAction::JogYPlus => self.jog_move(0.0, 1.0, 0.0),
+            ButtonAction::JogYMinus => self.jog_move(0.0, -1.0, 0.0),
+            ButtonAction::JogZPlus => self.jog_move(0.0, 0.0, 1.0),
+            ButtonAction::JogZMinus => self.jog_move(0.0, 0.0, -1.0),
+            ButtonAction::JogStepUp | ButtonAction::JogStepDown => {
+                let i = JOG_STEPS
+                    .iter()
+                    .position(|s| *s >= self.jog_step)
+                    .unwrap_or(JOG_STEPS.len() - 1);
+                let i = if action == ButtonAction::JogStepUp {
+                    (i + 1).min(JOG_STEPS.len() - 1)
+                } else {
+                    i.saturating_sub(1)
+                };
+                self.jog_step = JOG_STEPS[i];
+                self.info(format!("jog step {} mm", self.jog_step));
+                self.send_controller_msg();
             }
         }
-
-        self.apply_control(control);
-
         true
     }
-    pub fn program_mode(&mut self) -> bool {
-        while let Some(Event { event, .. }) = self.gilrs.next_event() {
-            if let EventType::ButtonReleased(Button::Select, _) = event {
-                self.info("Cancel current job".to_string());
-                self.set_current_mode(Mode::Manual);
-                if self.cnc.cancel_task().is_err() {
-                    self.error("cancel did not work".to_string());
-                    panic!("cancel did not work!");
-                };
+    /// turn the handwheel encoder counts into exact relative moves
+    fn handwheel_input(&mut self) {
+        let (delta, delta_a) = match self.handwheel.as_mut() {
+            Some(handwheel) => self.settings.handwheel.collect(handwheel.as_mut()),
+            None => return,
+        };
+        // the motor controller adds the move to the target of the running step,
+        // so the axis follows every detent of the wheel
+        if delta != Location::default() || delta_a != 0.0 {
+            self.cnc.manual_step(
+                delta.x,
+                delta.y,
+                delta.z,
+                delta_a,
+                self.settings.handwheel.speed,
+            );
+        }
+    }
+    /// move one jog step in the given direction
+    fn jog_move(&mut self, x: f64, y: f64, z: f64) {
+        let step = self.jog_step;
+        self.cnc.manual_step(
+            x * step,
+            y * step,
+            z * step,
+            0.0,
+            self.settings.default_speed,
+        );
+    }
+    /// true if the button is mapped to cancel the current program or calibration
+    fn is_cancel_button(&self, event: &EventType) -> bool {
+        match event {
+            EventType::ButtonPressed(button, _) => {
+                self.settings.gamepad.button_action(*button) == Some(ButtonAction::Cancel)
+            }
+            _ => false,
+        }
+    }
+    /// cancel button of the gamepad, or space, q or Ctrl-C in the terminal ui
+    fn cancel_requested(&mut self) -> bool {
+        let mut cancel = self.terminal.as_mut().is_some_and(|t| t.poll_cancel());
+        while let Some(event) = self.next_gamepad_event() {
+            if self.is_cancel_button(&event) {
+                cancel = true;
             }
         }
+        cancel
+    }
+    pub fn program_mode(&mut self) -> bool {
+        if self.cancel_requested() {
+            self.info("Cancel current job".to_string());
+            self.end_job(JobOutcome::Cancelled);
+            self.set_current_mode(Mode::Manual);
+            if self.cnc.cancel_task().is_err() {
+                self.error("cancel did not work".to_string());
+                panic!("cancel did not work!");
+            };
+        }
         if let Some(prog) = self.prog.as_mut() {
-            for next_instruction in prog {
+            while let Some(next_instruction) = prog.next() {
                 match next_instruction {
                     NextInstruction::Movement(next_movement) => {
                         self.cnc.query_g_task(next_movement);
+                        self.prog_lines.push(prog.current_line());
                     }
                     NextInstruction::Miscellaneous(next_movement) => {
                         self.cnc.query_m_task(next_movement);
+                        self.prog_lines.push(prog.current_line());
                     }
                     NextInstruction::NotSupported(err) => {
                         println!("NotSupported {:?}", err);
@@ -257,33 +443,37 @@ impl App {
                 };
             }
             thread::sleep(Duration::new(0, 100_000_000));
-
-            if self.cnc.get_state() == MachineState::Idle {
-                self.set_current_mode(Mode::Manual);
-                self.in_opp = false;
+        }
+        // a streamed program (GRBL) has no prog, it ends with the queue after the end of the stream
+        if self.cnc.get_state() == MachineState::Idle && !self.grbl_stream_open() {
+            if self.current_mode == Mode::Program {
+                self.end_job(JobOutcome::Completed);
             }
+            self.set_current_mode(Mode::Manual);
+            self.in_opp = false;
         }
 
+        self.history_track_line();
         self.set_prog_state(self.cnc.get_steps_todo(), self.cnc.get_steps_done());
 
         true
     }
     pub fn calibrate_mode(&mut self) -> bool {
-        while let Some(Event { event, .. }) = self.gilrs.next_event() {
-            if let EventType::ButtonReleased(Button::Select, _) = event {
-                self.set_current_mode(Mode::Manual);
-                if self.cnc.cancel_task().is_err() {
-                    self.error("cancel did not work".to_string());
-                    panic!("cancel did not work!");
-                };
-            }
+        if self.cancel_requested() {
+            self.set_current_mode(Mode::Manual);
+            if self.cnc.cancel_task().is_err() {
+                self.error("cancel did not work".to_string());
+                panic!("cancel did not work!");
+            };
         }
 
         if self.cnc.get_state() == MachineState::Idle {
             let calibrate_hight = Location {
                 x: 0.0f64,
                 y: 0.0f64,
-                z: if self.settings.invert_z {
+                z: if !self.calibrate_z {
+                    self.cnc.get_pos().z
+                } else if self.settings.invert_z {
                     -20.0f64
                 } else {
                     20.0f64
@@ -297,29 +487,64 @@ impl App {
 
         true
     }
-    pub fn start_program(&mut self, program_name: &str, invert_z: bool, scale: f64) -> bool {
+    pub fn calibrate(&mut self, x: CalibrateType, y: CalibrateType, z: CalibrateType) {
+        self.calibrate_z = !matches!(z, CalibrateType::None);
+        self.cnc.calibrate(x, y, z);
+        self.set_current_mode(Mode::Calibrate);
+        self.in_opp = true;
+        thread::sleep(Duration::new(0, 10_000_000));
+    }
+    /// drive x and y into the min end switches. Squares a gantry with slaved motors
+    pub fn home(&mut self) {
+        self.info("home x and y".to_string());
+        self.calibrate(CalibrateType::Min, CalibrateType::Min, CalibrateType::None);
+    }
+    /**
+     * load the program of a client or the selected program and switch to program mode
+     *
+     * @return the reason if the program is rejected or can not be loaded
+     */
+    pub fn start_program(
+        &mut self,
+        program_name: &str,
+        invert_z: bool,
+        scale: f64,
+        offset: Location<f64>,
+        start_line: Option<usize>,
+    ) -> Result<(), String> {
         if !self.calibrated {
             self.warning("start program without calibration".to_string());
         }
+        let path = program_path(&self.settings.input_dir, program_name)?;
         self.set_selected_program(Some(program_name.to_owned()));
-        if let Ok(load_prog) = Gnc::new(
-            &program_name,
+        let mut load_prog = Gnc::new(
+            &path,
             5.0,
             50.0,
             scale,
             self.cnc.get_pos(),
+            self.cnc.get_pos_a().unwrap_or(0.0),
             invert_z,
-        ) {
-            println!("commands found {:?}", load_prog.len());
-            self.prog = Some(load_prog);
-            self.set_current_mode(Mode::Program);
-            true
-        } else {
-            self.error("program is not able to load".to_string());
-            false
+            self.settings.arc_tolerance,
+        )
+        .map_err(|e| format!("program {} is not able to load: {}", program_name, e))?;
+        if load_prog.uses_inch() {
+            return Err(format!("program {} uses inch units (G20)", program_name));
+        }
+        println!("commands found {:?}", load_prog.len());
+        load_prog.set_offset(offset.clone());
+        if let Some(line) = start_line.filter(|line| *line > 1) {
+            load_prog.start_at_line(line, self.settings.resume_safe_z);
         }
+        self.prog = Some(load_prog);
+        self.prog_lines.clear();
+        self.history_start(program_name, scale, invert_z, &offset);
+        self.set_current_mode(Mode::Program);
+        Ok(())
     }
     pub fn cancel_program(&mut self) {
+        self.grbl_close_stream();
+        self.end_job(JobOutcome::Cancelled);
         self.set_selected_program(None);
         self.set_current_mode(Mode::Manual);
         if self.cnc.cancel_task().is_err() {
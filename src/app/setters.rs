@@ -1,6 +1,8 @@
 #![allow(clippy::option_map_unit_fn)]
 use super::App;
 
+use super::gamepad::GamepadMapping;
+use crate::types::Location;
 use crate::ui::types::{
     Mode, WsCommandSettingsSetRuntimeSettings, WsCommandSettingsSetSystemSettings,
 };
@@ -18,6 +20,7 @@ impl App {
         }
     }
     pub fn set_current_mode(&mut self, mode: Mode) {
+        self.track_job(&mode);
         self.current_mode = mode;
         self.send_status_msg();
     }
@@ -25,6 +28,14 @@ impl App {
         self.available_progs = available_progs;
         self.send_available_program_msg();
     }
+    pub fn set_jog(&mut self, jog: bool) {
+        if self.jog != jog {
+            self.jog = jog;
+            // stop a running stick move before switching to exact steps
+            self.apply_control(Location::default());
+            self.send_controller_msg();
+        }
+    }
     pub fn set_selected_program(&mut self, selected_program: Option<String>) {
         self.selected_program = selected_program;
         self.send_status_msg();
@@ -56,6 +67,13 @@ impl App {
         settings
             .external_input_enabled
             .map(|v| self.settings.external_input_enabled = v);
+        settings
+            .arc_tolerance
+            .map(|v| self.settings.arc_tolerance = v);
+        settings.jog_timeout.map(|v| self.settings.jog_timeout = v);
+        settings
+            .max_upload_size
+            .map(|v| self.settings.max_upload_size = v);
 
         self.settings.write_to_file(SETTINGS_PATH)
     }
@@ -68,9 +86,21 @@ impl App {
         self.settings.motor_x = settings.motor_x;
         self.settings.motor_y = settings.motor_y;
         self.settings.motor_z = settings.motor_z;
+        self.settings.motor_x_slave = settings.motor_x_slave;
+        self.settings.motor_y_slave = settings.motor_y_slave;
+        self.settings.motor_a = settings.motor_a;
         self.settings.calibrate_z_gpio = settings.calibrate_z_gpio;
         self.settings.on_off_gpio = settings.on_off_gpio;
         self.settings.switch_on_off_delay = settings.switch_on_off_delay;
+        self.settings.pulse_backend = settings.pulse_backend;
+        self.settings.pulse_latency = settings.pulse_latency;
+
+        self.settings.write_to_file(SETTINGS_PATH)
+    }
+
+    /// the mapping is used from the next gamepad event on
+    pub fn set_gamepad_settings(&mut self, mapping: GamepadMapping) -> Result<(), String> {
+        self.settings.gamepad = mapping;
 
         self.settings.write_to_file(SETTINGS_PATH)
     }
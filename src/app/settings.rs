@@ -1,4 +1,11 @@
-use crate::motor::MotorSettings;
+use super::gamepad::GamepadMapping;
+use crate::grbl::GrblSettings;
+use crate::io::handwheel::HandwheelSettings;
+use crate::motor::{MotorSettings, PulseBackendType};
+use crate::mqtt::MqttSettings;
+use crate::ui::auth::AuthSettings;
+use crate::ui::tls::TlsSettings;
+use crate::ui::HttpSettings;
 use serde::{Deserialize, Serialize};
 use std::{env, fs};
 
@@ -9,6 +16,14 @@ pub struct Settings {
     pub motor_x: MotorSettings,
     pub motor_y: MotorSettings,
     pub motor_z: MotorSettings,
+    // second motor driving the same axis (e.g. gantry). Only the GPIOs and direction are used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motor_x_slave: Option<MotorSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motor_y_slave: Option<MotorSettings>,
+    // optional rotary axis. The step size is in degree per step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motor_a: Option<MotorSettings>,
     pub calibrate_z_gpio: Option<u8>,
     pub on_off_gpio: Option<u8>,
     pub switch_on_off_delay: f64,
@@ -19,9 +34,65 @@ pub struct Settings {
     pub scale: f64,
     pub invert_z: bool,
     pub show_console_output: bool,
+    // keyboard jog and status in the terminal (or start with the argument `tui`)
+    #[serde(default)]
+    pub terminal_ui: bool,
     pub console_pos_update_reduce: u32,
     #[serde(default)]
     pub external_input_enabled: bool,
+    #[serde(default = "default_arc_tolerance")]
+    pub arc_tolerance: f64,
+    // [sec] a virtual joystick move stops if the UI sends no update within this time
+    #[serde(default = "default_jog_timeout")]
+    pub jog_timeout: f64,
+    // [MB] largest program accepted by the upload
+    #[serde(default = "default_max_upload_size")]
+    pub max_upload_size: u64,
+    // [mm] z above the work piece to approach the start of a resumed program
+    #[serde(default = "default_resume_safe_z")]
+    pub resume_safe_z: f64,
+    #[serde(default)]
+    pub pulse_backend: PulseBackendType,
+    // [sec] time a buffered pulse backend gets to play out a step
+    #[serde(default = "default_pulse_latency")]
+    pub pulse_latency: f64,
+    // gamepad buttons and axis bound to actions
+    #[serde(default)]
+    pub gamepad: GamepadMapping,
+    // USB handwheel pendant
+    #[serde(default)]
+    pub handwheel: HandwheelSettings,
+    // GRBL 1.1 emulation for senders like UGS, bCNC or CNCjs
+    #[serde(default)]
+    pub grbl: GrblSettings,
+    // login and roles of the UI and the REST API
+    #[serde(default)]
+    pub auth: AuthSettings,
+    // https / wss for the UI, the WebSocket and the REST API
+    #[serde(default)]
+    pub tls: TlsSettings,
+    // address, port and static files of the web server, read on start
+    #[serde(default)]
+    pub http: HttpSettings,
+    // status, position and infos for dashboards (e.g. Home Assistant)
+    #[serde(default)]
+    pub mqtt: MqttSettings,
+}
+
+fn default_arc_tolerance() -> f64 {
+    0.01f64
+}
+fn default_jog_timeout() -> f64 {
+    0.5f64
+}
+fn default_max_upload_size() -> u64 {
+    50u64
+}
+fn default_pulse_latency() -> f64 {
+    0.02f64
+}
+fn default_resume_safe_z() -> f64 {
+    5.0f64
 }
 
 impl Default for Settings {
@@ -41,6 +112,7 @@ impl Default for Settings {
                 acceleration_damping: 0.0009f64,
                 free_step_speed: 20.0f64,
                 acceleration_time_scale: 2.0f64,
+                backlash: 0.0f64,
             },
             motor_y: MotorSettings {
                 max_step_speed: 200,
@@ -55,6 +127,7 @@ impl Default for Settings {
                 acceleration_damping: 0.0009f64,
                 free_step_speed: 20.0f64,
                 acceleration_time_scale: 2.0f64,
+                backlash: 0.0f64,
             },
             motor_z: MotorSettings {
                 max_step_speed: 200,
@@ -69,7 +142,11 @@ impl Default for Settings {
                 acceleration_damping: 0.0009f64,
                 free_step_speed: 20.0f64,
                 acceleration_time_scale: 2.0f64,
+                backlash: 0.0f64,
             },
+            motor_x_slave: None,
+            motor_y_slave: None,
+            motor_a: None,
             calibrate_z_gpio: Some(16),
             on_off_gpio: Some(13),
             switch_on_off_delay: 3.5f64,
@@ -80,8 +157,22 @@ impl Default for Settings {
             scale: 1.0f64,
             invert_z: false,
             show_console_output: false,
+            terminal_ui: false,
             console_pos_update_reduce: 50u32,
             external_input_enabled: false,
+            arc_tolerance: default_arc_tolerance(),
+            jog_timeout: default_jog_timeout(),
+            max_upload_size: default_max_upload_size(),
+            resume_safe_z: default_resume_safe_z(),
+            pulse_backend: PulseBackendType::default(),
+            pulse_latency: default_pulse_latency(),
+            gamepad: GamepadMapping::default(),
+            handwheel: HandwheelSettings::default(),
+            grbl: GrblSettings::default(),
+            auth: AuthSettings::default(),
+            tls: TlsSettings::default(),
+            http: HttpSettings::default(),
+            mqtt: MqttSettings::default(),
         }
     }
 }
@@ -103,6 +194,9 @@ impl Settings {
             if arg == *"dev_mode" {
                 settings.dev_mode = true;
             }
+            if arg == *"tui" {
+                settings.terminal_ui = true;
+            }
         }
         settings
     }
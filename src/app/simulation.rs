@@ -0,0 +1,200 @@
+use super::{settings::Settings, App};
+
+use crate::gnc::{Gnc, NextInstruction};
+use crate::motor::{motor_controller::MotorController, Clock, MotorSettings, Recorder, Recording};
+use crate::types::Location;
+use crate::ui::types::{InfoLvl, WsInfoMessage, WsMessages, WsSimulationMessage};
+use serde::Serialize;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// step timeline of a dry run
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    pub program_name: String,
+    /// program line of every task
+    pub lines: Vec<usize>,
+    #[serde(flatten)]
+    pub recording: Recording,
+}
+
+impl SimulationResult {
+    pub fn get_msg(&self) -> WsSimulationMessage {
+        WsSimulationMessage {
+            program_name: self.program_name.clone(),
+            duration: self.recording.duration,
+            steps: self.recording.steps,
+            min: self.recording.min,
+            max: self.recording.max,
+        }
+    }
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("t,event,task,line,x,y,z,a\n");
+        for e in self.recording.events.iter() {
+            let line = self.lines.get(e.task as usize).copied().unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "{:.6},{},{},{},{},{},{},{}",
+                e.t, e.event, e.task, line, e.x, e.y, e.z, e.a
+            );
+        }
+        out
+    }
+}
+
+/// the last dry run, shared with the REST API for the export
+#[derive(Debug, Default)]
+pub struct Simulation {
+    running: AtomicBool,
+    last: Mutex<Option<Arc<SimulationResult>>>,
+}
+
+impl Simulation {
+    pub fn get_last(&self) -> Option<Arc<SimulationResult>> {
+        self.last.lock().unwrap().clone()
+    }
+}
+
+/**
+ * run a program on a motor controller with recording drivers and a virtual clock.
+ * The machine starts at the origin
+ *
+ * @return the timeline or why the program could not be loaded
+ */
+fn simulate(
+    settings: &Settings,
+    program_name: &str,
+    path: &Path,
+    invert_z: bool,
+    scale: f64,
+) -> Result<SimulationResult, String> {
+    let mut prog = Gnc::new(
+        path,
+        5.0,
+        50.0,
+        scale,
+        Location::default(),
+        0.0,
+        invert_z,
+        settings.arc_tolerance,
+    )
+    .map_err(|e| format!("program is not able to load: {}", e))?;
+
+    let clock = Clock::new_virtual();
+    let recorder = Arc::new(Recorder::new(clock.clone()));
+    let motor = |axis: usize, name: &str, m: &MotorSettings| {
+        let driver = Box::new(recorder.driver(axis, m.step_size));
+        App::create_motor(name, m, driver).with_clock(clock.clone())
+    };
+    let (_, external_input_receiver) = mpsc::channel();
+    let (external_input_request_sender, _) = mpsc::channel();
+    let mut cnc = MotorController::new(
+        None,
+        settings.switch_on_off_delay,
+        motor(0, "x", &settings.motor_x),
+        motor(1, "y", &settings.motor_y),
+        motor(2, "z", &settings.motor_z),
+        settings.motor_a.as_ref().map(|m| motor(3, "a", m)),
+        None,
+        false,
+        external_input_receiver,
+        external_input_request_sender,
+    );
+    cnc.attach_recorder(&recorder);
+
+    // queue the whole program before the first task starts
+    cnc.pause(true);
+    let mut lines = Vec::new();
+    while let Some(next_instruction) = prog.next() {
+        match next_instruction {
+            NextInstruction::Movement(next_movement) => cnc.query_g_task(next_movement),
+            NextInstruction::Miscellaneous(next_miscellaneous) => {
+                cnc.query_m_task(next_miscellaneous)
+            }
+            _ => continue,
+        }
+        lines.push(prog.current_line());
+    }
+    cnc.pause(false);
+
+    // the virtual clock stands still when the controller is idle
+    while !cnc.is_idle() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    clock.stop();
+
+    Ok(SimulationResult {
+        program_name: program_name.to_owned(),
+        lines,
+        recording: recorder.take_recording(),
+    })
+}
+
+impl App {
+    /// dry run in the background. The result is sent to all clients
+    pub fn simulate_program(
+        &mut self,
+        program_name: String,
+        path: PathBuf,
+        invert_z: bool,
+        scale: f64,
+    ) {
+        if self.simulation.running.swap(true, Relaxed) {
+            self.warning("a simulation is already running".to_string());
+            return;
+        }
+        self.info(format!("simulate {}", program_name));
+        let settings = self.settings.clone();
+        let simulation = self.simulation.clone();
+        let sender = self.ui_data_sender.clone();
+        thread::spawn(move || {
+            let msg = match simulate(&settings, &program_name, &path, invert_z, scale) {
+                Ok(result) => {
+                    let msg = WsMessages::Simulation(result.get_msg());
+                    *simulation.last.lock().unwrap() = Some(Arc::new(result));
+                    msg
+                }
+                Err(e) => WsMessages::Info(WsInfoMessage::new(InfoLvl::Error, e)),
+            };
+            simulation.running.store(false, Relaxed);
+            sender.send(msg).unwrap();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn simulate_runs_the_program_on_the_virtual_clock() {
+        let path = std::env::temp_dir().join(format!("simulate-{}.nc", std::process::id()));
+        // 1 mm with 0.5 mm/s: 250 steps of 0.004 mm in about 2 s
+        fs::write(&path, "G1 X1 F30\n").unwrap();
+        let result = simulate(&Settings::default(), "test.nc", &path, false, 1.0);
+        fs::remove_file(&path).unwrap();
+
+        let recording = result.unwrap().recording;
+        assert_eq!(recording.steps, 250);
+        assert_eq!(recording.step_interval, 1);
+        let last_step = recording.events.iter().rev().find(|e| e.is_step()).unwrap();
+        assert!((last_step.x - 1.0).abs() < 1e-9);
+        assert_eq!((last_step.y, last_step.z), (0.0, 0.0));
+        assert!((recording.max[0] - 1.0).abs() < 1e-9);
+        assert!(
+            recording.duration > 1.9 && recording.duration < 3.0,
+            "duration {}",
+            recording.duration
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::types::{Location, MachineState};
+use crate::ui::types::Mode;
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{read, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+};
+use std::{
+    io::{stdout, Write},
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// terminals send no key release. A move stops if the key repeat stops for this time
+const KEY_HOLD: Duration = Duration::from_millis(600);
+
+enum TerminalKey {
+    /// direction and time of the key press
+    Move(Location<f64>, Instant),
+    Stop,
+    ToggleSlow,
+    Quit,
+}
+
+/// input collected since the last poll
+#[derive(Default)]
+pub struct TerminalInput {
+    /// manual move while a key is held, zero once the keys are released
+    pub control: Option<Location<f64>>,
+    pub toggle_slow: bool,
+    pub quit: bool,
+}
+
+/// snapshot of the machine rendered in the terminal
+pub struct TerminalStatus<'a> {
+    pub pos: &'a Location<f64>,
+    pub a: Option<f64>,
+    pub state: MachineState,
+    pub mode: &'a Mode,
+    pub slow: bool,
+    pub selected_program: Option<&'a str>,
+}
+
+/**
+ * Keyboard jog and live status for a SSH session.
+ *
+ * arrows move x / y, PgUp / PgDn move z, space stops, s toggles slow moves, q quits.
+ * While a program or calibration runs space, q and Ctrl-C cancel it.
+ */
+pub struct TerminalUi {
+    receiver: Receiver<TerminalKey>,
+    control: Location<f64>,
+    last_key: Instant,
+}
+
+impl TerminalUi {
+    /// None if stdin is no terminal
+    pub fn start() -> Option<TerminalUi> {
+        if let Err(e) = enable_raw_mode() {
+            println!("terminal ui is not available: {:?}", e);
+            return None;
+        }
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let key_move = |x, y, z| TerminalKey::Move(Location::new(x, y, z), Instant::now());
+            while let Ok(event) = read() {
+                let key = match event {
+                    Event::Key(KeyEvent { code, modifiers }) => match code {
+                        KeyCode::Left => key_move(-1.0, 0.0, 0.0),
+                        KeyCode::Right => key_move(1.0, 0.0, 0.0),
+                        KeyCode::Up => key_move(0.0, 1.0, 0.0),
+                        KeyCode::Down => key_move(0.0, -1.0, 0.0),
+                        KeyCode::PageUp => key_move(0.0, 0.0, 1.0),
+                        KeyCode::PageDown => key_move(0.0, 0.0, -1.0),
+                        KeyCode::Char(' ') => TerminalKey::Stop,
+                        KeyCode::Char('s') => TerminalKey::ToggleSlow,
+                        KeyCode::Char('q') => TerminalKey::Quit,
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                            TerminalKey::Quit
+                        }
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                if sender.send(key).is_err() {
+                    break;
+                }
+            }
+        });
+        Some(TerminalUi {
+            receiver,
+            control: Location::default(),
+            last_key: Instant::now(),
+        })
+    }
+    pub fn poll(&mut self) -> TerminalInput {
+        let mut input = TerminalInput::default();
+        while let Ok(key) = self.receiver.try_recv() {
+            match key {
+                // keys pressed while no one polled (e.g. during a program) are outdated
+                TerminalKey::Move(control, at) if at.elapsed() < KEY_HOLD => {
+                    self.control = control.clone();
+                    self.last_key = at;
+                    input.control = Some(control);
+                }
+                TerminalKey::Move(..) => (),
+                TerminalKey::Stop => {
+                    self.control = Location::default();
+                    input.control = Some(Location::default());
+                }
+                TerminalKey::ToggleSlow => input.toggle_slow = true,
+                TerminalKey::Quit => input.quit = true,
+            }
+        }
+        if self.control != Location::default() {
+            if self.last_key.elapsed() > KEY_HOLD {
+                self.control = Location::default();
+            }
+            input.control = Some(self.control.clone());
+        }
+        input
+    }
+    /**
+     * read the keys while a program or calibration runs. Moves are dropped
+     *
+     * @return true if space, q or Ctrl-C was pressed to cancel
+     */
+    pub fn poll_cancel(&mut self) -> bool {
+        let mut cancel = false;
+        while let Ok(key) = self.receiver.try_recv() {
+            if matches!(key, TerminalKey::Stop | TerminalKey::Quit) {
+                cancel = true;
+            }
+        }
+        self.control = Location::default();
+        cancel
+    }
+    pub fn render(&self, status: TerminalStatus) {
+        let mut lines = vec![
+            "rusty cnc controller".to_string(),
+            String::new(),
+            format!("  x: {:10.3} mm", status.pos.x),
+            format!("  y: {:10.3} mm", status.pos.y),
+            format!("  z: {:10.3} mm", status.pos.z),
+        ];
+        if let Some(a) = status.a {
+            lines.push(format!("  a: {:10.3} deg", a));
+        }
+        lines.push(String::new());
+        lines.push(format!(
+            "  state: {:?}   mode: {:?}   slow: {}",
+            status.state, status.mode, status.slow
+        ));
+        lines.push(format!(
+            "  program: {}",
+            status.selected_program.unwrap_or("---")
+        ));
+        lines.push(String::new());
+        lines.push(if *status.mode == Mode::Manual {
+            "  arrows: x / y   PgUp / PgDn: z   space: stop   s: slow   q: quit".to_string()
+        } else {
+            "  space / q / Ctrl-C: cancel".to_string()
+        });
+
+        let mut out = stdout();
+        let _ = execute!(out, MoveTo(0, 0), Clear(ClearType::All));
+        // raw mode needs explicit carriage returns
+        let _ = out.write_all(lines.join("\r\n").as_bytes());
+        let _ = out.flush();
+    }
+}
+
+impl Drop for TerminalUi {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
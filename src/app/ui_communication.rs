@@ -1,18 +1,19 @@
-use super::App;
+use super::{program_path, run::JOG_STEPS, App};
 
 use crate::gnc::NextMiscellaneous;
 use crate::types::Location;
 use crate::ui::types::{
-    InfoLvl, ProgramInfo, WsAvailableProgramsMessage, WsCommandControl, WsCommandController,
+    InfoLvl, Mode, WsAvailableProgramsMessage, WsCommandControl, WsCommandController,
     WsCommandProgram, WsCommandSettings, WsCommands, WsCommandsFrom, WsControllerMessage,
     WsInfoMessage, WsMessages, WsPositionMessage, WsReplyMessage, WsStatusMessage,
 };
 use std::{
     fs::{remove_file, File, OpenOptions},
     io::prelude::*,
+    path::Path,
     sync::mpsc::Sender,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use uuid::Uuid;
 
@@ -39,7 +40,35 @@ impl App {
                         self.send_controller_msg();
                     }
                 }
+                WsCommands::Controller(WsCommandController::Move { x, y, z }) => {
+                    if self.current_mode == Mode::Manual {
+                        let clamp = |v: f64| {
+                            if v.is_finite() {
+                                v.clamp(-1.0, 1.0)
+                            } else {
+                                0.0
+                            }
+                        };
+                        self.ui_control = Some(Location::new(clamp(x), clamp(y), clamp(z)));
+                        self.ui_control_received = Instant::now();
+                    }
+                }
+                WsCommands::Controller(WsCommandController::Jog { jog }) => {
+                    self.set_jog(jog);
+                }
+                WsCommands::Controller(WsCommandController::JogStep { step }) => {
+                    if !JOG_STEPS.contains(&step) {
+                        self.warning(format!("jog step {} mm is not selectable", step));
+                    } else if self.jog_step != step {
+                        self.jog_step = step;
+                        self.send_controller_msg();
+                    }
+                }
                 WsCommands::Control(WsCommandControl::OnOff { on }) => {
+                    // a program must not cut with a stopped spindle
+                    if !on && self.current_mode == Mode::Program {
+                        self.cnc.pause(true);
+                    }
                     if self.cnc.is_switched_on() != on {
                         println!("switch to {}", on);
                         self.cnc.manual_miscellaneous(if on {
@@ -51,34 +80,112 @@ impl App {
                         self.send_status_msg();
                     }
                 }
+                WsCommands::Control(WsCommandControl::MoveRelative { x, y, z, a }) => {
+                    if self.current_mode == Mode::Manual {
+                        self.cnc
+                            .manual_step(x, y, z, a, self.settings.default_speed);
+                    } else {
+                        self.warning("relative move is only available in manual mode".to_string());
+                    }
+                }
+                WsCommands::Control(WsCommandControl::Home) => {
+                    if self.current_mode == Mode::Manual {
+                        self.home();
+                    } else {
+                        self.warning("homing is only available in manual mode".to_string());
+                    }
+                }
                 WsCommands::Program(WsCommandProgram::Get) => {
                     self.send_available_programs_msg(uuid)
                 }
+                WsCommands::Program(WsCommandProgram::Refresh) => {
+                    let available_progs = App::read_available_progs(&self.settings.input_dir);
+                    self.set_available_programs(available_progs);
+                    self.send_available_programs_msg(uuid);
+                }
                 WsCommands::Program(WsCommandProgram::Load { program_name }) => {
-                    self.send_program_data_msg(uuid, program_name)
+                    match program_path(&self.settings.input_dir, &program_name) {
+                        Ok(path) => {
+                            self.analyze_program(&program_name, path.clone());
+                            self.send_program_data_msg(uuid, program_name, &path)
+                        }
+                        Err(reason) => {
+                            self.send_program_rejected_message(uuid, program_name, reason)
+                        }
+                    }
                 }
                 WsCommands::Program(WsCommandProgram::Start {
                     program_name,
                     invert_z,
                     scale,
                 }) => {
-                    if self.start_program(&program_name, invert_z, scale) {
-                        self.send_start_reply_message(uuid, program_name)
+                    match self.start_program(
+                        &program_name,
+                        invert_z,
+                        scale,
+                        Location::default(),
+                        None,
+                    ) {
+                        Ok(()) => self.send_start_reply_message(uuid, program_name),
+                        Err(reason) => {
+                            self.send_program_rejected_message(uuid, program_name, reason)
+                        }
                     }
                 }
+                WsCommands::Program(WsCommandProgram::Resume {
+                    program_name,
+                    invert_z,
+                    scale,
+                    line,
+                    offset,
+                }) => {
+                    let offset = Location::new(offset.x, offset.y, offset.z);
+                    match self.start_program(&program_name, invert_z, scale, offset, Some(line)) {
+                        Ok(()) => {
+                            self.info(format!("resume {} at line {}", program_name, line));
+                            self.send_start_reply_message(uuid, program_name)
+                        }
+                        Err(reason) => {
+                            self.send_program_rejected_message(uuid, program_name, reason)
+                        }
+                    }
+                }
+                WsCommands::Program(WsCommandProgram::Simulate {
+                    program_name,
+                    invert_z,
+                    scale,
+                }) => match program_path(&self.settings.input_dir, &program_name) {
+                    Ok(path) => self.simulate_program(program_name, path, invert_z, scale),
+                    Err(reason) => self.send_program_rejected_message(uuid, program_name, reason),
+                },
                 WsCommands::Program(WsCommandProgram::Cancel) => {
                     self.cancel_program();
                     self.send_cancel_reply_message(uuid, true);
                 }
+                WsCommands::Program(WsCommandProgram::Pause { pause }) => {
+                    if self.current_mode == Mode::Program {
+                        self.cnc.pause(pause);
+                        self.send_status_msg();
+                    } else {
+                        self.warning("no program to pause".to_string());
+                    }
+                }
                 WsCommands::Program(WsCommandProgram::Save {
                     program_name,
                     program,
                 }) => {
+                    let path = match program_path(&self.settings.input_dir, &program_name) {
+                        Ok(path) => path,
+                        Err(reason) => {
+                            self.send_program_rejected_message(uuid, program_name, reason);
+                            return;
+                        }
+                    };
                     match OpenOptions::new()
                         .write(true)
                         .create(true)
                         .truncate(true)
-                        .open(program_name.clone())
+                        .open(path)
                     {
                         Err(why) => {
                             self.info(format!("couldn't open {}: {}", program_name, why));
@@ -99,17 +206,25 @@ impl App {
                     }
                 }
                 WsCommands::Program(WsCommandProgram::Delete { program_name }) => {
-                    self.send_delete_reply_message(
-                        uuid,
-                        program_name.clone(),
-                        remove_file(program_name).is_ok(),
-                    );
+                    match program_path(&self.settings.input_dir, &program_name) {
+                        Ok(path) => {
+                            let ok = remove_file(path).is_ok();
+                            self.send_delete_reply_message(uuid, program_name, ok);
+                        }
+                        Err(reason) => {
+                            self.send_program_rejected_message(uuid, program_name, reason)
+                        }
+                    }
                 }
+                WsCommands::Program(WsCommandProgram::History) => {
+                    self.send_history_reply_message(uuid)
+                }
+                WsCommands::Queue(cmd) => self.handle_queue_command(uuid, cmd),
                 WsCommands::Settings(WsCommandSettings::GetRuntime) => {
                     self.send_runtime_settings_reply_message(uuid);
                 }
                 WsCommands::Settings(WsCommandSettings::SetRuntime(settings)) => {
-                    match self.set_runtime_settings(settings, &update_path) {
+                    match self.set_runtime_settings(settings, update_path) {
                         Ok(()) => self.send_runtime_settings_saved_reply_message(uuid, true),
                         Err(_) => self.send_runtime_settings_saved_reply_message(uuid, false),
                     };
@@ -122,6 +237,13 @@ impl App {
                         Ok(()) => self.send_system_settings_saved_reply_message(uuid, true),
                         Err(_) => self.send_system_settings_saved_reply_message(uuid, false),
                     };
+                }
+                WsCommands::Settings(WsCommandSettings::GetGamepad) => {
+                    self.send_gamepad_settings_reply_message(uuid);
+                }
+                WsCommands::Settings(WsCommandSettings::SetGamepad(mapping)) => {
+                    let ok = self.set_gamepad_settings(mapping).is_ok();
+                    self.send_gamepad_settings_saved_reply_message(uuid, ok);
                 } //_ => (),
             };
         };
@@ -139,6 +261,7 @@ impl App {
             self.steps_todo,
             self.steps_done,
             self.cnc.is_switched_on(),
+            self.cnc.is_paused(),
         )
     }
     pub fn send_status_msg(&self) {
@@ -152,8 +275,7 @@ impl App {
                 progs: self
                     .available_progs
                     .iter()
-                    .map(|name| name.to_owned())
-                    .map(ProgramInfo::from_string)
+                    .map(|name| self.program_info(name.to_owned()))
                     .collect(),
                 input_dir: self.settings.input_dir.clone(),
             }))
@@ -166,6 +288,9 @@ impl App {
                 self.freeze_x,
                 self.freeze_y,
                 self.slow_control,
+                self.gamepad_connected,
+                self.jog,
+                self.jog_step,
             )))
             .unwrap();
     }
@@ -177,18 +302,20 @@ impl App {
                     progs: self
                         .available_progs
                         .iter()
-                        .map(|name| name.to_owned())
-                        .map(ProgramInfo::from_string)
+                        .map(|name| self.program_info(name.to_owned()))
                         .collect(),
                     input_dir: self.settings.input_dir.clone(),
                 }),
             })
             .unwrap();
     }
-    pub fn send_program_data_msg(&self, to: Uuid, program_name: String) {
-        let mut file = File::open(program_name.clone()).unwrap();
+    pub fn send_program_data_msg(&self, to: Uuid, program_name: String, path: &Path) {
         let mut program = String::new();
-        file.read_to_string(&mut program).unwrap();
+        if let Err(why) = File::open(path).and_then(|mut file| file.read_to_string(&mut program)) {
+            let reason = format!("couldn't read {}: {}", program_name, why);
+            self.send_program_rejected_message(to, program_name, reason);
+            return;
+        }
 
         self.ui_data_sender
             .send(WsMessages::Reply {
@@ -218,6 +345,19 @@ impl App {
             })
             .unwrap();
     }
+    /// the program points outside of the input directories, to no program or can not be loaded
+    pub fn send_program_rejected_message(&self, to: Uuid, program_name: String, reason: String) {
+        self.warning(reason.clone());
+        self.ui_data_sender
+            .send(WsMessages::Reply {
+                to,
+                msg: WsReplyMessage::ProgramRejected {
+                    program_name,
+                    reason,
+                },
+            })
+            .unwrap();
+    }
     pub fn send_save_reply_message(&self, to: Uuid, program_name: String, ok: bool) {
         self.ui_data_sender
             .send(WsMessages::Reply {
@@ -247,6 +387,9 @@ impl App {
                     invert_z: self.settings.invert_z,
                     show_console_output: self.settings.show_console_output,
                     console_pos_update_reduce: self.settings.console_pos_update_reduce,
+                    arc_tolerance: self.settings.arc_tolerance,
+                    jog_timeout: self.settings.jog_timeout,
+                    max_upload_size: self.settings.max_upload_size,
                 },
             })
             .unwrap();
@@ -268,9 +411,14 @@ impl App {
                     motor_x: self.settings.motor_x.clone(),
                     motor_y: self.settings.motor_y.clone(),
                     motor_z: self.settings.motor_z.clone(),
+                    motor_x_slave: self.settings.motor_x_slave.clone(),
+                    motor_y_slave: self.settings.motor_y_slave.clone(),
+                    motor_a: self.settings.motor_a.clone(),
                     calibrate_z_gpio: self.settings.calibrate_z_gpio,
                     on_off_gpio: self.settings.on_off_gpio,
                     switch_on_off_delay: self.settings.switch_on_off_delay,
+                    pulse_backend: self.settings.pulse_backend.clone(),
+                    pulse_latency: self.settings.pulse_latency,
                 },
             })
             .unwrap();
@@ -283,12 +431,28 @@ impl App {
             })
             .unwrap();
     }
-    pub fn get_pos_msg(pos: &Location<f64>) -> WsPositionMessage {
-        WsPositionMessage::new(pos.x, pos.y, pos.z)
+    pub fn send_gamepad_settings_reply_message(&self, to: Uuid) {
+        self.ui_data_sender
+            .send(WsMessages::Reply {
+                to,
+                msg: WsReplyMessage::GamepadSettings(self.settings.gamepad.clone()),
+            })
+            .unwrap();
+    }
+    pub fn send_gamepad_settings_saved_reply_message(&self, to: Uuid, ok: bool) {
+        self.ui_data_sender
+            .send(WsMessages::Reply {
+                to,
+                msg: WsReplyMessage::GamepadSettingsSaved { ok },
+            })
+            .unwrap();
+    }
+    pub fn get_pos_msg(pos: &Location<f64>, a: Option<f64>) -> WsPositionMessage {
+        WsPositionMessage::new(pos.x, pos.y, pos.z, a)
     }
-    pub fn send_pos_msg(&self, pos: &Location<f64>) {
+    pub fn send_pos_msg(&self, pos: &Location<f64>, a: Option<f64>) {
         self.ui_data_sender
-            .send(WsMessages::Position(App::get_pos_msg(pos)))
+            .send(WsMessages::Position(App::get_pos_msg(pos, a)))
             .unwrap();
     }
 }
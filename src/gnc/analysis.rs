@@ -0,0 +1,199 @@
+use super::{Gnc, Next3dMovement, NextInstruction, NextMiscellaneous};
+use crate::motor::MotorSettings;
+use crate::types::{LinearMovement, Location, MoveType};
+use gcode::{GCode, Mnemonic};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramAnalysis {
+    /// lines with at least one code
+    pub lines_of_code: u32,
+    /// lowest position [x, y, z] in mm
+    pub min: [f64; 3],
+    /// highest position [x, y, z] in mm
+    pub max: [f64; 3],
+    /// feed moves (G1, G2, G3) [mm]
+    pub cut_distance: f64,
+    /// rapid moves (G0) [mm]
+    pub rapid_distance: f64,
+    /// [s] at the programmed feeds, ramped up with the accelerations of the motors
+    pub duration: f64,
+    pub tools: Vec<i32>,
+    /// codes the controller skips, e.g. "G17" or "M8"
+    pub unsupported: Vec<String>,
+}
+
+/// ramp of one axis like `Motor::ramp_step`
+struct Ramp<'a> {
+    motor: &'a MotorSettings,
+    // [steps / sec] at the end of the last move
+    rate: f64,
+}
+
+impl<'a> Ramp<'a> {
+    /**
+     * - **steps** to move
+     * - **target** steps per sec the move requests
+     *
+     * @return time [s] the axis takes for the steps
+     */
+    fn run(&mut self, steps: u64, target: f64) -> f64 {
+        if steps == 0 {
+            // the motor stands still, the next move starts slow again
+            self.rate = 0.0;
+            return 0.0;
+        }
+        let m = self.motor;
+        let target = target.min(m.max_step_speed as f64 / m.step_size);
+        self.rate = self.rate.min(target);
+
+        let mut time = 0.0f64;
+        let mut done = 0u64;
+        while done < steps && self.rate < target {
+            let current = self.rate.max(m.free_step_speed);
+            let next = (current + m.acceleration - current * m.acceleration_damping).min(target);
+            if next <= self.rate {
+                // the damping stops the ramp below the target
+                break;
+            }
+            self.rate = next;
+            time += 1.0 / next;
+            done += 1;
+        }
+        if self.rate > 0.0 {
+            time += (steps - done) as f64 / self.rate;
+        }
+        time
+    }
+}
+
+fn code_name(code: &GCode) -> String {
+    let letter = match code.mnemonic() {
+        Mnemonic::General => 'G',
+        Mnemonic::Miscellaneous => 'M',
+        Mnemonic::ProgramNumber => 'O',
+        Mnemonic::ToolChange => 'T',
+    };
+    match code.minor_number() {
+        0 => format!("{}{}", letter, code.major_number()),
+        minor => format!("{}{}.{}", letter, code.major_number(), minor),
+    }
+}
+
+impl ProgramAnalysis {
+    fn add_position(&mut self, pos: &Location<f64>, first: bool) {
+        for (i, v) in [pos.x, pos.y, pos.z].iter().enumerate() {
+            if first || *v < self.min[i] {
+                self.min[i] = *v;
+            }
+            if first || *v > self.max[i] {
+                self.max[i] = *v;
+            }
+        }
+    }
+    fn add_move(&mut self, m: &Next3dMovement, ramps: &mut [Ramp; 3]) {
+        let (movement, rapid) = match &m.move_type {
+            MoveType::Linear(movement) => (movement, false),
+            MoveType::Rapid(movement) => (movement, true),
+        };
+        let LinearMovement {
+            delta, distance, ..
+        } = movement;
+        let distance = *distance;
+        if rapid {
+            self.rapid_distance += distance;
+        } else {
+            self.cut_distance += distance;
+        }
+        if m.speed <= 0.0 {
+            return;
+        }
+        // [mm / min] like the motor controller
+        let nominal = distance / (m.speed / 60.0);
+        let mut time = nominal;
+        for (ramp, d) in ramps.iter_mut().zip([delta.x, delta.y, delta.z].iter()) {
+            let steps = (d.abs() / ramp.motor.step_size).round() as u64;
+            time = time.max(ramp.run(steps, steps as f64 / nominal));
+        }
+        self.duration += time;
+    }
+}
+
+impl Gnc {
+    /**
+     * run through the program without a machine
+     *
+     * - **motors** settings of the x, y and z motor for the acceleration
+     */
+    pub fn analyze(&self, motors: [&MotorSettings; 3]) -> ProgramAnalysis {
+        let mut analysis = ProgramAnalysis::default();
+        let mut lines = BTreeSet::new();
+        let mut tools = BTreeSet::new();
+        let mut unsupported = BTreeSet::new();
+        for code in self.codes.iter() {
+            lines.insert(code.span().line);
+        }
+        analysis.lines_of_code = lines.len() as u32;
+
+        // the distances of an inch program would be read as mm
+        if self.uses_inch() {
+            analysis.unsupported.push("G20".to_string());
+            return analysis;
+        }
+
+        let mut prog = self.clone();
+        let mut ramps = [
+            Ramp {
+                motor: motors[0],
+                rate: 0.0,
+            },
+            Ramp {
+                motor: motors[1],
+                rate: 0.0,
+            },
+            Ramp {
+                motor: motors[2],
+                rate: 0.0,
+            },
+        ];
+        let mut selected_tool = None;
+        let mut first = true;
+        while let Some(code) = prog.codes.get(prog.current_step).cloned() {
+            prog.current_step += 1;
+            // the tool is selected with T, M6 changes to it
+            if code.mnemonic() == Mnemonic::ToolChange {
+                selected_tool = Some(code.major_number() as i32);
+                tools.insert(code.major_number() as i32);
+                continue;
+            }
+            let mut next = prog.parse_code(code.clone());
+            while let Some(instruction) = next {
+                match instruction {
+                    NextInstruction::Movement(m) => {
+                        analysis.add_position(&m.from, first);
+                        analysis.add_position(&m.to, false);
+                        analysis.add_move(&m, &mut ramps);
+                        first = false;
+                    }
+                    // M6 without T changes to the selected tool
+                    NextInstruction::Miscellaneous(NextMiscellaneous::ToolChange(tool))
+                        if code.value_for('T').is_some() || selected_tool.is_none() =>
+                    {
+                        tools.insert(tool);
+                    }
+                    NextInstruction::NotSupported(_) => {
+                        unsupported.insert(code_name(&code));
+                    }
+                    _ => (),
+                }
+                // arcs queue their remaining chords
+                next = prog.pending.pop_front();
+            }
+        }
+        analysis.tools = tools.into_iter().collect();
+        analysis.unsupported = unsupported.into_iter().collect();
+        analysis
+    }
+}
@@ -0,0 +1,181 @@
+use crate::types::{CircleDirection, Location};
+use std::f64::consts::PI;
+
+/// smallest chord tolerance in mm. Keeps the segment count bounded for tiny settings
+const MIN_TOLERANCE: f64 = 0.001;
+/// sweep below this angle is handled as full circle (start == end)
+const FULL_CIRCLE_EPSILON: f64 = 0.000_001;
+
+/**
+ * split an arc in the XY plane into chord end points
+ *
+ * - **from** start position of the arc
+ * - **to** end position of the arc. Z is interpolated linear (helix)
+ * - **center** absolute center of the arc
+ * - **direction** CW or CCW
+ * - **tolerance** max distance in mm between a chord and the real arc
+ *
+ * the last point is always exactly **to**
+ */
+pub fn interpolate(
+    from: &Location<f64>,
+    to: &Location<f64>,
+    center: &Location<f64>,
+    direction: CircleDirection,
+    tolerance: f64,
+) -> Vec<Location<f64>> {
+    let radius = (from.x - center.x).hypot(from.y - center.y);
+    if radius == 0.0 {
+        return vec![to.clone()];
+    }
+
+    let start_angle = (from.y - center.y).atan2(from.x - center.x);
+    let end_angle = (to.y - center.y).atan2(to.x - center.x);
+    let mut sweep = match direction {
+        CircleDirection::CW => start_angle - end_angle,
+        CircleDirection::CCW => end_angle - start_angle,
+    };
+    if sweep < FULL_CIRCLE_EPSILON {
+        sweep += 2.0 * PI;
+    }
+
+    // max angle of one chord to stay within the tolerance: sagitta = r * (1 - cos(a / 2))
+    let tolerance = tolerance.max(MIN_TOLERANCE);
+    let max_angle = if tolerance >= radius {
+        PI / 2.0
+    } else {
+        2.0 * (1.0 - tolerance / radius).acos()
+    };
+    let segments = (sweep / max_angle).ceil().max(1.0) as usize;
+
+    let angle_step = match direction {
+        CircleDirection::CW => -sweep / segments as f64,
+        CircleDirection::CCW => sweep / segments as f64,
+    };
+    let z_step = (to.z - from.z) / segments as f64;
+
+    let mut points: Vec<Location<f64>> = (1..segments)
+        .map(|i| {
+            let angle = start_angle + angle_step * i as f64;
+            Location::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+                from.z + z_step * i as f64,
+            )
+        })
+        .collect();
+    points.push(to.clone());
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: f64 = 10.0;
+
+    fn center() -> Location<f64> {
+        Location::new(0.0, 0.0, 0.0)
+    }
+
+    fn quarter(direction: CircleDirection, tolerance: f64) -> Vec<Location<f64>> {
+        interpolate(
+            &Location::new(RADIUS, 0.0, 0.0),
+            &Location::new(0.0, RADIUS, 0.0),
+            &center(),
+            direction,
+            tolerance,
+        )
+    }
+
+    /// largest distance between the middle of a chord and the arc
+    fn max_deviation(from: &Location<f64>, points: &[Location<f64>]) -> f64 {
+        let mut last = from.clone();
+        let mut max = 0.0f64;
+        for p in points {
+            let middle = ((last.x + p.x) / 2.0).hypot((last.y + p.y) / 2.0);
+            max = max.max(RADIUS - middle);
+            last = p.clone();
+        }
+        max
+    }
+
+    #[test]
+    fn chords_stay_within_the_tolerance() {
+        let from = Location::new(RADIUS, 0.0, 0.0);
+        let mut last_count = 0;
+        for tolerance in [0.5, 0.1, 0.01, 0.001].iter() {
+            let points = quarter(CircleDirection::CCW, *tolerance);
+            assert!(max_deviation(&from, &points) <= *tolerance + 1e-9);
+            // the chords are as long as the tolerance allows
+            let segments = (PI / 2.0 / (2.0 * (1.0 - tolerance / RADIUS).acos())).ceil();
+            assert_eq!(points.len(), segments as usize);
+            assert!(points.len() > last_count);
+            last_count = points.len();
+        }
+        // a smaller tolerance than MIN_TOLERANCE does not add chords
+        assert_eq!(
+            quarter(CircleDirection::CCW, 0.0).len(),
+            quarter(CircleDirection::CCW, MIN_TOLERANCE).len()
+        );
+    }
+
+    #[test]
+    fn same_start_and_end_is_a_full_circle() {
+        let from = Location::new(RADIUS, 0.0, 0.0);
+        for direction in [CircleDirection::CW, CircleDirection::CCW].iter() {
+            let points = interpolate(&from, &from, &center(), *direction, 0.01);
+            let half = points.len() / 2;
+            assert!(points.len() > 4);
+            assert!((points[half - 1].x + RADIUS).abs() < 0.5);
+            assert!(points
+                .iter()
+                .all(|p| (p.x.hypot(p.y) - RADIUS).abs() < 1e-9));
+            assert!(points.last() == Some(&from));
+        }
+    }
+
+    #[test]
+    fn g2_runs_clockwise_and_g3_counterclockwise() {
+        let from = Location::new(RADIUS, 0.0, 0.0);
+        // cross product of the radius vectors, positive if the arc turns counterclockwise
+        let turn = |points: &[Location<f64>]| {
+            let mut last = from.clone();
+            points
+                .iter()
+                .map(|p| {
+                    let turn = last.x * p.y - last.y * p.x;
+                    last = p.clone();
+                    turn.signum()
+                })
+                .collect::<Vec<f64>>()
+        };
+
+        let ccw = quarter(CircleDirection::CCW, 0.01);
+        assert!(turn(&ccw).iter().all(|t| *t > 0.0));
+        assert!(ccw.iter().all(|p| p.x >= -1e-9 && p.y >= -1e-9));
+
+        // clockwise it is the long way through the other quadrants
+        let cw = quarter(CircleDirection::CW, 0.01);
+        assert!(turn(&cw).iter().all(|t| *t < 0.0));
+        assert!(cw.iter().any(|p| p.y < -RADIUS + 0.1));
+        assert!(cw.len() > 2 * ccw.len());
+        assert!(cw.last() == Some(&Location::new(0.0, RADIUS, 0.0)));
+    }
+
+    #[test]
+    fn z_is_interpolated_along_a_helix() {
+        let points = interpolate(
+            &Location::new(RADIUS, 0.0, 0.0),
+            &Location::new(0.0, RADIUS, -2.0),
+            &center(),
+            CircleDirection::CCW,
+            0.01,
+        );
+        let steps: Vec<f64> = points.windows(2).map(|w| w[1].z - w[0].z).collect();
+        assert!(steps
+            .iter()
+            .all(|s| (s - steps[0]).abs() < 1e-9 && *s < 0.0));
+        assert_eq!(points.last().unwrap().z, -2.0);
+    }
+}
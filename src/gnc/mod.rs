@@ -1,7 +1,12 @@
 #![allow(dead_code)]
-use crate::types::{CircleDirection, CircleMovement, LinearMovement, Location, MoveType};
+mod analysis;
+mod arc;
+
+use crate::types::{CircleDirection, LinearMovement, Location, MoveType};
+pub use analysis::ProgramAnalysis;
 use gcode::{buffers::DefaultBuffers, GCode, Mnemonic, Nop, Parser};
-use std::{fs::File, io::prelude::*};
+use sha2::{Digest, Sha256};
+use std::{collections::VecDeque, fs::File, io::prelude::*, path::Path};
 
 #[derive(Debug, Clone)]
 enum Coordinations {
@@ -17,43 +22,119 @@ pub struct Gnc {
     scaler: f64,
     coordinations: Coordinations,
     current_position: Location<f64>,
+    /// angle of the rotary A axis [deg]
+    current_a: f64,
     invert_z: bool,
+    /// work offset, added to the absolute coordinates
+    offset: Location<f64>,
     current_speed: f64,
     rapid_speed: f64,
+    /// max deviation in mm between an arc and its chords
+    arc_tolerance: f64,
+    /// already parsed instructions (arc chords) waiting to be returned
+    pending: VecDeque<NextInstruction>,
 }
 
+#[allow(clippy::too_many_arguments)]
 impl Gnc {
     pub fn new(
-        path: &str,
+        path: &Path,
         default_speed: f64,
         rapid_speed: f64,
         scaler: f64,
         start_pos: Location<f64>,
+        start_a: f64,
         invert_z: bool,
+        arc_tolerance: f64,
     ) -> std::io::Result<Gnc> {
         let mut file = File::open(path)?;
         let mut content = String::new();
         file.read_to_string(&mut content)?;
 
-        let mut codes = vec![];
-        let lines: Parser<Nop, DefaultBuffers> = Parser::new(&content, Nop);
-        for line in lines {
-            for code in line.gcodes() {
-                codes.push(code.to_owned());
-            }
-        }
+        Ok(Gnc::from_content(
+            content,
+            default_speed,
+            rapid_speed,
+            scaler,
+            start_pos,
+            start_a,
+            invert_z,
+            arc_tolerance,
+        ))
+    }
+
+    pub fn from_content(
+        content: String,
+        default_speed: f64,
+        rapid_speed: f64,
+        scaler: f64,
+        start_pos: Location<f64>,
+        start_a: f64,
+        invert_z: bool,
+        arc_tolerance: f64,
+    ) -> Gnc {
+        let codes = parse_codes(&content);
 
-        Ok(Gnc {
+        Gnc {
             content,
             codes,
             current_step: 0,
             scaler,
             coordinations: Coordinations::Absolute,
             current_position: start_pos,
+            current_a: start_a,
             invert_z,
+            offset: Location::default(),
             current_speed: default_speed,
             rapid_speed,
-        })
+            arc_tolerance,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// continue a streamed program from a position the machine was moved to in between
+    pub fn set_position(&mut self, pos: Location<f64>, a: f64) {
+        self.current_position = pos;
+        self.current_a = a;
+    }
+    /// shift the absolute coordinates of the program, e.g. to the origin of a second stock
+    pub fn set_offset(&mut self, offset: Location<f64>) {
+        self.offset = offset;
+    }
+    /// G91 is active
+    pub fn is_relative(&self) -> bool {
+        matches!(self.coordinations, Coordinations::Relative)
+    }
+    /**
+     * append a line to a streamed program. The modal state (position, speed, G90/G91) is kept.
+     *
+     * @return number of codes found in the line
+     */
+    pub fn push_line(&mut self, line: &str) -> usize {
+        let codes = parse_codes(line);
+        let count = codes.len();
+        self.content.push_str(line);
+        self.content.push('\n');
+        self.codes.extend(codes);
+        count
+    }
+
+    /// the program switches to inch units (G20), the controller runs in mm only
+    pub fn uses_inch(&self) -> bool {
+        self.codes
+            .iter()
+            .any(|code| code.mnemonic() == Mnemonic::General && code.major_number() == 20)
+    }
+    /// sha256 of the program as hex
+    pub fn hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.content.as_bytes()))
+    }
+    /// line (starting at 1) of the code returned last
+    pub fn current_line(&self) -> usize {
+        self.current_step
+            .checked_sub(1)
+            .and_then(|i| self.codes.get(i))
+            .map_or(0, |code| code.span().line + 1)
     }
 
     pub fn len(&self) -> usize {
@@ -78,6 +159,8 @@ pub struct Next3dMovement {
     pub from: Location<f64>,
     /** target pos */
     pub to: Location<f64>,
+    /** target angle of the rotary axis [deg] */
+    pub a: f64,
     /** movement type (Linear, Rapid, Bevel, ...) */
     pub move_type: MoveType,
 }
@@ -105,17 +188,16 @@ impl Iterator for Gnc {
 
     // next() is the only required method
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(pending) = self.pending.pop_front() {
+            return Some(pending);
+        }
+
         let step = self.codes.get(self.current_step);
         self.current_step += 1;
         step?; // only continue if step
         let code = step.unwrap().to_owned();
 
-        let res = match code.mnemonic() {
-            Mnemonic::General => self.parse_g_code(code),
-            Mnemonic::Miscellaneous => self.parse_m_code(code),
-            Mnemonic::ProgramNumber => self.parse_p_code(code),
-            Mnemonic::ToolChange => self.parse_t_code(code),
-        };
+        let res = self.parse_code(code);
 
         match res {
             None => self.next(),
@@ -125,6 +207,118 @@ impl Iterator for Gnc {
 }
 
 impl Gnc {
+    /**
+     * continue the program at the given line (starting at 1). The lines before only update the
+     * modal state (position, G90/G91, feed, spindle, tool). The machine approaches the start from
+     * above: up to the safe z (or stays higher), over the start, spindle on and plunge with the
+     * feed.
+     */
+    pub fn start_at_line(&mut self, line: usize, safe_z: f64) {
+        let machine_pos = self.current_position.clone();
+        let machine_a = self.current_a;
+        let mut spindle_on = false;
+        let mut tool = None;
+        while let Some(code) = self
+            .codes
+            .get(self.current_step)
+            .filter(|code| code.span().line + 1 < line)
+            .cloned()
+        {
+            self.current_step += 1;
+            match self.parse_code(code) {
+                Some(NextInstruction::Miscellaneous(NextMiscellaneous::SwitchOn)) => {
+                    spindle_on = true
+                }
+                Some(NextInstruction::Miscellaneous(NextMiscellaneous::SwitchOff)) => {
+                    spindle_on = false
+                }
+                Some(NextInstruction::Miscellaneous(NextMiscellaneous::ToolChange(t))) => {
+                    tool = Some(t)
+                }
+                _ => (),
+            }
+        }
+        // chords of an arc before the line
+        self.pending.clear();
+
+        let start = self.current_position.clone();
+        let start_a = self.current_a;
+        let safe_z = self.abs_pos(None, None, Some(safe_z as f32)).z;
+        // a machine above the safe z does not go down before it moves over the start.
+        // Up is negative with an inverted z
+        let safe_z = if self.invert_z {
+            safe_z.min(machine_pos.z)
+        } else {
+            safe_z.max(machine_pos.z)
+        };
+        let above_machine = Location::new(machine_pos.x, machine_pos.y, safe_z);
+        let above_start = Location::new(start.x, start.y, safe_z);
+
+        // retract first, the tool is changed above the work piece
+        self.push_move(
+            machine_pos,
+            above_machine.clone(),
+            machine_a,
+            machine_a,
+            true,
+        );
+        if let Some(t) = tool {
+            self.pending.push_back(NextInstruction::Miscellaneous(
+                NextMiscellaneous::ToolChange(t),
+            ));
+        }
+        self.push_move(above_machine, above_start.clone(), machine_a, start_a, true);
+        if spindle_on {
+            self.pending
+                .push_back(NextInstruction::Miscellaneous(NextMiscellaneous::SwitchOn));
+        }
+        self.push_move(above_start, start, start_a, start_a, false);
+    }
+    /// queue a straight move of the resume approach
+    fn push_move(
+        &mut self,
+        from: Location<f64>,
+        to: Location<f64>,
+        from_a: f64,
+        a: f64,
+        rapid: bool,
+    ) {
+        let delta = to.clone() - from.clone();
+        let delta_a = a - from_a;
+        let distance = distance_with_a(&delta, delta_a);
+        if distance == 0.0 {
+            return;
+        }
+        let movement = LinearMovement {
+            delta,
+            delta_a,
+            distance,
+        };
+        self.pending
+            .push_back(NextInstruction::Movement(Next3dMovement {
+                speed: if rapid {
+                    self.rapid_speed
+                } else {
+                    self.current_speed
+                },
+                from,
+                to,
+                a,
+                move_type: if rapid {
+                    MoveType::Rapid(movement)
+                } else {
+                    MoveType::Linear(movement)
+                },
+            }));
+    }
+    fn parse_code(&mut self, code: GCode) -> Option<NextInstruction> {
+        match code.mnemonic() {
+            Mnemonic::General => self.parse_g_code(code),
+            Mnemonic::Miscellaneous => self.parse_m_code(code),
+            Mnemonic::ProgramNumber => self.parse_p_code(code),
+            Mnemonic::ToolChange => self.parse_t_code(code),
+        }
+    }
     fn parse_g_code(&mut self, code: GCode) -> Option<NextInstruction> {
         match code.major_number() {
             0 => {
@@ -133,7 +327,8 @@ impl Gnc {
                     code.value_for('Y'),
                     code.value_for('Z'),
                 );
-                let distance = delta.distance();
+                let delta_a = self.move_delta_a(code.value_for('A'));
+                let distance = distance_with_a(&delta, delta_a);
                 if distance == 0.0 {
                     return None;
                 }
@@ -145,7 +340,12 @@ impl Gnc {
                         code.value_for('Y'),
                         code.value_for('Z'),
                     ),
-                    move_type: MoveType::Rapid(LinearMovement { delta, distance }),
+                    a: self.update_a(code.value_for('A')),
+                    move_type: MoveType::Rapid(LinearMovement {
+                        delta,
+                        delta_a,
+                        distance,
+                    }),
                 };
                 Some(NextInstruction::Movement(next_move))
             }
@@ -155,7 +355,8 @@ impl Gnc {
                     code.value_for('Y'),
                     code.value_for('Z'),
                 );
-                let distance = delta.distance();
+                let delta_a = self.move_delta_a(code.value_for('A'));
+                let distance = distance_with_a(&delta, delta_a);
                 if distance == 0.0 {
                     return None;
                 }
@@ -169,25 +370,31 @@ impl Gnc {
                         code.value_for('Y'),
                         code.value_for('Z'),
                     ),
-                    move_type: MoveType::Linear(LinearMovement { delta, distance }),
+                    a: self.update_a(code.value_for('A')),
+                    move_type: MoveType::Linear(LinearMovement {
+                        delta,
+                        delta_a,
+                        distance,
+                    }),
                 };
                 Some(NextInstruction::Movement(next_move))
             }
             major_number @ 2 | major_number @ 3 => {
-                let delta = self.move_delta(
+                let speed = self.get_speed(code.value_for('F'));
+                let from = self.current_position.clone();
+                let center = from.clone()
+                    + self.rel_pos(
+                        code.value_for('I'),
+                        code.value_for('J'),
+                        code.value_for('K'),
+                    );
+                let to = self.update_location(
                     code.value_for('X'),
                     code.value_for('Y'),
                     code.value_for('Z'),
                 );
-                if delta.distance() == 0.0 {
-                    return None;
-                }
-                let speed = self.get_speed(code.value_for('F'));
-                let center = self.rel_pos(
-                    code.value_for('I'),
-                    code.value_for('J'),
-                    code.value_for('K'),
-                );
+                let from_a = self.current_a;
+                let to_a = self.update_a(code.value_for('A'));
 
                 let turn_direction = if major_number == 2 {
                     CircleDirection::CW
@@ -195,29 +402,42 @@ impl Gnc {
                     CircleDirection::CCW
                 };
 
-                let next_move = Next3dMovement {
-                    speed,
-                    from: self.current_position.clone(),
-                    to: self.update_location(
-                        code.value_for('X'),
-                        code.value_for('Y'),
-                        code.value_for('Z'),
-                    ),
-                    move_type: MoveType::Circle(CircleMovement {
-                        center: center.clone(),
-                        radius_sq: center.distance_sq(),
-                        turn_direction,
-                    }),
-                };
-                Some(NextInstruction::Movement(next_move))
-            }
-            20 => {
-                // Some(NextInstruction::InternalInstruction(format!(
-                //     "use inch unit {}",
-                //     code.major_number()
-                // )));
-                panic!("inch not supported");
+                // feed the arc as short chords through the linear move path
+                let chords =
+                    arc::interpolate(&from, &to, &center, turn_direction, self.arc_tolerance);
+                let chord_count = chords.len() as f64;
+                let mut chord_from = from;
+                let mut chord_from_a = from_a;
+                for (i, chord_to) in chords.into_iter().enumerate() {
+                    let chord_to_a = from_a + (to_a - from_a) * (i + 1) as f64 / chord_count;
+                    let delta = chord_to.clone() - chord_from.clone();
+                    let delta_a = chord_to_a - chord_from_a;
+                    let distance = distance_with_a(&delta, delta_a);
+                    if distance == 0.0 {
+                        continue;
+                    }
+                    self.pending
+                        .push_back(NextInstruction::Movement(Next3dMovement {
+                            speed,
+                            from: chord_from,
+                            to: chord_to.clone(),
+                            a: chord_to_a,
+                            move_type: MoveType::Linear(LinearMovement {
+                                delta,
+                                delta_a,
+                                distance,
+                            }),
+                        }));
+                    chord_from = chord_to;
+                    chord_from_a = chord_to_a;
+                }
+                self.pending.pop_front()
             }
+            // inch units are not supported
+            20 => Some(NextInstruction::NotSupported(format!(
+                "{}",
+                code.major_number()
+            ))),
             21 => Some(NextInstruction::InternalInstruction(format!(
                 "use mm unit {}",
                 code.major_number()
@@ -274,12 +494,7 @@ impl Gnc {
     fn move_delta(&self, x: Option<f32>, y: Option<f32>, z: Option<f32>) -> Location<f64> {
         match self.coordinations {
             Coordinations::Relative => self.rel_pos(x, y, z),
-            Coordinations::Absolute => Location {
-                x: get_or_default(x, self.current_position.x, false) - self.current_position.x,
-                y: get_or_default(y, self.current_position.y, false) - self.current_position.y,
-                z: get_or_default(z, self.current_position.z, self.invert_z)
-                    - self.current_position.z,
-            },
+            Coordinations::Absolute => self.abs_pos(x, y, z) - self.current_position.clone(),
         }
     }
     fn rel_pos(&self, x: Option<f32>, y: Option<f32>, z: Option<f32>) -> Location<f64> {
@@ -290,21 +505,42 @@ impl Gnc {
         }
     }
 
+    /// target of an absolute move, missing coordinates keep the current position
+    fn abs_pos(&self, x: Option<f32>, y: Option<f32>, z: Option<f32>) -> Location<f64> {
+        let abs = |value: Option<f32>, current: f64, offset: f64, invert: bool| match value {
+            Some(_) => get_or_default(value, 0.0, invert) + offset,
+            None => current,
+        };
+        Location {
+            x: abs(x, self.current_position.x, self.offset.x, false),
+            y: abs(y, self.current_position.y, self.offset.y, false),
+            z: abs(z, self.current_position.z, self.offset.z, self.invert_z),
+        }
+    }
+
     /// Update the current Location for the next instruction coordinate corresponding to the relative or absolute mode
     fn update_location(&mut self, x: Option<f32>, y: Option<f32>, z: Option<f32>) -> Location<f64> {
         match self.coordinations {
-            Coordinations::Relative => self.current_position = self.rel_pos(x, y, z),
-            Coordinations::Absolute => {
-                self.current_position = Location {
-                    x: get_or_default(x, self.current_position.x, false),
-                    y: get_or_default(y, self.current_position.y, false),
-                    z: get_or_default(z, self.current_position.z, self.invert_z),
-                }
+            Coordinations::Relative => {
+                self.current_position = self.current_position.clone() + self.rel_pos(x, y, z)
             }
+            Coordinations::Absolute => self.current_position = self.abs_pos(x, y, z),
         };
         self.current_position.clone()
     }
 
+    /// Calculate the rotation of the A axis corresponding to the relative or absolute mode
+    fn move_delta_a(&self, a: Option<f32>) -> f64 {
+        match self.coordinations {
+            Coordinations::Relative => get_or_default(a, 0.0, false),
+            Coordinations::Absolute => get_or_default(a, self.current_a, false) - self.current_a,
+        }
+    }
+    fn update_a(&mut self, a: Option<f32>) -> f64 {
+        self.current_a += self.move_delta_a(a);
+        self.current_a
+    }
+
     fn get_speed(&mut self, value: Option<f32>) -> f64 {
         if let Some(v) = value {
             self.current_speed = v as f64;
@@ -313,6 +549,22 @@ impl Gnc {
     }
 }
 
+fn parse_codes(content: &str) -> Vec<GCode> {
+    let mut codes = vec![];
+    let lines: Parser<Nop, DefaultBuffers> = Parser::new(content, Nop);
+    for line in lines {
+        for code in line.gcodes() {
+            codes.push(code.to_owned());
+        }
+    }
+    codes
+}
+
+/// feed distance of a move. Degrees of the A axis count like mm
+pub fn distance_with_a(delta: &Location<f64>, delta_a: f64) -> f64 {
+    (delta.distance_sq() + delta_a * delta_a).sqrt()
+}
+
 fn get_or_default(value: Option<f32>, default: f64, invert: bool) -> f64 {
     let value = if let Some(v) = value {
         v as f64
@@ -326,3 +578,74 @@ fn get_or_default(value: Option<f32>, default: f64, invert: bool) -> f64 {
         value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(content: &str) -> Gnc {
+        Gnc::from_content(
+            content.to_string(),
+            5.0,
+            50.0,
+            1.0,
+            Location::default(),
+            0.0,
+            false,
+            0.01,
+        )
+    }
+
+    #[test]
+    fn inch_units_are_not_supported() {
+        let mut prog = program("G20\nG1 X1 F100\n");
+        assert!(prog.uses_inch());
+        assert!(matches!(
+            prog.next(),
+            Some(NextInstruction::NotSupported(_))
+        ));
+        assert!(!program("G21\nG1 X1 F100\n").uses_inch());
+    }
+
+    #[test]
+    fn start_at_line_retracts_changes_the_tool_and_plunges_with_the_spindle_on() {
+        let mut prog = program("G90\nG0 Z5\nM6 T2\nM3 S1000\nG1 X10 Y5 F300\nG1 Z-1\nG1 X20\nM5\n");
+        prog.start_at_line(7, 10.0);
+
+        let next_move = |prog: &mut Gnc| match prog.next() {
+            Some(NextInstruction::Movement(m)) => m,
+            other => panic!("no movement but {:?}", other),
+        };
+        let retract = next_move(&mut prog);
+        assert!(matches!(retract.move_type, MoveType::Rapid(_)));
+        assert_eq!(retract.to, Location::new(0.0, 0.0, 10.0));
+        assert!(matches!(
+            prog.next(),
+            Some(NextInstruction::Miscellaneous(
+                NextMiscellaneous::ToolChange(_)
+            ))
+        ));
+        let over_start = next_move(&mut prog);
+        assert!(matches!(over_start.move_type, MoveType::Rapid(_)));
+        assert_eq!(over_start.to, Location::new(10.0, 5.0, 10.0));
+        assert!(matches!(
+            prog.next(),
+            Some(NextInstruction::Miscellaneous(NextMiscellaneous::SwitchOn))
+        ));
+        let plunge = next_move(&mut prog);
+        assert!(matches!(plunge.move_type, MoveType::Linear(_)));
+        assert_eq!(plunge.to, Location::new(10.0, 5.0, -1.0));
+
+        // the line itself runs with the modal state of the skipped lines: absolute, F300
+        let cut = next_move(&mut prog);
+        assert!(matches!(cut.move_type, MoveType::Linear(_)));
+        assert_eq!(cut.from, Location::new(10.0, 5.0, -1.0));
+        assert_eq!(cut.to, Location::new(20.0, 5.0, -1.0));
+        assert_eq!(cut.speed, plunge.speed);
+        assert_ne!(cut.speed, prog.rapid_speed);
+        assert!(matches!(
+            prog.next(),
+            Some(NextInstruction::Miscellaneous(NextMiscellaneous::SwitchOff))
+        ));
+    }
+}
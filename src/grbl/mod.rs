@@ -0,0 +1,199 @@
+mod pty;
+mod session;
+
+use crate::types::Location;
+use crate::ui::auth::{Auth, Role};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    net::TcpListener,
+    sync::{Arc, RwLock},
+    thread,
+};
+
+/// greeting after a connect and a soft reset. Senders wait for it before streaming
+pub const WELCOME: &str = "Grbl 1.1h ['$' for help]";
+/// size of the serial RX buffer of a GRBL board, used by senders for character counting
+pub const RX_BUFFER_SIZE: usize = 128;
+/// reply to a sender without the required role. GRBL has no auth, it is the code of a locked machine
+pub const AUTH_FAILED: u8 = 9;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrblSettings {
+    pub enabled: bool,
+    // address of the tcp port, "0.0.0.0" for senders on other machines
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    // port for network senders (e.g. 23 like a ESP32 GRBL)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_port: Option<u16>,
+    // symlink to a pseudo terminal for serial senders (e.g. /tmp/ttyGRBL)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pty_link: Option<String>,
+}
+
+fn default_bind_address() -> String {
+    String::from("127.0.0.1")
+}
+
+impl Default for GrblSettings {
+    fn default() -> Self {
+        GrblSettings {
+            enabled: false,
+            bind_address: default_bind_address(),
+            tcp_port: None,
+            pty_link: None,
+        }
+    }
+}
+
+/// jog of a `$J=` line. Values are in mm, the feed in mm/min
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GrblJog {
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    pub a: Option<f64>,
+    pub feed: f64,
+    /// G90 / G91 of the jog line, None uses the modal state of the stream
+    pub relative: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GrblCommand {
+    /// g-code block
+    Line(String),
+    /// `$$`
+    ReadSettings,
+    /// `$n=value` and the role of the sender
+    WriteSetting(u32, f64, Role),
+    /// `$G`
+    ParserState,
+    /// `$H`
+    Home,
+    /// `$J=...`
+    Jog(GrblJog),
+    // realtime commands are executed at once and have no reply
+    FeedHold,
+    CycleStart,
+    Reset,
+    JogCancel,
+    /// a sender closed its connection
+    Disconnected,
+}
+
+/// lines printed before the `ok`, or the GRBL error code
+pub type GrblResult = Result<Vec<String>, u8>;
+
+#[derive(Debug)]
+pub struct GrblRequest {
+    pub command: GrblCommand,
+    /// dropped without a reply, if the request is flushed by a reset
+    pub reply: Option<Sender<GrblResult>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GrblState {
+    Idle,
+    Run,
+    Hold,
+    Jog,
+    Home,
+}
+
+/// machine state for the `?` status report, updated by the app
+#[derive(Debug, Clone)]
+pub struct GrblStatus {
+    pub state: GrblState,
+    pub pos: Location<f64>,
+    pub a: Option<f64>,
+}
+
+impl Default for GrblStatus {
+    fn default() -> Self {
+        GrblStatus {
+            state: GrblState::Idle,
+            pos: Location::default(),
+            a: None,
+        }
+    }
+}
+
+impl GrblStatus {
+    pub fn report(&self) -> String {
+        let state = match self.state {
+            GrblState::Idle => "Idle",
+            GrblState::Run => "Run",
+            GrblState::Hold => "Hold:0",
+            GrblState::Jog => "Jog",
+            GrblState::Home => "Home",
+        };
+        let mut report = format!(
+            "<{}|MPos:{:.3},{:.3},{:.3}",
+            state, self.pos.x, self.pos.y, self.pos.z
+        );
+        if let Some(a) = self.a {
+            let _ = write!(report, ",{:.3}", a);
+        }
+        report.push_str("|FS:0,0>");
+        report
+    }
+}
+
+/// requests of all sender sessions and the shared status
+pub struct Grbl {
+    pub receiver: Receiver<GrblRequest>,
+    pub status: Arc<RwLock<GrblStatus>>,
+}
+
+/**
+ * Start the GRBL 1.1 emulation on the configured tcp port and / or pseudo terminal
+ *
+ * - **auth** with auth enabled a sender has to send `$AUTH=<token>` of an operator first
+ *
+ * @return None if it is disabled
+ */
+pub fn start(settings: &GrblSettings, auth: Arc<Auth>) -> Option<Grbl> {
+    if !settings.enabled {
+        return None;
+    }
+    let (sender, receiver) = unbounded::<GrblRequest>();
+    let status = Arc::new(RwLock::new(GrblStatus::default()));
+
+    if let Some(port) = settings.tcp_port {
+        match TcpListener::bind((settings.bind_address.as_str(), port)) {
+            Ok(listener) => {
+                let sender = sender.clone();
+                let status = status.clone();
+                let auth = auth.clone();
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        println!("GRBL sender connected {:?}", stream.peer_addr());
+                        match stream.try_clone() {
+                            Ok(writer) => session::start(
+                                stream,
+                                writer,
+                                sender.clone(),
+                                status.clone(),
+                                auth.clone(),
+                            ),
+                            Err(e) => println!("GRBL connection failed {:?}", e),
+                        }
+                    }
+                });
+            }
+            Err(e) => println!("GRBL tcp port {} is not available: {:?}", port, e),
+        }
+    }
+    if let Some(link) = settings.pty_link.as_ref() {
+        match pty::open(link) {
+            Ok((reader, writer)) => session::start(reader, writer, sender, status.clone(), auth),
+            Err(e) => println!("GRBL pty {} is not available: {:?}", link, e),
+        }
+    }
+
+    Some(Grbl { receiver, status })
+}
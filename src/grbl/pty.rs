@@ -0,0 +1,34 @@
+use nix::{
+    pty::openpty,
+    sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg},
+    unistd::ttyname,
+};
+use std::{
+    fs::{self, File},
+    os::unix::{fs::symlink, io::FromRawFd},
+};
+
+/**
+ * open a raw pseudo terminal and link its slave side, senders open the link like a serial port
+ *
+ * The slave fd is never closed. Otherwise the master reads fail once a sender disconnects.
+ *
+ * @return reader and writer of the master side
+ */
+pub fn open(link: &str) -> Result<(File, File), String> {
+    let pty = openpty(None, None).map_err(|e| e.to_string())?;
+    let mut termios = tcgetattr(pty.slave).map_err(|e| e.to_string())?;
+    cfmakeraw(&mut termios);
+    tcsetattr(pty.slave, SetArg::TCSANOW, &termios).map_err(|e| e.to_string())?;
+
+    let path = ttyname(pty.slave).map_err(|e| e.to_string())?;
+    if fs::symlink_metadata(link).is_ok() {
+        fs::remove_file(link).map_err(|e| e.to_string())?;
+    }
+    symlink(&path, link).map_err(|e| e.to_string())?;
+    println!("GRBL pty {:?} linked to {}", path, link);
+
+    let reader = unsafe { File::from_raw_fd(pty.master) };
+    let writer = reader.try_clone().map_err(|e| e.to_string())?;
+    Ok((reader, writer))
+}
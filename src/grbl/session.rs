@@ -0,0 +1,456 @@
+use super::{
+    GrblCommand, GrblJog, GrblRequest, GrblResult, GrblStatus, AUTH_FAILED, RX_BUFFER_SIZE, WELCOME,
+};
+use crate::ui::auth::{Auth, Role};
+
+use crossbeam_channel::{bounded, unbounded, Sender};
+use std::{
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+};
+
+const SOFT_RESET: u8 = 0x18;
+const JOG_CANCEL: u8 = 0x85;
+
+/**
+ * Serve one sender (tcp connection or pty).
+ *
+ * The reader thread executes realtime commands at once and passes the lines on.
+ * The line thread answers a line not before the app took it, so the socket buffer
+ * fills up and the char counting of the sender stays in sync while the planner is full.
+ * Until the sender is authorized only `?` and `$AUTH=<token>` are accepted.
+ * Writing the motor settings (`$1xx=`) needs an admin token.
+ */
+pub fn start<R, W>(
+    mut reader: R,
+    writer: W,
+    requests: Sender<GrblRequest>,
+    status: Arc<RwLock<GrblStatus>>,
+    auth: Arc<Auth>,
+) where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    let writer = Arc::new(Mutex::new(writer));
+    // lines received before a soft reset are dropped
+    let generation = Arc::new(AtomicUsize::new(0));
+    let (line_sender, line_receiver) = unbounded::<(usize, String)>();
+    let role = Arc::new(Mutex::new(auth.role_of(None)));
+
+    write_lines(&writer, &[WELCOME.to_string()]);
+
+    {
+        let writer = writer.clone();
+        let generation = generation.clone();
+        let requests = requests.clone();
+        let role = role.clone();
+        thread::spawn(move || {
+            for (line_generation, line) in line_receiver {
+                if line_generation != generation.load(Relaxed) {
+                    continue;
+                }
+                let result = match authorize(&line, &auth, &role) {
+                    Ok(role) => execute_line(&line, &requests, role),
+                    Err(result) => Some(result),
+                };
+                if line_generation != generation.load(Relaxed) {
+                    continue;
+                }
+                match result {
+                    Some(Ok(mut lines)) => {
+                        lines.push("ok".to_string());
+                        write_lines(&writer, &lines);
+                    }
+                    Some(Err(code)) => write_lines(&writer, &[format!("error:{}", code)]),
+                    None => (),
+                }
+            }
+        });
+    }
+
+    thread::spawn(move || {
+        let realtime = |command| {
+            if is_operator(*role.lock().unwrap()) {
+                let _ = requests.send(GrblRequest {
+                    command,
+                    reply: None,
+                });
+            }
+        };
+        let mut buf = [0u8; RX_BUFFER_SIZE];
+        let mut line = Vec::<u8>::new();
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            for &byte in buf[..n].iter() {
+                match byte {
+                    b'?' => write_lines(&writer, &[status.read().unwrap().report()]),
+                    b'!' => realtime(GrblCommand::FeedHold),
+                    b'~' => realtime(GrblCommand::CycleStart),
+                    SOFT_RESET => {
+                        generation.fetch_add(1, Relaxed);
+                        line.clear();
+                        realtime(GrblCommand::Reset);
+                        write_lines(&writer, &[String::new(), WELCOME.to_string()]);
+                    }
+                    JOG_CANCEL => realtime(GrblCommand::JogCancel),
+                    b'\n' | b'\r' => {
+                        if !line.is_empty() {
+                            let text = String::from_utf8_lossy(&line).to_string();
+                            if line_sender.send((generation.load(Relaxed), text)).is_err() {
+                                return;
+                            }
+                            line.clear();
+                        }
+                    }
+                    // overrides and other extended realtime commands are not supported
+                    0x80..=0xff => (),
+                    _ => line.push(byte),
+                }
+            }
+        }
+        println!("GRBL sender disconnected");
+        realtime(GrblCommand::Disconnected);
+    });
+}
+
+fn is_operator(role: Option<Role>) -> bool {
+    role.is_some_and(|role| role >= Role::Operator)
+}
+
+/**
+ * check the `$AUTH=<token>` line, the token is case sensitive
+ *
+ * @return the role of the sender to execute the line, or the reply for the auth line
+ * and for a line of a sender that is not authorized
+ */
+fn authorize(line: &str, auth: &Auth, role: &Mutex<Option<Role>>) -> Result<Role, GrblResult> {
+    let line = line.trim();
+    let is_auth_line = line
+        .get(..6)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("$AUTH="));
+    let mut role = role.lock().unwrap();
+    if is_auth_line {
+        let token_role = auth.role_of(Some(line[6..].trim()));
+        return Err(if is_operator(token_role) {
+            *role = token_role;
+            Ok(vec![])
+        } else {
+            Err(AUTH_FAILED)
+        });
+    }
+    match *role {
+        Some(role) if is_operator(Some(role)) => Ok(role),
+        _ => Err(Err(AUTH_FAILED)),
+    }
+}
+
+fn write_lines<W: Write>(writer: &Mutex<W>, lines: &[String]) {
+    let mut writer = writer.lock().unwrap();
+    for line in lines {
+        let _ = write!(writer, "{}\r\n", line);
+    }
+    let _ = writer.flush();
+}
+
+/// send a request to the app and wait for the reply. None if it was flushed by a reset
+fn request(requests: &Sender<GrblRequest>, command: GrblCommand) -> Option<GrblResult> {
+    let (reply, receiver) = bounded(1);
+    requests
+        .send(GrblRequest {
+            command,
+            reply: Some(reply),
+        })
+        .ok()?;
+    receiver.recv().ok()
+}
+
+fn execute_line(line: &str, requests: &Sender<GrblRequest>, role: Role) -> Option<GrblResult> {
+    let line = line.trim().to_uppercase();
+    let fixed = |lines: &[&str]| -> Option<GrblResult> {
+        Some(Ok(lines.iter().map(|l| l.to_string()).collect()))
+    };
+    match line.as_str() {
+        "" => fixed(&[]),
+        "$" => fixed(&["[HLP:$$ $# $G $I $N $x=val $J=line $X $H ~ ! ? ctrl-x]"]),
+        "$$" => request(requests, GrblCommand::ReadSettings),
+        "$#" => fixed(&[
+            "[G54:0.000,0.000,0.000]",
+            "[G55:0.000,0.000,0.000]",
+            "[G56:0.000,0.000,0.000]",
+            "[G57:0.000,0.000,0.000]",
+            "[G58:0.000,0.000,0.000]",
+            "[G59:0.000,0.000,0.000]",
+            "[G28:0.000,0.000,0.000]",
+            "[G30:0.000,0.000,0.000]",
+            "[G92:0.000,0.000,0.000]",
+            "[TLO:0.000]",
+            "[PRB:0.000,0.000,0.000:0]",
+        ]),
+        "$G" => request(requests, GrblCommand::ParserState),
+        "$I" => fixed(&["[VER:1.1h.20190825:]", "[OPT:V,15,128]"]),
+        "$N" => fixed(&["$N0=", "$N1="]),
+        // there is no alarm state to unlock
+        "$X" => fixed(&[]),
+        "$H" => request(requests, GrblCommand::Home),
+        _ if line.starts_with("$J=") => match parse_jog(&line[3..]) {
+            Ok(jog) => request(requests, GrblCommand::Jog(jog)),
+            Err(code) => Some(Err(code)),
+        },
+        _ if line.starts_with('$') => match parse_setting(&line[1..]) {
+            Some((number, value)) => {
+                request(requests, GrblCommand::WriteSetting(number, value, role))
+            }
+            // invalid or unsupported `$` statement
+            None => Some(Err(3)),
+        },
+        _ => request(requests, GrblCommand::Line(line)),
+    }
+}
+
+/// `n=value` of a `$n=value` line
+fn parse_setting(statement: &str) -> Option<(u32, f64)> {
+    let mut parts = statement.splitn(2, '=');
+    let number = parts.next()?.trim().parse().ok()?;
+    let value = parts.next()?.trim().parse().ok()?;
+    Some((number, value))
+}
+
+/**
+ * parse the words of a jog line
+ *
+ * @return the jog or the GRBL error code (2 bad number, 16 invalid jog, 22 no feed, 26 no axis)
+ */
+fn parse_jog(block: &str) -> Result<GrblJog, u8> {
+    let chars = block
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<char>>();
+    let mut jog = GrblJog::default();
+    let mut feed = None;
+    let mut scale = 1.0f64;
+    let mut words = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let letter = chars[i];
+        let start = i + 1;
+        i = start;
+        while i < chars.len() && (chars[i].is_ascii_digit() || "+-.".contains(chars[i])) {
+            i += 1;
+        }
+        let value = chars[start..i]
+            .iter()
+            .collect::<String>()
+            .parse::<f64>()
+            .map_err(|_| 2u8)?;
+        words.push((letter, value));
+    }
+    // the unit has to be known before the values are scaled
+    for (letter, value) in words.iter() {
+        match (*letter, *value as u32) {
+            ('G', 20) => scale = 25.4,
+            ('G', 21) => scale = 1.0,
+            _ => (),
+        }
+    }
+    for (letter, value) in words {
+        match letter {
+            'G' => match value as u32 {
+                20 | 21 => (),
+                90 => jog.relative = Some(false),
+                91 => jog.relative = Some(true),
+                // machine coordinates are the only coordinates
+                53 => (),
+                _ => return Err(16),
+            },
+            'X' => jog.x = Some(value * scale),
+            'Y' => jog.y = Some(value * scale),
+            'Z' => jog.z = Some(value * scale),
+            // rotary axis in degree
+            'A' => jog.a = Some(value),
+            'F' => feed = Some(value * scale),
+            _ => return Err(16),
+        }
+    }
+    jog.feed = feed.ok_or(22u8)?;
+    if jog.x.is_none() && jog.y.is_none() && jog.z.is_none() && jog.a.is_none() {
+        return Err(26);
+    }
+    Ok(jog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::auth::{AuthSettings, TokenSettings};
+    use crossbeam_channel::Receiver;
+    use std::{io::Cursor, time::Duration};
+
+    fn auth(enabled: bool) -> Auth {
+        let token = |token: &str, role| TokenSettings {
+            name: token.to_string(),
+            token: token.to_string(),
+            role,
+        };
+        Auth::new(AuthSettings {
+            enabled,
+            tokens: vec![
+                token("view", Role::Viewer),
+                token("op-token", Role::Operator),
+                token("admin", Role::Admin),
+            ],
+            ..AuthSettings::default()
+        })
+    }
+
+    /// answer the requests of a line with `ok` and keep the commands
+    fn answer(requests: Receiver<GrblRequest>) -> thread::JoinHandle<Vec<GrblCommand>> {
+        thread::spawn(move || {
+            let mut commands = vec![];
+            while let Ok(request) = requests.recv_timeout(Duration::from_millis(500)) {
+                if let Some(reply) = request.reply {
+                    let _ = reply.send(Ok(vec![]));
+                }
+                commands.push(request.command);
+            }
+            commands
+        })
+    }
+
+    /// output of a session, shared with the test
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn auth_line_sets_the_role_of_the_sender() {
+        let auth = auth(true);
+        let role = Mutex::new(auth.role_of(None));
+        assert_eq!(authorize("G0 X1", &auth, &role), Err(Err(AUTH_FAILED)));
+        assert_eq!(
+            authorize("$AUTH=wrong", &auth, &role),
+            Err(Err(AUTH_FAILED))
+        );
+        assert_eq!(authorize("$AUTH=view", &auth, &role), Err(Err(AUTH_FAILED)));
+        // the token is case sensitive, the command is not
+        assert_eq!(
+            authorize("$AUTH=OP-TOKEN", &auth, &role),
+            Err(Err(AUTH_FAILED))
+        );
+        assert_eq!(
+            authorize(" $auth= op-token ", &auth, &role),
+            Err(Ok(vec![]))
+        );
+        assert_eq!(authorize("G0 X1", &auth, &role), Ok(Role::Operator));
+        assert_eq!(authorize("$AUTH=admin", &auth, &role), Err(Ok(vec![])));
+        assert_eq!(authorize("$110=500", &auth, &role), Ok(Role::Admin));
+
+        let open = Auth::new(AuthSettings::default());
+        let role = Mutex::new(open.role_of(None));
+        assert_eq!(authorize("G0 X1", &open, &role), Ok(Role::Admin));
+    }
+
+    #[test]
+    fn realtime_bytes_are_executed_at_once() {
+        let (requests, received) = unbounded();
+        let answered = answer(received);
+        let output = Output::default();
+        start(
+            Cursor::new(b"?!~\x85G0 X1\n\x18".to_vec()),
+            output.clone(),
+            requests,
+            Arc::new(RwLock::new(GrblStatus::default())),
+            Arc::new(auth(false)),
+        );
+        let commands = answered.join().unwrap();
+
+        let realtime = commands
+            .iter()
+            .filter(|c| !matches!(c, GrblCommand::Line(_)))
+            .cloned()
+            .collect::<Vec<GrblCommand>>();
+        assert_eq!(
+            realtime,
+            vec![
+                GrblCommand::FeedHold,
+                GrblCommand::CycleStart,
+                GrblCommand::JogCancel,
+                GrblCommand::Reset,
+                GrblCommand::Disconnected
+            ]
+        );
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("<Idle|MPos:0.000,0.000,0.000"));
+        assert_eq!(output.matches(WELCOME).count(), 2);
+    }
+
+    #[test]
+    fn realtime_bytes_of_an_unauthorized_sender_are_ignored() {
+        let (requests, received) = unbounded();
+        let answered = answer(received);
+        start(
+            Cursor::new(b"!~\x18".to_vec()),
+            Output::default(),
+            requests,
+            Arc::new(RwLock::new(GrblStatus::default())),
+            Arc::new(auth(true)),
+        );
+        assert!(answered.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn settings_are_parsed_and_sent_with_the_role() {
+        assert_eq!(parse_setting("110=5000"), Some((110, 5000.0)));
+        assert_eq!(parse_setting(" 12 = 0.01 "), Some((12, 0.01)));
+        assert_eq!(parse_setting("110"), None);
+        assert_eq!(parse_setting("X=1"), None);
+        assert_eq!(parse_setting("110=fast"), None);
+
+        let (requests, received) = unbounded();
+        let answered = answer(received);
+        assert_eq!(
+            execute_line("$110=5000", &requests, Role::Operator),
+            Some(Ok(vec![]))
+        );
+        assert_eq!(
+            execute_line("$nope", &requests, Role::Operator),
+            Some(Err(3))
+        );
+        drop(requests);
+        assert_eq!(
+            answered.join().unwrap(),
+            vec![GrblCommand::WriteSetting(110, 5000.0, Role::Operator)]
+        );
+    }
+
+    #[test]
+    fn jog_lines_are_parsed_in_mm() {
+        let jog = parse_jog("G91 X10 Y-2.5 F100").unwrap();
+        assert_eq!(jog.relative, Some(true));
+        assert_eq!((jog.x, jog.y, jog.z), (Some(10.0), Some(-2.5), None));
+        assert_eq!(jog.feed, 100.0);
+        let inch = parse_jog("G20G90Z1F10").unwrap();
+        assert_eq!(
+            (inch.z, inch.feed, inch.relative),
+            (Some(25.4), 254.0, Some(false))
+        );
+        assert_eq!(parse_jog("X1"), Err(22));
+        assert_eq!(parse_jog("G91F100"), Err(26));
+        assert_eq!(parse_jog("G1X1F100"), Err(16));
+        assert_eq!(parse_jog("X1..2F100"), Err(2));
+    }
+}
@@ -0,0 +1,229 @@
+#![allow(dead_code)]
+use crate::types::Location;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// raw state of a XHC style pendant (WHB04B) sent with every encoder or switch change
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandwheelReport {
+    /// position of the axis selector
+    pub axis_code: u8,
+    /// position of the step / feed selector
+    pub step_code: u8,
+    /// encoder counts since the last report
+    pub delta: i8,
+}
+
+/// source of handwheel reports. Polled without blocking from the app loop
+pub trait HandwheelSource: Send {
+    fn next_report(&mut self) -> Option<HandwheelReport>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HandwheelAxis {
+    X,
+    Y,
+    Z,
+    A,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandwheelAxisBinding {
+    pub code: u8,
+    pub axis: HandwheelAxis,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandwheelStepBinding {
+    pub code: u8,
+    /// [mm] (or [deg] for A) per encoder count
+    pub step: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandwheelSettings {
+    pub enabled: bool,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    // [mm/min]
+    pub speed: f64,
+    pub axes: Vec<HandwheelAxisBinding>,
+    pub steps: Vec<HandwheelStepBinding>,
+}
+
+impl Default for HandwheelSettings {
+    fn default() -> Self {
+        let axis = |code, axis| HandwheelAxisBinding { code, axis };
+        let step = |code, step| HandwheelStepBinding { code, step };
+        HandwheelSettings {
+            enabled: false,
+            vendor_id: 0x10ce,
+            product_id: 0xeb93,
+            speed: 600.0,
+            axes: vec![
+                axis(0x11, HandwheelAxis::X),
+                axis(0x12, HandwheelAxis::Y),
+                axis(0x13, HandwheelAxis::Z),
+                axis(0x14, HandwheelAxis::A),
+            ],
+            steps: vec![
+                step(0x0d, 0.001),
+                step(0x0e, 0.01),
+                step(0x0f, 0.1),
+                step(0x10, 1.0),
+            ],
+        }
+    }
+}
+
+impl HandwheelSettings {
+    /**
+     * map a report to a move
+     *
+     * @return axis and distance, None if the selectors are off or not mapped
+     */
+    pub fn map(&self, report: &HandwheelReport) -> Option<(HandwheelAxis, f64)> {
+        let axis = self.axes.iter().find(|a| a.code == report.axis_code)?.axis;
+        let step = self.steps.iter().find(|s| s.code == report.step_code)?.step;
+        if report.delta == 0 {
+            None
+        } else {
+            Some((axis, report.delta as f64 * step))
+        }
+    }
+    /**
+     * read all pending reports of the source
+     *
+     * @return the sum of their moves [mm] and the move of the A axis [deg]
+     */
+    pub fn collect(&self, source: &mut dyn HandwheelSource) -> (Location<f64>, f64) {
+        let mut delta = Location::<f64>::default();
+        let mut delta_a = 0.0f64;
+        while let Some(report) = source.next_report() {
+            match self.map(&report) {
+                Some((HandwheelAxis::X, d)) => delta.x += d,
+                Some((HandwheelAxis::Y, d)) => delta.y += d,
+                Some((HandwheelAxis::Z, d)) => delta.z += d,
+                Some((HandwheelAxis::A, d)) => delta_a += d,
+                None => (),
+            }
+        }
+        (delta, delta_a)
+    }
+}
+
+/// source fed by a channel, to drive the handwheel input without hardware
+#[derive(Debug)]
+pub struct MockHandwheel {
+    receiver: Receiver<HandwheelReport>,
+}
+
+impl MockHandwheel {
+    pub fn new() -> (Self, Sender<HandwheelReport>) {
+        let (sender, receiver) = channel();
+        (MockHandwheel { receiver }, sender)
+    }
+}
+
+impl HandwheelSource for MockHandwheel {
+    fn next_report(&mut self) -> Option<HandwheelReport> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// XHC WHB04B USB pendant, read by a thread with hidapi. Reconnects if the pendant is unplugged.
+#[cfg(feature = "handwheel")]
+pub struct XhcHandwheel {
+    receiver: Receiver<HandwheelReport>,
+}
+
+#[cfg(feature = "handwheel")]
+impl XhcHandwheel {
+    pub fn new(vendor_id: u16, product_id: u16) -> Self {
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || XhcHandwheel::read(vendor_id, product_id, sender));
+        XhcHandwheel { receiver }
+    }
+    fn read(vendor_id: u16, product_id: u16, sender: Sender<HandwheelReport>) {
+        use std::{thread, time::Duration};
+        loop {
+            let device = hidapi::HidApi::new()
+                .and_then(|api| api.open(vendor_id, product_id))
+                .map_err(|e| println!("handwheel not available: {:?}", e));
+            if let Ok(device) = device {
+                let mut buf = [0u8; 8];
+                // report: [0x04, seed, key 1, key 2, step, axis, delta, checksum]
+                while let Ok(n) = device.read(&mut buf) {
+                    if n >= 7 && buf[0] == 0x04 && buf[6] != 0 {
+                        let report = HandwheelReport {
+                            step_code: buf[4],
+                            axis_code: buf[5],
+                            delta: buf[6] as i8,
+                        };
+                        if sender.send(report).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+}
+
+#[cfg(feature = "handwheel")]
+impl HandwheelSource for XhcHandwheel {
+    fn next_report(&mut self) -> Option<HandwheelReport> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(axis_code: u8, step_code: u8, delta: i8) -> HandwheelReport {
+        HandwheelReport {
+            axis_code,
+            step_code,
+            delta,
+        }
+    }
+
+    #[test]
+    fn map_ignores_unmapped_selectors() {
+        let settings = HandwheelSettings::default();
+        assert_eq!(settings.map(&report(0x00, 0x0e, 3)), None);
+        assert_eq!(settings.map(&report(0x11, 0x00, 3)), None);
+        assert_eq!(settings.map(&report(0x11, 0x0e, 0)), None);
+        assert_eq!(
+            settings.map(&report(0x14, 0x10, -2)),
+            Some((HandwheelAxis::A, -2.0))
+        );
+    }
+
+    #[test]
+    fn collect_sums_all_pending_reports() {
+        let settings = HandwheelSettings::default();
+        let (mut handwheel, sender) = MockHandwheel::new();
+        for _ in 0..3 {
+            sender.send(report(0x11, 0x0e, 1)).unwrap();
+        }
+        sender.send(report(0x12, 0x0f, -2)).unwrap();
+        sender.send(report(0x13, 0x00, 5)).unwrap();
+        sender.send(report(0x14, 0x10, 4)).unwrap();
+
+        let (delta, delta_a) = settings.collect(&mut handwheel);
+        assert!((delta.x - 0.03).abs() < 1e-9);
+        assert!((delta.y + 0.2).abs() < 1e-9);
+        assert_eq!(delta.z, 0.0);
+        assert_eq!(delta_a, 4.0);
+
+        // the reports are consumed
+        assert_eq!(settings.collect(&mut handwheel).1, 0.0);
+    }
+}
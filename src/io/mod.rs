@@ -1,4 +1,5 @@
 pub mod actor;
+pub mod handwheel;
 pub mod switch;
 
 pub use actor::*;
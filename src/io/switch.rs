@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 use rppal::gpio::{Gpio, InputPin, Level};
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum SwitchState {
     OPEN,
@@ -1,7 +1,9 @@
 mod app;
 mod gnc;
+mod grbl;
 mod io;
 mod motor;
+mod mqtt;
 mod types;
 mod ui;
 
@@ -0,0 +1,99 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// time one loop of the motor controller thread takes on the virtual clock
+const VIRTUAL_TICK: Duration = Duration::from_micros(10);
+
+#[derive(Debug)]
+pub struct VirtualClock {
+    start: Instant,
+    // [ns] since start
+    elapsed: AtomicU64,
+    stopped: AtomicBool,
+}
+
+/// time source of the motors and the motor controller thread.
+/// The virtual clock only moves forward while a task runs, so a simulation runs as fast as possible
+#[derive(Clone, Debug, Default)]
+pub enum Clock {
+    #[default]
+    Real,
+    Virtual(Arc<VirtualClock>),
+}
+
+impl Clock {
+    pub fn new_virtual() -> Self {
+        Clock::Virtual(Arc::new(VirtualClock {
+            start: Instant::now(),
+            elapsed: AtomicU64::new(0),
+            stopped: AtomicBool::new(false),
+        }))
+    }
+    pub fn is_virtual(&self) -> bool {
+        matches!(self, Clock::Virtual(_))
+    }
+    pub fn now(&self) -> Instant {
+        match self {
+            Clock::Real => Instant::now(),
+            Clock::Virtual(clock) => {
+                clock.start + Duration::from_nanos(clock.elapsed.load(Relaxed))
+            }
+        }
+    }
+    pub fn elapsed(&self, since: Instant) -> Duration {
+        self.now().saturating_duration_since(since)
+    }
+    /// time since the virtual clock was created. Always zero for the real clock
+    pub fn virtual_time(&self) -> Duration {
+        match self {
+            Clock::Real => Duration::default(),
+            Clock::Virtual(clock) => Duration::from_nanos(clock.elapsed.load(Relaxed)),
+        }
+    }
+    pub fn sleep(&self, duration: Duration) {
+        match self {
+            Clock::Real => thread::sleep(duration),
+            Clock::Virtual(clock) => {
+                clock.elapsed.fetch_add(duration.as_nanos() as u64, Relaxed);
+            }
+        }
+    }
+    /// wait for something else than a move. The virtual clock stands still meanwhile
+    pub fn idle(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+    /// busy wait for the real clock, jump to the time for the virtual clock
+    pub fn wait_until(&self, at: Instant) {
+        match self {
+            Clock::Real => while Instant::now() < at {},
+            Clock::Virtual(clock) => {
+                let at = at.saturating_duration_since(clock.start).as_nanos() as u64;
+                clock.elapsed.fetch_max(at, Relaxed);
+            }
+        }
+    }
+    /// one loop of a running task. The real clock moves on its own
+    pub fn tick(&self) {
+        if let Clock::Virtual(_) = self {
+            self.sleep(VIRTUAL_TICK);
+        }
+    }
+    /// end the motor controller thread running on this clock. The real clock never stops
+    pub fn stop(&self) {
+        if let Clock::Virtual(clock) = self {
+            clock.stopped.store(true, Relaxed);
+        }
+    }
+    pub fn is_stopped(&self) -> bool {
+        match self {
+            Clock::Real => false,
+            Clock::Virtual(clock) => clock.stopped.load(Relaxed),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::time::Duration;
+
+/// counters of one motor, written by the motor controller thread
+#[derive(Debug, Default)]
+pub struct MotorMetrics {
+    pub steps: AtomicU64,
+    /// steps refused by the driver (end switch)
+    pub blocked: AtomicU64,
+    /// [ns] largest delay of a step behind its planned time
+    pub max_jitter_ns: AtomicU64,
+}
+
+impl MotorMetrics {
+    pub fn add_jitter(&self, late: Duration) {
+        self.max_jitter_ns
+            .fetch_max(late.as_nanos() as u64, Relaxed);
+    }
+}
@@ -1,12 +1,17 @@
 #![allow(dead_code)]
+pub mod clock;
+pub mod metrics;
 pub mod mock_motor;
 pub mod motor_controller;
 pub mod motor_controller_thread;
+pub mod pulse;
+pub mod recording;
+pub mod slaved_driver;
 pub mod step_motor;
 pub mod task;
 
 use crate::types::Direction;
-use log::{max_level, LevelFilter};
+use log::{debug, max_level, trace, LevelFilter};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::Debug,
@@ -16,10 +21,15 @@ use std::{
         atomic::{AtomicI64, Ordering::Relaxed},
         Arc,
     },
-    time::SystemTime,
+    time::{Duration, Instant},
 };
 
+pub use clock::Clock;
+pub use metrics::MotorMetrics;
 pub use mock_motor::MockMotor;
+pub use pulse::{PulseDriver, ThreadedPulseBackend};
+pub use recording::{Recorder, Recording};
+pub use slaved_driver::SlavedDriver;
 pub use step_motor::StepMotor;
 pub type Result<T> = result::Result<T, &'static str>;
 
@@ -45,12 +55,74 @@ pub struct MotorSettings {
     pub free_step_speed: f64,
     // value to adjust UI Graph
     pub acceleration_time_scale: f64,
+    // [mm] play of the lead screw, compensated on every direction change
+    #[serde(default)]
+    pub backlash: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PulseBackendType {
+    /// step directly from the motor controller thread
+    #[default]
+    Direct,
+    /// play precomputed step timelines on a dedicated thread
+    Threaded,
+}
+
+/// steps a buffered driver did not play, Right counts positive
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DroppedSteps {
+    /// steps counted in the position
+    pub steps: i64,
+    /// backlash compensation, not part of the position
+    pub compensation: i64,
+}
+
+impl DroppedSteps {
+    pub fn add(&mut self, direction: &Direction, compensation: bool) {
+        let step = match direction {
+            Direction::Left => -1,
+            Direction::Right => 1,
+        };
+        if compensation {
+            self.compensation += step;
+        } else {
+            self.steps += step;
+        }
+    }
 }
 
 pub trait Driver: std::fmt::Debug {
     fn do_step(&mut self, direction: &Direction) -> Result<Direction>;
+    /// schedule a step for the given time. Drivers without timing backend step immediately.
+    fn do_step_at(&mut self, direction: &Direction, _at: Instant) -> Result<Direction> {
+        self.do_step(direction)
+    }
+    /// schedule a step that takes up the backlash and is not counted in the position
+    fn do_compensation_step_at(&mut self, direction: &Direction, at: Instant) -> Result<Direction> {
+        self.do_step_at(direction, at)
+    }
+    /// true if the driver plays the steps on its own timing and `Motor::step` must not block
+    fn is_buffered(&self) -> bool {
+        false
+    }
     fn get_step_size(&self) -> f64;
     fn is_blocked(&mut self) -> Option<Direction>;
+    /// take the steps a buffered driver accepted but could not play, e.g. at an end switch
+    fn take_rejected(&mut self) -> DroppedSteps {
+        DroppedSteps::default()
+    }
+    /// drop the steps a buffered driver has not played yet
+    fn flush(&mut self) -> DroppedSteps {
+        DroppedSteps::default()
+    }
+    /// homing allows a slaved axis to step one side alone into its end switch
+    fn set_homing(&mut self, _homing: bool) {}
+    /// largest delay of a played step behind its deadline, for drivers with own timing
+    fn max_jitter(&self) -> Option<Duration> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -64,16 +136,23 @@ pub struct MotorInner {
 pub struct Motor {
     name: String,
     pos: Arc<AtomicI64>,
+    metrics: Arc<MotorMetrics>,
     step_size: f64,
     inner: Arc<Mutex<MotorInner>>,
     // for speed
     last_speed: f64,
-    t_last: SystemTime,
+    t_last: Instant,
+    clock: Clock,
     // values to ramp up the motor speed
     max_step_speed: f64, // [step / sec]
     acceleration: f64,
     acceleration_damping: f64,
     free_step_speed: f64, // [step / sec]
+    // backlash compensation
+    backlash_steps: u32,
+    /// position of the nut in the play, 0 at the left flank and backlash_steps at the right one.
+    /// None until the first step engages a flank
+    slack: Option<i64>,
 }
 
 impl Motor {
@@ -83,22 +162,28 @@ impl Motor {
         acceleration: f64,
         acceleration_damping: f64,
         free_step_speed: f64,
+        backlash: f64,
         driver: Box<dyn Driver + Send>,
     ) -> Self {
-        println!("{}", max_step_speed);
+        debug!("{} max speed {}", name, max_step_speed);
         Motor {
             name: name.clone(),
             pos: Arc::new(AtomicI64::new(0)),
+            metrics: Arc::new(MotorMetrics::default()),
             step_size: driver.get_step_size(),
             max_step_speed: max_step_speed / driver.get_step_size(),
 
             last_speed: free_step_speed,
-            t_last: SystemTime::now(),
+            t_last: Instant::now(),
+            clock: Clock::Real,
 
             acceleration,
             acceleration_damping,
             free_step_speed,
 
+            backlash_steps: (backlash / driver.get_step_size()).round().max(0.0) as u32,
+            slack: None,
+
             inner: Arc::new(Mutex::new(MotorInner {
                 name,
                 max_step_speed: max_step_speed as u64, // steps per second
@@ -106,10 +191,54 @@ impl Motor {
             })),
         }
     }
+    /// run the ramp on another clock, e.g. the virtual clock of a simulation
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.t_last = clock.now();
+        self.clock = clock;
+        self
+    }
     /**
      * @return The time the motor was blocked.
      */
     pub fn step(&mut self, direction: Direction) -> f64 {
+        let mut blocked = 0.0f64;
+
+        // take up the backlash first. These steps do not change the logical position
+        let flank = match direction {
+            Direction::Left => 0,
+            Direction::Right => self.backlash_steps as i64,
+        };
+        let compensation = self.slack.map_or(0, |slack| (flank - slack).abs());
+        // counted when submitted like the steps, refused pulses are given back
+        self.slack = Some(flank);
+        for _ in 0..compensation {
+            blocked += self.ramp_step(&direction, true).0;
+        }
+
+        let (step_blocked, res) = self.ramp_step(&direction, false);
+        match res {
+            Ok(Direction::Left) => {
+                if max_level() == LevelFilter::Debug {
+                    print!("-");
+                }
+                (*self.pos).fetch_sub(1, Relaxed);
+            }
+            Ok(Direction::Right) => {
+                if max_level() == LevelFilter::Debug {
+                    print!("+");
+                }
+                (*self.pos).fetch_add(1, Relaxed);
+            }
+            Err(_) => (),
+        };
+        blocked + step_blocked
+    }
+    /**
+     * step with the speed limited by the ramp
+     *
+     * @return The time the motor was blocked and the result of the driver.
+     */
+    fn ramp_step(&mut self, direction: &Direction, compensation: bool) -> (f64, Result<Direction>) {
         // block motor to have a smooth ramp
         // this will slow slow down all motors, because all motors run in one thread
 
@@ -125,22 +254,31 @@ impl Motor {
         let min_delta_t = 1.0f64 / max_speed;
 
         // delta T to last step (speed the motor/program request to run. (needs to be decelerated if it is faster than allowed))
-        let d_t = self.t_last.elapsed().unwrap().as_secs_f64();
+        // t_last could be in the future if the driver buffers the steps
+        let now = self.clock.now();
+        let d_t = now.saturating_duration_since(self.t_last).as_secs_f64();
+        let buffered = self.inner.lock().unwrap().driver.is_buffered();
 
         // block if the required wait time is larger the the elapsed time
-        let blocked = if min_delta_t > d_t {
+        let (blocked, step_at) = if min_delta_t > d_t {
             let required_wait_for = min_delta_t - d_t;
-            while min_delta_t- 0.000005  > self.t_last.elapsed().unwrap().as_secs_f64() {
-
+            let step_at = self.t_last + Duration::from_secs_f64(min_delta_t);
+            // a buffered driver gets the step scheduled instead of waiting for it
+            if !buffered {
+                self.clock.wait_until(
+                    self.t_last + Duration::from_secs_f64((min_delta_t - 0.000005).max(0.0)),
+                );
+                self.metrics
+                    .add_jitter(self.clock.now().saturating_duration_since(step_at));
             }
             self.last_speed = max_speed;
-            required_wait_for
+            (required_wait_for, step_at)
         } else {
             self.last_speed = 1.0 / d_t;
-            0.0f64
+            (0.0f64, now)
         };
 
-        println!(
+        trace!(
             "{} ls: {:1.6}   max: {:3.2}   d_t: {:1.6}   bl: {:1.6}",
             self.name,
             self.last_speed * self.step_size,
@@ -153,34 +291,77 @@ impl Motor {
         //     current_speed_st_p_s,
         //     max_speed,
         // );
-        self.t_last = SystemTime::now();
+        self.t_last = if buffered { step_at } else { self.clock.now() };
 
-        // do step now
+        // do step now (or schedule it)
 
-        match (*self.inner.lock().unwrap().driver).do_step(&direction) {
-            Ok(Direction::Left) => {
-                if max_level() == LevelFilter::Debug {
-                    print!("-");
-                }
-                (*self.pos).fetch_sub(1, Relaxed);
-            }
-            Ok(Direction::Right) => {
-                if max_level() == LevelFilter::Debug {
-                    print!("+");
-                }
-                (*self.pos).fetch_add(1, Relaxed);
+        let (res, rejected) = {
+            let mut inner = self.inner.lock().unwrap();
+            let res = if compensation {
+                inner.driver.do_compensation_step_at(direction, step_at)
+            } else {
+                inner.driver.do_step_at(direction, step_at)
+            };
+            // a buffered driver measures the delay where the step is played
+            if let Some(late) = inner.driver.max_jitter() {
+                self.metrics.add_jitter(late);
             }
-            Err(_) => (),
+            (res, inner.driver.take_rejected())
+        };
+        self.correct_rejected(rejected);
+        match res {
+            Ok(_) => self.metrics.steps.fetch_add(1, Relaxed),
+            Err(_) => self.metrics.blocked.fetch_add(1, Relaxed),
         };
-        blocked
+        (blocked, res)
     }
     pub fn is_blocked(&mut self) -> Option<Direction> {
         (*self.inner.lock().unwrap().driver).is_blocked()
     }
+    pub fn set_homing(&mut self, homing: bool) {
+        (*self.inner.lock().unwrap().driver).set_homing(homing);
+    }
+    /// the steps and compensation pulses a buffered driver did not play were counted already
+    fn correct_dropped(&mut self, dropped: DroppedSteps) {
+        (*self.pos).fetch_sub(dropped.steps, Relaxed);
+        if let Some(slack) = self.slack.as_mut() {
+            *slack = (*slack - dropped.compensation).clamp(0, self.backlash_steps as i64);
+        }
+    }
+    fn correct_rejected(&mut self, rejected: DroppedSteps) {
+        if rejected != DroppedSteps::default() {
+            self.correct_dropped(rejected);
+            let count = rejected.steps.unsigned_abs() + rejected.compensation.unsigned_abs();
+            self.metrics.steps.fetch_sub(count, Relaxed);
+            self.metrics.blocked.fetch_add(count, Relaxed);
+        }
+    }
+    /// stop the steps scheduled on a buffered driver. The position goes back by the dropped steps
+    pub fn flush(&mut self) {
+        let (dropped, rejected) = {
+            let mut inner = self.inner.lock().unwrap();
+            let dropped = inner.driver.flush();
+            (dropped, inner.driver.take_rejected())
+        };
+        self.correct_rejected(rejected);
+        self.correct_dropped(dropped);
+        // the next step ramps up from standstill
+        self.last_speed = self.free_step_speed;
+        self.t_last = self.clock.now();
+    }
     pub fn get_pos_ref(&self) -> Arc<AtomicI64> {
         self.pos.clone()
     }
+    pub fn get_metrics(&self) -> Arc<MotorMetrics> {
+        self.metrics.clone()
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
     pub fn get_step_size(&self) -> f64 {
         self.step_size
     }
+    pub fn get_clock(&self) -> Clock {
+        self.clock.clone()
+    }
 }
@@ -1,7 +1,10 @@
-use super::{motor_controller_thread::MotorControllerThread, task::CalibrateType, Motor};
+use super::{
+    motor_controller_thread::MotorControllerThread, task::CalibrateType, Motor, MotorMetrics,
+    Recorder,
+};
 
 use super::{
-    task::{ManualInstruction, ManualTask, Task},
+    task::{ManualInstruction, ManualStepTask, ManualTask, Task},
     Result,
 };
 use crate::gnc::{Next3dMovement, NextMiscellaneous};
@@ -37,6 +40,7 @@ pub enum ExternalInputRequest {
 pub struct MotorController {
     thread: thread::JoinHandle<()>,
     cancel_task: Arc<AtomicBool>,
+    pause: Arc<AtomicBool>,
     state: Arc<AtomicU32>,
     task_query: Arc<Mutex<Vec<Task>>>,
     manual_instruction_sender: Sender<ManualInstruction>,
@@ -46,7 +50,10 @@ pub struct MotorController {
     x: Arc<AtomicI64>,
     y: Arc<AtomicI64>,
     z: Arc<AtomicI64>,
+    a: Arc<AtomicI64>,
+    step_size_a: Option<f64>,
     on_off_state: Arc<AtomicBool>,
+    motor_metrics: Vec<(String, Arc<MotorMetrics>)>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -57,6 +64,7 @@ impl MotorController {
         motor_x: Motor,
         motor_y: Motor,
         motor_z: Motor,
+        motor_a: Option<Motor>,
         z_calibrate: Option<Switch>,
 
         external_input_enabled: bool,
@@ -64,6 +72,7 @@ impl MotorController {
         external_input_request_sender: Sender<ExternalInputRequest>,
     ) -> Self {
         let cancel_task = Arc::new(AtomicBool::new(false));
+        let pause = Arc::new(AtomicBool::new(false));
         let state = Arc::new(AtomicU32::new(0));
         let steps_todo = Arc::new(AtomicI64::new(0));
         let steps_done = Arc::new(AtomicI64::new(0));
@@ -81,21 +90,47 @@ impl MotorController {
         let x = motor_x.get_pos_ref();
         let y = motor_y.get_pos_ref();
         let z = motor_z.get_pos_ref();
+        // without A axis the position stays 0
+        let a = motor_a
+            .as_ref()
+            .map(|m| m.get_pos_ref())
+            .unwrap_or_else(|| Arc::new(AtomicI64::new(0)));
+        let step_size_a = motor_a.as_ref().map(|m| m.get_step_size());
+        let motors = [
+            Some(&motor_x),
+            Some(&motor_y),
+            Some(&motor_z),
+            motor_a.as_ref(),
+        ];
+        let motor_metrics = motors
+            .iter()
+            .flatten()
+            .map(|m| (m.get_name().to_string(), m.get_metrics()))
+            .collect();
+        let a_inner = a.clone();
         let state_inner = state.clone();
         let steps_todo_inner = steps_todo.clone();
         let steps_done_inner = steps_done.clone();
         let on_off_state_inner = on_off_state.clone();
         let cancel_task_inner = cancel_task.clone();
+        let pause_inner = pause.clone();
         let task_query_inner = task_query.clone();
+        // the controller runs on the clock of its motors
+        let clock = motor_x.get_clock();
         let thread = std::thread::spawn(move || {
-            set_current_thread_priority(ThreadPriority::Max).unwrap();
+            // a simulation must not take the CPU from the real controller
+            if !clock.is_virtual() {
+                set_current_thread_priority(ThreadPriority::Max).unwrap();
+            }
             let mut inner = MotorControllerThread::new(
                 motor_x.get_pos_ref(),
                 motor_y.get_pos_ref(),
                 motor_z.get_pos_ref(),
+                a_inner,
                 motor_x,
                 motor_y,
                 motor_z,
+                motor_a,
                 z_calibrate,
                 None,
                 Location::default(),
@@ -103,6 +138,7 @@ impl MotorController {
                 steps_todo_inner,
                 steps_done_inner,
                 cancel_task_inner,
+                pause_inner,
                 task_query_inner,
                 receive_manual_instruction,
                 external_input_enabled,
@@ -111,7 +147,8 @@ impl MotorController {
                 on_off_state_inner,
                 on_off,
                 switch_on_off_delay,
-            );
+            )
+            .with_clock(clock);
 
             inner.run();
         });
@@ -122,13 +159,17 @@ impl MotorController {
             steps_todo,
             steps_done,
             cancel_task,
+            pause,
             step_sizes,
             x,
             y,
             z,
+            a,
+            step_size_a,
             task_query,
             manual_instruction_sender,
             on_off_state,
+            motor_metrics,
         }
     }
     pub fn query_g_task(&mut self, task: Next3dMovement) {
@@ -159,9 +200,31 @@ impl MotorController {
     pub fn get_steps_done(&self) -> i64 {
         self.steps_done.load(Relaxed)
     }
+    /// tasks in the queue that are not started yet
+    pub fn get_queued(&self) -> i64 {
+        let len = self.task_query.lock().unwrap().len() as i64;
+        (len - self.steps_done.load(Relaxed) - 1).max(0)
+    }
+    /// step counters of the motors by axis name
+    pub fn get_motor_metrics(&self) -> Vec<(String, Arc<MotorMetrics>)> {
+        self.motor_metrics.clone()
+    }
     pub fn is_switched_on(&self) -> bool {
         self.on_off_state.load(Relaxed)
     }
+    /// all queued tasks are done
+    pub fn is_idle(&self) -> bool {
+        let queue = self.task_query.lock().unwrap();
+        queue.is_empty() && self.get_state() == MachineState::Idle
+    }
+    /// log the state changes of this controller in the timeline of a simulation
+    pub fn attach_recorder(&self, recorder: &Recorder) {
+        recorder.watch(
+            self.state.clone(),
+            self.on_off_state.clone(),
+            self.steps_done.clone(),
+        );
+    }
     pub fn manual_move(&mut self, x: f64, y: f64, z: f64, speed_mm_min: f64) {
         if self
             .manual_instruction_sender
@@ -176,6 +239,20 @@ impl MotorController {
             println!("can't send manual move");
         }
     }
+    /// move exactly the given distance [mm]
+    pub fn manual_step(&mut self, x: f64, y: f64, z: f64, a: f64, speed_mm_min: f64) {
+        if self
+            .manual_instruction_sender
+            .send(ManualInstruction::Step(ManualStepTask {
+                delta: Location::new(x, y, z),
+                delta_a: a,
+                speed_mm_min,
+            }))
+            .is_err()
+        {
+            println!("can't send manual step");
+        }
+    }
     pub fn manual_miscellaneous(&mut self, task: NextMiscellaneous) {
         if self
             .manual_instruction_sender
@@ -185,8 +262,16 @@ impl MotorController {
             println!("can't send manual miscellaneous task");
         }
     }
+    /// feed hold. The current move stops and continues on resume
+    pub fn pause(&mut self, pause: bool) {
+        self.pause.store(pause, Relaxed);
+    }
+    pub fn is_paused(&self) -> bool {
+        self.pause.load(Relaxed)
+    }
     pub fn cancel_task(&mut self) -> Result<()> {
         self.task_query.lock().unwrap().clear();
+        self.pause.store(false, Relaxed);
         self.cancel_task.store(true, Relaxed);
         while self.cancel_task.load(Relaxed) {
             thread::sleep(Duration::from_nanos(100));
@@ -194,7 +279,8 @@ impl MotorController {
         Ok(())
     }
     pub fn reset(&mut self) {
-        self.set_pos(Location::default())
+        self.set_pos(Location::default());
+        self.a.store(0, Relaxed);
     }
     pub fn set_pos(&mut self, pos: Location<f64>) {
         let steps: Location<i64> = (pos / self.step_sizes.clone()).into();
@@ -213,4 +299,9 @@ impl MotorController {
         let pos: Location<f64> = self.motor_pos().into();
         pos * self.step_sizes.clone()
     }
+    /// angle of the A axis [deg]. None if the machine has no A axis
+    pub fn get_pos_a(&self) -> Option<f64> {
+        self.step_size_a
+            .map(|step_size| self.a.load(Relaxed) as f64 * step_size)
+    }
 }
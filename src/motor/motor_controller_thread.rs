@@ -3,14 +3,14 @@ use super::motor_controller::{ExternalInput, ExternalInputRequest};
 use super::task::{
     CalibrateType, InnerTask, InnerTaskCalibrate, InnerTaskProduction, ManualInstruction, Task,
 };
-use super::Motor;
+use super::{Clock, Motor};
 use crate::gnc::NextMiscellaneous;
 use crate::io::{Actor, Switch};
 use crate::types::{
-    CircleDirection, CircleStep, CircleStepCCW, CircleStepCW, CircleStepDir, Direction, Location,
-    MachineState, SteppedCircleMovement, SteppedLinearMovement,
-    SteppedMoveType::{Circle, Linear, Rapid},
+    Direction, Location, MachineState, SteppedLinearMovement,
+    SteppedMoveType::{Linear, Rapid},
 };
+use log::trace;
 use std::{
     fmt::Debug,
     ops::{Div, Mul},
@@ -20,8 +20,7 @@ use std::{
         mpsc::{Receiver, Sender},
         Arc,
     },
-    thread,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant},
 };
 
 #[derive(Debug)]
@@ -29,16 +28,19 @@ pub struct MotorControllerThread {
     motor_x: Motor,
     motor_y: Motor,
     motor_z: Motor,
+    motor_a: Option<Motor>,
 
     z_calibrate: Option<Switch>,
 
     x_step: Arc<AtomicI64>,
     y_step: Arc<AtomicI64>,
     z_step: Arc<AtomicI64>,
+    a_step: Arc<AtomicI64>,
 
     current_task: Option<InnerTask>,
     current_location: Location<f64>,
     cancel_task: Arc<AtomicBool>,
+    pause: Arc<AtomicBool>,
     state: Arc<AtomicU32>,
     steps_todo: Arc<AtomicI64>,
     steps_done: Arc<AtomicI64>,
@@ -53,6 +55,8 @@ pub struct MotorControllerThread {
     external_input_required: bool,
     external_input_receiver: Receiver<ExternalInput>,
     external_input_request_sender: Sender<ExternalInputRequest>,
+
+    clock: Clock,
 }
 
 impl MotorControllerThread {
@@ -60,9 +64,11 @@ impl MotorControllerThread {
         x_step: Arc<AtomicI64>,
         y_step: Arc<AtomicI64>,
         z_step: Arc<AtomicI64>,
+        a_step: Arc<AtomicI64>,
         motor_x: Motor,
         motor_y: Motor,
         motor_z: Motor,
+        motor_a: Option<Motor>,
         z_calibrate: Option<Switch>,
         current_task: Option<InnerTask>,
         current_location: Location<f64>,
@@ -70,6 +76,7 @@ impl MotorControllerThread {
         steps_todo: Arc<AtomicI64>,
         steps_done: Arc<AtomicI64>,
         cancel_task: Arc<AtomicBool>,
+        pause: Arc<AtomicBool>,
         task_query: Arc<Mutex<Vec<Task>>>,
         manual_instruction_receiver: Receiver<ManualInstruction>,
         external_input_enabled: bool,
@@ -83,9 +90,11 @@ impl MotorControllerThread {
             x_step,
             y_step,
             z_step,
+            a_step,
             motor_x,
             motor_y,
             motor_z,
+            motor_a,
             z_calibrate,
             current_task,
             current_location,
@@ -93,6 +102,7 @@ impl MotorControllerThread {
             steps_todo,
             steps_done,
             cancel_task,
+            pause,
             task_query,
             manual_instruction_receiver,
             on_off_state,
@@ -102,8 +112,14 @@ impl MotorControllerThread {
             external_input_required: false,
             external_input_receiver,
             external_input_request_sender,
+            clock: Clock::Real,
         }
     }
+    /// run on the clock of the motors
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
     pub fn get_pos(&self) -> Location<i64> {
         Location {
             x: self.x_step.load(Relaxed),
@@ -111,6 +127,16 @@ impl MotorControllerThread {
             z: self.z_step.load(Relaxed),
         }
     }
+    pub fn get_pos_a(&self) -> i64 {
+        self.a_step.load(Relaxed)
+    }
+    /// deg per step of the A axis. 0.0 without A axis
+    fn get_step_size_a(&self) -> f64 {
+        self.motor_a
+            .as_ref()
+            .map(|m| m.get_step_size())
+            .unwrap_or(0.0)
+    }
     fn get_step_sizes(&self) -> Location<f64> {
         Location {
             x: self.motor_x.get_step_size(),
@@ -119,61 +145,116 @@ impl MotorControllerThread {
         }
     }
     pub fn run(&mut self) {
-        let mut last_step: Option<SystemTime> = None; //SystemTime::now();
-        let mut curve_close_to_destination = false;
-        let mut last_distance_to_destination = 100;
         let mut q_ptr = 0;
 
         let mut stepper_delay = 0.0f64; // sec a motor is block during a linier move
 
         let mut calculate_z_phase = 0i32;
         let mut calibrate_z_pos_temp = Location::<i64>::default();
+        let mut paused_at: Option<Instant> = None;
+        let mut homing = false;
+        // [mm] and [deg] the running manual steps end at
+        let mut step_target: Option<(Location<f64>, f64)> = None;
 
         let program_task: u32 = MachineState::ProgramTask.into();
         let calibrate: u32 = MachineState::Calibrate.into();
 
         loop {
-            // read it but drop it to avoid a command jam after program or calibration completed
+            if self.clock.is_stopped() {
+                break;
+            }
+            if self.current_task.is_some() {
+                self.clock.tick();
+            }
+
+            // read it but drop the moves to avoid a command jam after program or calibration
+            // completed. The spindle is switched in every state, e.g. off during a feed hold
             let next_manual_task = self.manual_instruction_receiver.try_recv();
-            if self.state.load(Relaxed) != program_task && self.state.load(Relaxed) != calibrate {
-                match next_manual_task {
-                    Ok(ManualInstruction::Movement(next_task)) => {
-                        let max_speed = next_task.speed_mm_min;
-                        let task = Task::Manual(next_task);
-                        self.state.store(task.machine_state().into(), Relaxed);
-                        self.current_task = InnerTask::from_task(
-                            task,
-                            self.get_pos(),
-                            self.get_step_sizes(),
-                            max_speed,
-                        );
+            let running =
+                self.state.load(Relaxed) == program_task || self.state.load(Relaxed) == calibrate;
+            match next_manual_task {
+                Ok(ManualInstruction::Movement(next_task)) if !running => {
+                    step_target = None;
+                    let max_speed = next_task.speed_mm_min;
+                    let task = Task::Manual(next_task);
+                    self.state.store(task.machine_state().into(), Relaxed);
+                    self.current_task = InnerTask::from_task(
+                        task,
+                        self.get_pos(),
+                        self.get_step_sizes(),
+                        self.get_pos_a(),
+                        self.get_step_size_a(),
+                        max_speed,
+                        self.clock.now(),
+                    );
+                }
+                Ok(ManualInstruction::Step(mut next_task)) if !running => {
+                    // a step during the last one adds to its target, so no step is cut short
+                    let pos: Location<f64> = self.get_pos().into();
+                    let pos = pos * self.get_step_sizes();
+                    let pos_a = self.get_pos_a() as f64 * self.get_step_size_a();
+                    let (target, target_a) = match (&self.current_task, step_target.take()) {
+                        (Some(InnerTask::Production(_)), Some(target)) => target,
+                        _ => (pos.clone(), pos_a),
+                    };
+                    let target = target + next_task.delta.clone();
+                    let target_a = target_a + next_task.delta_a;
+                    next_task.delta = target.clone() - pos;
+                    next_task.delta_a = target_a - pos_a;
+                    step_target = Some((target, target_a));
+
+                    let max_speed = next_task.speed_mm_min;
+                    let task = Task::ManualStep(next_task);
+                    self.state.store(task.machine_state().into(), Relaxed);
+                    self.current_task = InnerTask::from_task(
+                        task,
+                        self.get_pos(),
+                        self.get_step_sizes(),
+                        self.get_pos_a(),
+                        self.get_step_size_a(),
+                        max_speed,
+                        self.clock.now(),
+                    );
+                }
+                Ok(ManualInstruction::Miscellaneous(next_miscellaneous)) => {
+                    match next_miscellaneous {
+                        NextMiscellaneous::SwitchOn => self.switch_on(),
+                        NextMiscellaneous::SwitchOff => self.switch_off(),
+                        _ => (),
                     }
-                    Ok(ManualInstruction::Miscellaneous(next_miscellaneous)) => {
-                        match next_miscellaneous {
-                            NextMiscellaneous::SwitchOn => {
-                                self.switch_on();
-                                self.current_task = None;
-                            }
-                            NextMiscellaneous::SwitchOff => {
-                                self.switch_off();
-                                self.current_task = None;
-                            }
-                            _ => (),
-                        }
+                    // a manual move stops, the task of a program continues
+                    if !running {
+                        self.current_task = None;
                     }
-                    Err(_) => (),
-                };
-            }
+                }
+                _ => (),
+            };
 
             // check flag to cancel current task
             if self.cancel_task.load(Relaxed) {
                 self.cancel_task.store(false, Relaxed);
                 self.current_task = None;
+                self.flush_motors();
                 self.steps_todo.store(0, Relaxed);
                 self.steps_done.store(0, Relaxed);
                 println!("MotorControllerThread: cancel task");
             };
 
+            // feed hold: keep the current task and continue it on a shifted timeline
+            if self.pause.load(Relaxed) {
+                if paused_at.is_none() {
+                    paused_at = Some(self.clock.now());
+                    // the task continues from the position the pulses really reached
+                    self.flush_motors();
+                }
+                self.clock.idle(Duration::new(0, 10_000));
+                continue;
+            } else if let Some(at) = paused_at.take() {
+                if let Some(InnerTask::Production(task)) = self.current_task.as_mut() {
+                    task.start_time += self.clock.elapsed(at);
+                }
+            }
+
             // check flag if machine wait for external input (tool change, new stock, turn stock, speed changed, ...)
             if self.external_input_required {
                 // try_recv() => sleep + continue; To keep the cancel task in the loop
@@ -190,27 +271,37 @@ impl MotorControllerThread {
                         self.external_input_required = false;
                     }
                     Err(_) => {
-                        thread::sleep(Duration::new(0, 10_000));
+                        self.clock.idle(Duration::new(0, 10_000));
                         continue;
                     }
                 }
             }
 
+            // a slaved axis steps one side alone only during the calibration
+            let calibrating = matches!(self.current_task, Some(InnerTask::Calibrate(_)));
+            if calibrating != homing {
+                homing = calibrating;
+                self.motor_x.set_homing(homing);
+                self.motor_y.set_homing(homing);
+            }
+
             match &self.current_task {
                 Some(InnerTask::Production(InnerTaskProduction {
                     start_time,
                     move_type,
                     from,
-                    destination,
+                    from_a,
                     ..
                 })) => match move_type {
                     Linear(SteppedLinearMovement {
                         delta,
+                        delta_a,
                         speed,
                         distance,
                     })
                     | Rapid(SteppedLinearMovement {
                         delta,
+                        delta_a,
                         speed,
                         distance,
                     }) => {
@@ -220,8 +311,12 @@ impl MotorControllerThread {
                         }
 
                         let already_moved_this_task = (self.get_pos() - from.clone()).abs();
+                        let already_moved_a = (self.get_pos_a() - from_a).unsigned_abs();
+                        let a = *delta_a;
 
-                        let complete_runtime = Duration::from_secs_f64(*distance / (*speed / 60.0f64)).as_micros() as u64;
+                        let complete_runtime =
+                            Duration::from_secs_f64(*distance / (*speed / 60.0f64)).as_micros()
+                                as u64;
 
                         // remove time waiting for the steppers to ramp-up.
                         // Other wise the rest of the track will try compensate it with driving to fast
@@ -230,148 +325,84 @@ impl MotorControllerThread {
 
                         // check if already arrived complete
                         // @TODO slow down ramp here??
-                        if delta.abs() == already_moved_this_task.clone() || (x == 0 && y == 0 && z == 0) {
+                        if (delta.abs() == already_moved_this_task.clone()
+                            && a.unsigned_abs() == already_moved_a)
+                            || (x == 0 && y == 0 && z == 0 && a == 0)
+                        {
                             self.current_task = None;
                             continue;
                         }
 
-                        let mut runtime = Self::calc_runtime(start_time.elapsed().unwrap().as_secs_f64(), &mut stepper_delay);
+                        let mut runtime = Self::calc_runtime(
+                            self.clock.elapsed(*start_time).as_secs_f64(),
+                            &mut stepper_delay,
+                        );
                         if x != 0
-                            && x.abs() as u64 != already_moved_this_task.x
-                            && runtime > complete_runtime / x.abs() as u64 * already_moved_this_task.x
+                            && x.unsigned_abs() != already_moved_this_task.x
+                            && runtime
+                                > complete_runtime / x.unsigned_abs() * already_moved_this_task.x
                         {
                             stepper_delay += self.motor_x.step(x.into());
                         }
 
-                        runtime = Self::calc_runtime(start_time.elapsed().unwrap().as_secs_f64(), &mut stepper_delay);
+                        runtime = Self::calc_runtime(
+                            self.clock.elapsed(*start_time).as_secs_f64(),
+                            &mut stepper_delay,
+                        );
                         if y != 0
-                            && y.abs() as u64 != already_moved_this_task.y
-                            && runtime > complete_runtime / y.abs() as u64 * already_moved_this_task.y
+                            && y.unsigned_abs() != already_moved_this_task.y
+                            && runtime
+                                > complete_runtime / y.unsigned_abs() * already_moved_this_task.y
                         {
                             stepper_delay += self.motor_y.step(y.into());
                         }
 
-                        runtime = Self::calc_runtime(start_time.elapsed().unwrap().as_secs_f64(), &mut stepper_delay);
+                        runtime = Self::calc_runtime(
+                            self.clock.elapsed(*start_time).as_secs_f64(),
+                            &mut stepper_delay,
+                        );
                         if z != 0
-                            && z.abs() as u64 != already_moved_this_task.z
-                            && runtime > complete_runtime / z.abs() as u64 * already_moved_this_task.z
+                            && z.unsigned_abs() != already_moved_this_task.z
+                            && runtime
+                                > complete_runtime / z.unsigned_abs() * already_moved_this_task.z
                         {
                             stepper_delay += self.motor_z.step(z.into());
                         }
-                    }
-                    Circle(SteppedCircleMovement {
-                        turn_direction,
-                        center,
-                        radius_sq,
-                        step_sizes,
-                        speed,
-                        step_delay,
-                        ..
-                    }) => {
-                        if last_step.is_some()
-                            && last_step.unwrap().elapsed().unwrap().as_secs_f64() <= *step_delay
-                        {
-                            continue;
-                        }
-                        last_step = Some(SystemTime::now());
 
-                        let abs_center: Location<i64> = from.clone() + center.clone();
-                        let rel_to_center = self.get_pos() - abs_center.clone();
-
-                        let step_dir: CircleStep = match turn_direction {
-                            CircleDirection::CW => {
-                                let next_step: CircleStepCW = rel_to_center.into();
-                                next_step.into()
-                            }
-                            CircleDirection::CCW => {
-                                let next_step: CircleStepCCW = rel_to_center.into();
-                                next_step.into()
-                            }
-                        };
-                        match step_dir.main {
-                            CircleStepDir::Right => self.motor_x.step(Direction::Right),
-                            CircleStepDir::Down => self.motor_y.step(Direction::Left),
-                            CircleStepDir::Left => self.motor_x.step(Direction::Left),
-                            CircleStepDir::Up => self.motor_y.step(Direction::Right),
-                        };
-                        let pos_before_move = self.get_pos();
-                        let delta_before_op: Location<f64> =
-                            (pos_before_move.clone() - abs_center.clone()).into();
-                        let delta_before_op_step_correct =
-                            delta_before_op.clone() * step_sizes.clone();
-                        let delta_radius_before_op =
-                            radius_sq - delta_before_op_step_correct.distance_sq();
-                        let pos_after_move = pos_before_move
-                            + match step_dir.opt {
-                                CircleStepDir::Right => Location::<i64>::new(1, 0, 0),
-                                CircleStepDir::Down => Location::<i64>::new(0, -1, 0),
-                                CircleStepDir::Left => Location::<i64>::new(-1, 0, 0),
-                                CircleStepDir::Up => Location::<i64>::new(0, 1, 0),
-                            };
-
-                        let delta_after_op: Location<f64> =
-                            (pos_after_move - abs_center.clone()).into();
-                        let delta_after_op_step_correct =
-                            delta_after_op.clone() * step_sizes.clone();
-                        let delta_radius_after_op =
-                            radius_sq - delta_after_op_step_correct.distance_sq();
-                        if delta_radius_before_op.abs() > delta_radius_after_op.abs() {
-                            match step_dir.opt {
-                                CircleStepDir::Right => self.motor_x.step(Direction::Right),
-                                CircleStepDir::Down => self.motor_y.step(Direction::Left),
-                                CircleStepDir::Left => self.motor_x.step(Direction::Left),
-                                CircleStepDir::Up => self.motor_y.step(Direction::Right),
-                            };
-                        }
-
-                        let dist_destination: Location<i64> = destination.clone() - self.get_pos();
-                        let dist_to_dest = dist_destination.clone().distance_sq();
-                        if dist_to_dest < 25 * 25 && !curve_close_to_destination {
-                            curve_close_to_destination = true;
-                        }
-
-                        if curve_close_to_destination && dist_to_dest > last_distance_to_destination
+                        runtime = Self::calc_runtime(
+                            self.clock.elapsed(*start_time).as_secs_f64(),
+                            &mut stepper_delay,
+                        );
+                        if a != 0
+                            && a.unsigned_abs() != already_moved_a
+                            && runtime > complete_runtime / a.unsigned_abs() * already_moved_a
                         {
-                            let dist_destination_f64: Location<f64> =
-                                dist_destination.clone().into();
-                            let distance = (dist_destination_f64 * step_sizes.clone()).distance();
-                            stepper_delay = 0.0f64;
-                            self.current_task = Some(InnerTask::Production(InnerTaskProduction {
-                                destination: destination.clone(),
-                                from: self.get_pos(),
-                                start_time: SystemTime::now(),
-                                move_type: Linear(SteppedLinearMovement {
-                                    delta: dist_destination.clone(),
-                                    distance,
-                                    speed: *speed,
-                                }),
-                            }));
-                            curve_close_to_destination = false;
-                            last_distance_to_destination = 100;
-                        } else if curve_close_to_destination {
-                            last_distance_to_destination = dist_to_dest;
-                        }
-
-                        if dist_destination.distance_sq() == 0 {
-                            curve_close_to_destination = false;
-                            last_distance_to_destination = 100;
-                            println!("at destination, set currentTask to NONE");
-                            self.current_task = None;
+                            if let Some(motor_a) = self.motor_a.as_mut() {
+                                stepper_delay += motor_a.step(a.into());
+                            }
                         }
                     }
                 },
                 Some(InnerTask::Calibrate(InnerTaskCalibrate {
+                    x,
+                    y,
                     z,
                     from,
                     start_time,
                     step_sizes,
-                    ..
                 })) => {
-                    let runtime = start_time.elapsed().unwrap().as_micros() as u64;
+                    let runtime = self.clock.elapsed(*start_time).as_micros() as u64;
                     let move_in_task = (self.get_pos() - from.clone()).abs();
-                    let z_steps = move_in_task.z;
+                    let calibrate_steps = move_in_task.x + move_in_task.y + move_in_task.z;
+
+                    if runtime > calibrate_steps * 4_000 {
+                        // home x and y first. A slaved axis latches each side on its own switch
+                        let x_homed = Self::home_axis(&mut self.motor_x, x);
+                        let y_homed = Self::home_axis(&mut self.motor_y, y);
+                        if !x_homed || !y_homed {
+                            continue;
+                        }
 
-                    if runtime > z_steps * 4_000 {
                         match z {
                             CalibrateType::Min => {
                                 if self.motor_z.is_blocked() == Some(Direction::Left) {
@@ -412,9 +443,11 @@ impl MotorControllerThread {
                                                 Some(InnerTask::Production(InnerTaskProduction {
                                                     destination: delta.clone(),
                                                     from: self.get_pos(),
-                                                    start_time: SystemTime::now(),
+                                                    from_a: self.get_pos_a(),
+                                                    start_time: self.clock.now(),
                                                     move_type: Linear(SteppedLinearMovement {
                                                         delta, // or (calibrate_z_pos_temp - self.get_pos()).div(2)
+                                                        delta_a: 0,
                                                         distance,
                                                         speed: 360.0f64,
                                                     }),
@@ -438,19 +471,21 @@ impl MotorControllerThread {
                                     self.motor_z.step(Direction::Right);
                                 };
                             }
-                            CalibrateType::None => (),
+                            CalibrateType::None => self.current_task = None,
                         }
                     }
                 }
                 Some(InnerTask::Miscellaneous(hardware_task)) => match hardware_task {
                     NextMiscellaneous::SwitchOn => {
                         self.switch_on();
-                        thread::sleep(Duration::from_secs_f64(self.switch_on_off_delay));
+                        self.clock
+                            .sleep(Duration::from_secs_f64(self.switch_on_off_delay));
                         self.current_task = None;
                     }
                     NextMiscellaneous::SwitchOff => {
                         self.switch_off();
-                        thread::sleep(Duration::from_secs_f64(self.switch_on_off_delay));
+                        self.clock
+                            .sleep(Duration::from_secs_f64(self.switch_on_off_delay));
                         self.current_task = None;
                     }
                     NextMiscellaneous::ToolChange(tool) if self.external_input_enabled => {
@@ -458,18 +493,23 @@ impl MotorControllerThread {
                         self.external_input_request_sender
                             .send(ExternalInputRequest::ChangeTool(*tool))
                             .unwrap();
+                        self.current_task = None;
                     }
                     NextMiscellaneous::SpeedChange(speed) if self.external_input_enabled => {
                         self.external_input_required = true;
                         self.external_input_request_sender
                             .send(ExternalInputRequest::ChangeSpeed(*speed))
                             .unwrap();
+                        self.current_task = None;
+                    }
+                    NextMiscellaneous::ToolChange(_) | NextMiscellaneous::SpeedChange(_) => {
+                        self.current_task = None
                     }
-                    NextMiscellaneous::ToolChange(_) | NextMiscellaneous::SpeedChange(_) => (),
                 },
                 None => {
                     stepper_delay = 0.0f64;
                     calculate_z_phase = 0;
+                    step_target = None;
                     match self.task_query.lock() {
                         Ok(ref mut locked_queue) if locked_queue.len() > q_ptr => {
                             let next = locked_queue[q_ptr].clone();
@@ -477,17 +517,20 @@ impl MotorControllerThread {
                             self.steps_done.store(q_ptr as i64, Relaxed);
                             self.steps_todo
                                 .store((locked_queue.len() - q_ptr) as i64, Relaxed);
-                            println!("next {:?} {:?}", q_ptr, locked_queue.len() - q_ptr);
+                            trace!("next {:?} {:?}", q_ptr, locked_queue.len() - q_ptr);
                             q_ptr += 1;
 
                             self.current_task = InnerTask::from_task(
                                 next,
                                 self.get_pos(),
                                 self.get_step_sizes(),
+                                self.get_pos_a(),
+                                self.get_step_size_a(),
                                 40.0f64,
+                                self.clock.now(),
                             );
                         }
-                        Ok(ref mut locked_queue) => {
+                        Ok(mut locked_queue) => {
                             locked_queue.clear();
                             q_ptr = 0;
 
@@ -497,12 +540,12 @@ impl MotorControllerThread {
                                 self.steps_done.store(0, Relaxed);
                                 self.state.store(idle, Relaxed);
                             }
-                            #[allow(clippy::drop_ref)]
+                            // release the queue while idle
                             drop(locked_queue);
-                            thread::sleep(Duration::new(0, 10_000));
+                            self.clock.idle(Duration::new(0, 10_000));
                         }
                         _ => {
-                            thread::sleep(Duration::new(0, 10_000));
+                            self.clock.idle(Duration::new(0, 10_000));
                         }
                     }
                 }
@@ -510,6 +553,35 @@ impl MotorControllerThread {
         }
     }
 
+    /**
+     * step an axis towards the end switch of the calibrate type
+     *
+     * @return true if the axis reached the switch or has nothing to home
+     */
+    fn home_axis(motor: &mut Motor, calibrate: &CalibrateType) -> bool {
+        let direction = match calibrate {
+            CalibrateType::Min => Direction::Left,
+            CalibrateType::Max => Direction::Right,
+            _ => return true,
+        };
+        if motor.is_blocked() == Some(direction.clone()) {
+            true
+        } else {
+            motor.step(direction);
+            false
+        }
+    }
+
+    /// stop the pulses the buffered drivers still have to play
+    fn flush_motors(&mut self) {
+        self.motor_x.flush();
+        self.motor_y.flush();
+        self.motor_z.flush();
+        if let Some(motor_a) = self.motor_a.as_mut() {
+            motor_a.flush();
+        }
+    }
+
     fn calc_runtime(elapsed: f64, blocked_delay: &mut f64) -> u64 {
         if elapsed < *blocked_delay {
             *blocked_delay = 0.0;
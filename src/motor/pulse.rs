@@ -0,0 +1,489 @@
+use super::{Driver, DroppedSteps, Result};
+use crate::types::Direction;
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicI8, AtomicU64, Ordering::Relaxed},
+        mpsc::{channel, sync_channel, Receiver, Sender, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use thread_priority::{set_current_thread_priority, ThreadPriority};
+
+/// sleep until this time before the deadline and busy wait the rest
+const SPIN_THRESHOLD: Duration = Duration::from_micros(200);
+/// pulses in flight, submit waits if the pulse thread is that far behind
+const PULSE_QUEUE: usize = 1024;
+
+/// one step on a precomputed timeline
+#[derive(Debug, Clone)]
+pub struct StepPulse {
+    /// time to emit the pulse
+    pub deadline: Instant,
+    pub direction: Direction,
+    /// backlash compensation, not counted in the motor position
+    pub compensation: bool,
+}
+
+/// message to the thread of a ThreadedPulseBackend
+#[derive(Debug)]
+enum PulseMessage {
+    Pulse(StepPulse),
+    /// end of the pulses to drop, replied with the dropped steps
+    Flush(Sender<DroppedSteps>),
+    /// switch the driver in or out of homing between the pulses
+    Homing(bool),
+}
+
+/// pulse that was played out by a backend
+#[derive(Debug, Clone)]
+pub struct PlayedPulse {
+    pub deadline: Instant,
+    pub played_at: Instant,
+    pub direction: Direction,
+}
+
+/// plays out step pulses with precise timing, decoupled from the motor controller thread
+pub trait PulseBackend: Debug {
+    /// append a pulse to the timeline. Returns without waiting for the pulse to be played.
+    fn submit(&mut self, pulse: StepPulse) -> Result<()>;
+    /// end switch state reported by the backend
+    fn is_blocked(&mut self) -> Option<Direction>;
+    /// take the steps the driver refused since the last call, e.g. at an end switch
+    fn take_rejected(&mut self) -> DroppedSteps;
+    /// max delay between deadline and played pulse in µs
+    fn max_jitter_us(&self) -> u64;
+    /// drop all pulses that are not played yet
+    fn flush(&mut self) -> DroppedSteps;
+    /// homing mode of the driver, applied after the pulses submitted before
+    fn set_homing(&mut self, homing: bool);
+}
+
+/**
+ * Driver to fill the timeline of a PulseBackend.
+ *
+ * Every step is scheduled **latency** after the time the motor requests it,
+ * the backend gets this time to play it out exactly.
+ */
+#[derive(Debug)]
+pub struct PulseDriver {
+    backend: Box<dyn PulseBackend + Send>,
+    step_size: f64,
+    latency: Duration,
+}
+
+impl PulseDriver {
+    pub fn new(backend: Box<dyn PulseBackend + Send>, step_size: f64, latency: Duration) -> Self {
+        PulseDriver {
+            backend,
+            step_size,
+            latency,
+        }
+    }
+    fn submit(
+        &mut self,
+        direction: &Direction,
+        at: Instant,
+        compensation: bool,
+    ) -> Result<Direction> {
+        if self.backend.is_blocked().as_ref() == Some(direction) {
+            return Err("is blocked at end");
+        }
+        self.backend.submit(StepPulse {
+            deadline: at + self.latency,
+            direction: direction.clone(),
+            compensation,
+        })?;
+        Ok(direction.clone())
+    }
+}
+
+impl Driver for PulseDriver {
+    fn do_step(&mut self, direction: &Direction) -> Result<Direction> {
+        self.do_step_at(direction, Instant::now())
+    }
+    fn do_step_at(&mut self, direction: &Direction, at: Instant) -> Result<Direction> {
+        self.submit(direction, at, false)
+    }
+    fn do_compensation_step_at(&mut self, direction: &Direction, at: Instant) -> Result<Direction> {
+        self.submit(direction, at, true)
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+    fn get_step_size(&self) -> f64 {
+        self.step_size
+    }
+    fn is_blocked(&mut self) -> Option<Direction> {
+        self.backend.is_blocked()
+    }
+    fn take_rejected(&mut self) -> DroppedSteps {
+        self.backend.take_rejected()
+    }
+    fn flush(&mut self) -> DroppedSteps {
+        self.backend.flush()
+    }
+    fn set_homing(&mut self, homing: bool) {
+        self.backend.set_homing(homing);
+    }
+    fn max_jitter(&self) -> Option<Duration> {
+        Some(Duration::from_micros(self.backend.max_jitter_us()))
+    }
+}
+
+/**
+ * Backend with a dedicated max priority thread, that owns the real driver
+ * and plays the pulses on their deadline.
+ *
+ * Used with the StepMotor on the RPi and with the MockMotor as simulation backend.
+ */
+#[derive(Debug)]
+pub struct ThreadedPulseBackend {
+    sender: SyncSender<PulseMessage>,
+    blocked: Arc<AtomicI8>,
+    rejected: Arc<Mutex<DroppedSteps>>,
+    flushing: Arc<AtomicBool>,
+    max_jitter_us: Arc<AtomicU64>,
+    timeline: Option<Arc<Mutex<Vec<PlayedPulse>>>>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl ThreadedPulseBackend {
+    pub fn new(driver: Box<dyn Driver + Send>) -> Self {
+        ThreadedPulseBackend::start(driver, None)
+    }
+    /// simulation backend. Keeps all played pulses to compare them with the requested timeline
+    pub fn simulation(driver: Box<dyn Driver + Send>) -> Self {
+        ThreadedPulseBackend::start(driver, Some(Arc::new(Mutex::new(Vec::new()))))
+    }
+    fn start(
+        driver: Box<dyn Driver + Send>,
+        timeline: Option<Arc<Mutex<Vec<PlayedPulse>>>>,
+    ) -> Self {
+        let (sender, receiver) = sync_channel::<PulseMessage>(PULSE_QUEUE);
+        let blocked = Arc::new(AtomicI8::new(0));
+        let rejected = Arc::new(Mutex::new(DroppedSteps::default()));
+        let flushing = Arc::new(AtomicBool::new(false));
+        let max_jitter_us = Arc::new(AtomicU64::new(0));
+
+        let blocked_inner = blocked.clone();
+        let rejected_inner = rejected.clone();
+        let flushing_inner = flushing.clone();
+        let max_jitter_inner = max_jitter_us.clone();
+        let timeline_inner = timeline.clone();
+        let thread = thread::spawn(move || {
+            if set_current_thread_priority(ThreadPriority::Max).is_err() {
+                println!("> failed to set pulse thread priority");
+            }
+            ThreadedPulseBackend::play(
+                driver,
+                receiver,
+                blocked_inner,
+                rejected_inner,
+                flushing_inner,
+                max_jitter_inner,
+                timeline_inner,
+            );
+        });
+
+        ThreadedPulseBackend {
+            sender,
+            blocked,
+            rejected,
+            flushing,
+            max_jitter_us,
+            timeline,
+            thread,
+        }
+    }
+    fn play(
+        mut driver: Box<dyn Driver + Send>,
+        receiver: Receiver<PulseMessage>,
+        blocked: Arc<AtomicI8>,
+        rejected: Arc<Mutex<DroppedSteps>>,
+        flushing: Arc<AtomicBool>,
+        max_jitter_us: Arc<AtomicU64>,
+        timeline: Option<Arc<Mutex<Vec<PlayedPulse>>>>,
+    ) {
+        // end switches are read by this thread only, the state lags the submitted pulses by the latency
+        let store_blocked = |driver: &mut Box<dyn Driver + Send>| {
+            blocked.store(
+                match driver.is_blocked() {
+                    Some(Direction::Left) => -1,
+                    Some(Direction::Right) => 1,
+                    None => 0,
+                },
+                Relaxed,
+            )
+        };
+        store_blocked(&mut driver);
+
+        let mut dropped = DroppedSteps::default();
+        while let Ok(message) = receiver.recv() {
+            let StepPulse {
+                deadline,
+                direction,
+                compensation,
+            } = match message {
+                PulseMessage::Pulse(pulse) => pulse,
+                PulseMessage::Flush(reply) => {
+                    flushing.store(false, Relaxed);
+                    let _ = reply.send(dropped);
+                    dropped = DroppedSteps::default();
+                    continue;
+                }
+                PulseMessage::Homing(homing) => {
+                    driver.set_homing(homing);
+                    store_blocked(&mut driver);
+                    continue;
+                }
+            };
+            if flushing.load(Relaxed) {
+                dropped.add(&direction, compensation);
+                continue;
+            }
+
+            let now = Instant::now();
+            if deadline > now + SPIN_THRESHOLD {
+                thread::sleep(deadline - now - SPIN_THRESHOLD);
+            }
+            // flushed while waiting for the deadline
+            if flushing.load(Relaxed) {
+                dropped.add(&direction, compensation);
+                continue;
+            }
+            while Instant::now() < deadline {}
+
+            let played_at = Instant::now();
+            // the motor counted the step when it was submitted, it corrects the refused ones
+            if driver.do_step(&direction).is_err() {
+                rejected.lock().unwrap().add(&direction, compensation);
+                store_blocked(&mut driver);
+                continue;
+            }
+
+            let jitter = played_at.saturating_duration_since(deadline).as_micros() as u64;
+            if jitter > max_jitter_us.load(Relaxed) {
+                max_jitter_us.store(jitter, Relaxed);
+            }
+            store_blocked(&mut driver);
+            if let Some(timeline) = timeline.as_ref() {
+                timeline.lock().unwrap().push(PlayedPulse {
+                    deadline,
+                    played_at,
+                    direction,
+                });
+            }
+        }
+    }
+    /// played pulses of a simulation backend
+    pub fn timeline(&self) -> Vec<PlayedPulse> {
+        self.timeline
+            .as_ref()
+            .map(|t| t.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+}
+
+impl PulseBackend for ThreadedPulseBackend {
+    fn submit(&mut self, pulse: StepPulse) -> Result<()> {
+        self.sender
+            .send(PulseMessage::Pulse(pulse))
+            .map_err(|_| "pulse thread is not running")
+    }
+    fn is_blocked(&mut self) -> Option<Direction> {
+        match self.blocked.load(Relaxed) {
+            -1 => Some(Direction::Left),
+            1 => Some(Direction::Right),
+            _ => None,
+        }
+    }
+    fn take_rejected(&mut self) -> DroppedSteps {
+        std::mem::take(&mut *self.rejected.lock().unwrap())
+    }
+    fn max_jitter_us(&self) -> u64 {
+        self.max_jitter_us.load(Relaxed)
+    }
+    /// the pulse thread drops everything up to the flush message and replies the count
+    fn flush(&mut self) -> DroppedSteps {
+        self.flushing.store(true, Relaxed);
+        let (reply, dropped) = channel();
+        if self.sender.send(PulseMessage::Flush(reply)).is_err() {
+            return DroppedSteps::default();
+        }
+        dropped.recv().unwrap_or_default()
+    }
+    fn set_homing(&mut self, homing: bool) {
+        let _ = self.sender.send(PulseMessage::Homing(homing));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motor::{MockMotor, Motor};
+
+    fn pulse(deadline: Instant, direction: Direction) -> StepPulse {
+        StepPulse {
+            deadline,
+            direction,
+            compensation: false,
+        }
+    }
+
+    #[test]
+    fn simulation_plays_pulses_in_order_on_their_deadline() {
+        let mut backend = ThreadedPulseBackend::simulation(Box::new(MockMotor::new(0.01)));
+        let start = Instant::now() + Duration::from_millis(5);
+        for i in 0..10 {
+            let direction = if i < 5 {
+                Direction::Right
+            } else {
+                Direction::Left
+            };
+            backend
+                .submit(pulse(start + Duration::from_micros(i * 500), direction))
+                .unwrap();
+        }
+        thread::sleep(Duration::from_millis(50));
+
+        let timeline = backend.timeline();
+        assert_eq!(timeline.len(), 10);
+        for (i, played) in timeline.iter().enumerate() {
+            assert!(played.played_at >= played.deadline);
+            assert_eq!(
+                played.deadline,
+                start + Duration::from_micros(i as u64 * 500)
+            );
+        }
+        assert_eq!(timeline[4].direction, Direction::Right);
+        assert_eq!(timeline[5].direction, Direction::Left);
+        assert!(backend.max_jitter_us() < 50_000);
+    }
+
+    #[test]
+    fn flush_drops_pending_pulses() {
+        let mut backend = ThreadedPulseBackend::simulation(Box::new(MockMotor::new(0.01)));
+        let start = Instant::now() + Duration::from_secs(1);
+        for i in 0..3 {
+            backend
+                .submit(pulse(start + Duration::from_millis(i), Direction::Right))
+                .unwrap();
+        }
+        backend
+            .submit(pulse(start + Duration::from_millis(3), Direction::Left))
+            .unwrap();
+
+        assert_eq!(backend.flush().steps, 2);
+        assert!(backend.timeline().is_empty());
+
+        // the backend plays again after the flush
+        backend
+            .submit(pulse(Instant::now(), Direction::Left))
+            .unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(backend.timeline().len(), 1);
+    }
+
+    /// refuses the steps right of `end`
+    #[derive(Debug)]
+    struct EndSwitch {
+        pos: i64,
+        end: i64,
+    }
+
+    impl Driver for EndSwitch {
+        fn do_step(&mut self, direction: &Direction) -> Result<Direction> {
+            match direction {
+                Direction::Right if self.pos >= self.end => return Err("is blocked at end"),
+                Direction::Right => self.pos += 1,
+                Direction::Left => self.pos -= 1,
+            }
+            Ok(direction.clone())
+        }
+        fn get_step_size(&self) -> f64 {
+            0.01
+        }
+        fn is_blocked(&mut self) -> Option<Direction> {
+            if self.pos >= self.end {
+                Some(Direction::Right)
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn refused_steps_are_reported_back() {
+        let mut backend = ThreadedPulseBackend::simulation(Box::new(EndSwitch { pos: 0, end: 3 }));
+        let start = Instant::now();
+        for i in 0..5 {
+            backend
+                .submit(pulse(
+                    start + Duration::from_micros(i * 100),
+                    Direction::Right,
+                ))
+                .unwrap();
+        }
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(backend.timeline().len(), 3);
+        assert_eq!(backend.is_blocked(), Some(Direction::Right));
+        assert_eq!(backend.take_rejected().steps, 2);
+        assert_eq!(backend.take_rejected(), DroppedSteps::default());
+    }
+
+    #[test]
+    fn motor_position_drops_the_refused_steps() {
+        let backend = ThreadedPulseBackend::simulation(Box::new(EndSwitch { pos: 0, end: 3 }));
+        let driver = PulseDriver::new(Box::new(backend), 0.01, Duration::from_millis(1));
+        let mut motor = Motor::new(
+            "x".to_string(),
+            100.0,
+            1000.0,
+            0.0,
+            1000.0,
+            0.0,
+            Box::new(driver),
+        );
+        for _ in 0..5 {
+            motor.step(Direction::Right);
+        }
+        thread::sleep(Duration::from_millis(50));
+        motor.flush();
+
+        assert_eq!(motor.get_pos_ref().load(Relaxed), 3);
+        assert_eq!(motor.get_metrics().blocked.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn flush_gives_back_the_dropped_backlash_compensation() {
+        let backend = ThreadedPulseBackend::simulation(Box::new(MockMotor::new(0.01)));
+        let timeline = backend.timeline.clone().unwrap();
+        let driver = PulseDriver::new(Box::new(backend), 0.01, Duration::from_millis(100));
+        // 3 steps backlash
+        let mut motor = Motor::new(
+            "x".to_string(),
+            100.0,
+            1000.0,
+            0.0,
+            1000.0,
+            0.03,
+            Box::new(driver),
+        );
+        motor.step(Direction::Right);
+        motor.step(Direction::Right);
+        motor.step(Direction::Left);
+        motor.flush();
+        assert!(timeline.lock().unwrap().is_empty());
+        assert_eq!(motor.get_pos_ref().load(Relaxed), 0);
+
+        // the compensation to the left was not played, the nut is still at the right flank
+        motor.step(Direction::Right);
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(timeline.lock().unwrap().len(), 1);
+        assert_eq!(motor.get_pos_ref().load(Relaxed), 1);
+    }
+}
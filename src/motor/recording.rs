@@ -0,0 +1,235 @@
+use super::{Clock, Driver, Result};
+use crate::types::{Direction, MachineState};
+use serde::Serialize;
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicI64, AtomicU32, Ordering::Relaxed},
+        Arc, Mutex,
+    },
+};
+
+/// step events of the axes x, y, z and a, as [Left, Right]
+const STEP_EVENTS: [[&str; 2]; 4] = [["x-", "x+"], ["y-", "y+"], ["z-", "z+"], ["a-", "a+"]];
+/// a longer timeline is thinned out: every second step event is dropped and from then on only
+/// every second step is recorded
+const MAX_EVENTS: usize = 500_000;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEvent {
+    /// [s] on the virtual clock
+    pub t: f64,
+    /// step of an axis ("x+", "z-") or a state change ("state ProgramTask", "spindle on", "task 3")
+    pub event: Cow<'static, str>,
+    /// index of the task the motor controller works on
+    pub task: i64,
+    /// motor positions [mm] (A axis [deg]), backlash compensation steps included
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub a: f64,
+}
+
+impl TimelineEvent {
+    pub fn is_step(&self) -> bool {
+        self.event.ends_with('+') || self.event.ends_with('-')
+    }
+}
+
+/// state of the motor controller logged between the steps
+#[derive(Debug)]
+struct Watched {
+    state: Arc<AtomicU32>,
+    on_off: Arc<AtomicBool>,
+    task: Arc<AtomicI64>,
+}
+
+/// timeline of a recorder. The totals include the steps that were thinned out of the events
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recording {
+    /// [s] of the last step or state change on the virtual clock
+    pub duration: f64,
+    pub steps: u64,
+    /// every nth step is in the events, it doubles whenever the events are thinned out
+    pub step_interval: u64,
+    /// lowest motor positions [x, y, z] in mm
+    pub min: [f64; 3],
+    /// highest motor positions [x, y, z] in mm
+    pub max: [f64; 3],
+    pub events: Vec<TimelineEvent>,
+}
+
+impl Default for Recording {
+    fn default() -> Self {
+        Recording {
+            duration: 0.0,
+            steps: 0,
+            step_interval: 1,
+            min: [0.0; 3],
+            max: [0.0; 3],
+            events: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Timeline {
+    steps: [i64; 4],
+    step_sizes: [f64; 4],
+    state: u32,
+    on: bool,
+    task: i64,
+    recording: Recording,
+}
+
+impl Timeline {
+    fn pos(&self, axis: usize) -> f64 {
+        self.steps[axis] as f64 * self.step_sizes[axis]
+    }
+    fn push(&mut self, t: f64, event: Cow<'static, str>) {
+        let event = TimelineEvent {
+            t,
+            event,
+            task: self.task,
+            x: self.pos(0),
+            y: self.pos(1),
+            z: self.pos(2),
+            a: self.pos(3),
+        };
+        self.recording.duration = t;
+        self.recording.events.push(event);
+    }
+    fn step(&mut self, t: f64, axis: usize, direction: &Direction) {
+        let (delta, side) = match direction {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 1),
+        };
+        self.steps[axis] += delta;
+        let recording = &mut self.recording;
+        recording.duration = t;
+        recording.steps += 1;
+        if axis < 3 {
+            let pos = self.steps[axis] as f64 * self.step_sizes[axis];
+            recording.min[axis] = recording.min[axis].min(pos);
+            recording.max[axis] = recording.max[axis].max(pos);
+        }
+        if recording.steps.is_multiple_of(recording.step_interval) {
+            self.push(t, Cow::Borrowed(STEP_EVENTS[axis][side]));
+        }
+        if self.recording.events.len() >= MAX_EVENTS {
+            self.thin_out();
+        }
+    }
+    /// drop every second step event, the state changes are kept
+    fn thin_out(&mut self) {
+        let mut odd = false;
+        self.recording.events.retain(|e| {
+            if !e.is_step() {
+                return true;
+            }
+            odd = !odd;
+            odd
+        });
+        self.recording.step_interval *= 2;
+    }
+}
+
+/// collects the steps of all recording drivers on the timeline of a virtual clock
+#[derive(Debug)]
+pub struct Recorder {
+    clock: Clock,
+    watched: Mutex<Option<Watched>>,
+    timeline: Mutex<Timeline>,
+}
+
+impl Recorder {
+    pub fn new(clock: Clock) -> Self {
+        Recorder {
+            clock,
+            watched: Mutex::new(None),
+            timeline: Mutex::new(Timeline::default()),
+        }
+    }
+    /**
+     * driver of one axis writing into this recorder
+     *
+     * - **axis** 0 = x, 1 = y, 2 = z, 3 = a
+     * - **step_size** mm (deg) per step
+     */
+    pub fn driver(self: &Arc<Self>, axis: usize, step_size: f64) -> RecordingDriver {
+        self.timeline.lock().unwrap().step_sizes[axis] = step_size;
+        RecordingDriver {
+            axis,
+            step_size,
+            recorder: self.clone(),
+        }
+    }
+    /// log the changes of the machine state, the spindle and the task index
+    pub fn watch(&self, state: Arc<AtomicU32>, on_off: Arc<AtomicBool>, task: Arc<AtomicI64>) {
+        *self.watched.lock().unwrap() = Some(Watched {
+            state,
+            on_off,
+            task,
+        });
+    }
+    /// compare the watched state with the last one. Changes get the time of the probe
+    pub fn probe(&self) {
+        let t = self.clock.virtual_time().as_secs_f64();
+        let watched = self.watched.lock().unwrap();
+        let watched = match watched.as_ref() {
+            Some(watched) => watched,
+            None => return,
+        };
+        let mut timeline = self.timeline.lock().unwrap();
+        let state = watched.state.load(Relaxed);
+        if state != timeline.state {
+            timeline.state = state;
+            let state: MachineState = state.into();
+            timeline.push(t, format!("state {:?}", state).into());
+        }
+        let on = watched.on_off.load(Relaxed);
+        if on != timeline.on {
+            timeline.on = on;
+            timeline.push(t, if on { "spindle on" } else { "spindle off" }.into());
+        }
+        let task = watched.task.load(Relaxed);
+        if task != timeline.task {
+            timeline.task = task;
+            timeline.push(t, format!("task {}", task).into());
+        }
+    }
+    fn step(&self, axis: usize, direction: &Direction) {
+        self.probe();
+        let t = self.clock.virtual_time().as_secs_f64();
+        self.timeline.lock().unwrap().step(t, axis, direction);
+    }
+    /// the recorded timeline, the recorder starts over empty
+    pub fn take_recording(&self) -> Recording {
+        self.probe();
+        std::mem::take(&mut self.timeline.lock().unwrap().recording)
+    }
+}
+
+/// driver of a simulation. Every step is logged with its time on the virtual clock
+#[derive(Debug)]
+pub struct RecordingDriver {
+    axis: usize,
+    step_size: f64,
+    recorder: Arc<Recorder>,
+}
+
+impl Driver for RecordingDriver {
+    fn do_step(&mut self, direction: &Direction) -> Result<Direction> {
+        self.recorder.step(self.axis, direction);
+        Ok(direction.clone())
+    }
+    fn get_step_size(&self) -> f64 {
+        self.step_size
+    }
+    fn is_blocked(&mut self) -> Option<Direction> {
+        None
+    }
+}
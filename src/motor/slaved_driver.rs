@@ -0,0 +1,66 @@
+use super::{Driver, DroppedSteps, Result};
+use crate::types::Direction;
+use std::fmt::Debug;
+
+/**
+ * Two drivers stepping together on one axis (e.g. a gantry with two Y motors).
+ *
+ * While homing each side stops at its own end switch, so driving the axis into the switches
+ * latches both sides independently and squares the gantry.
+ * Otherwise both sides step together or not at all.
+ */
+#[derive(Debug)]
+pub struct SlavedDriver {
+    master: Box<dyn Driver + Send>,
+    slave: Box<dyn Driver + Send>,
+    homing: bool,
+}
+
+impl SlavedDriver {
+    pub fn new(master: Box<dyn Driver + Send>, slave: Box<dyn Driver + Send>) -> Self {
+        SlavedDriver {
+            master,
+            slave,
+            homing: false,
+        }
+    }
+}
+
+impl Driver for SlavedDriver {
+    fn do_step(&mut self, direction: &Direction) -> Result<Direction> {
+        if self.homing {
+            let master = self.master.do_step(direction);
+            let slave = self.slave.do_step(direction);
+            return match (master, slave) {
+                (Err(e), Err(_)) => Err(e),
+                _ => Ok(direction.clone()),
+            };
+        }
+        // one side alone would rack the gantry
+        if self.master.is_blocked().as_ref() == Some(direction)
+            || self.slave.is_blocked().as_ref() == Some(direction)
+        {
+            return Err("is blocked at end");
+        }
+        self.master.do_step(direction)?;
+        self.slave.do_step(direction)
+    }
+    fn get_step_size(&self) -> f64 {
+        self.master.get_step_size()
+    }
+    /// blocked if one side reached its end switch. While homing only if both sides did
+    fn is_blocked(&mut self) -> Option<Direction> {
+        match (self.master.is_blocked(), self.slave.is_blocked()) {
+            (Some(master), Some(slave)) if master == slave => Some(master),
+            (Some(blocked), _) | (_, Some(blocked)) if !self.homing => Some(blocked),
+            _ => None,
+        }
+    }
+    fn set_homing(&mut self, homing: bool) {
+        self.homing = homing;
+    }
+    fn flush(&mut self) -> DroppedSteps {
+        self.slave.flush();
+        self.master.flush()
+    }
+}
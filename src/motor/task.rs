@@ -1,15 +1,16 @@
-use crate::gnc::{Next3dMovement, NextMiscellaneous};
+use crate::gnc::{distance_with_a, Next3dMovement, NextMiscellaneous};
 use crate::types::{
-    CircleMovement, LinearMovement, Location, MachineState, MoveType, SteppedCircleMovement,
-    SteppedLinearMovement, SteppedMoveType,
+    LinearMovement, Location, MachineState, MoveType, SteppedLinearMovement, SteppedMoveType,
 };
-use std::{fmt::Debug, time::SystemTime};
+use std::{fmt::Debug, time::Instant};
 
 #[derive(Debug)]
 pub struct InnerTaskProduction {
-    pub start_time: SystemTime,
+    pub start_time: Instant,
     // stepper position to start from
     pub from: Location<i64>,
+    // A axis stepper position to start from
+    pub from_a: i64,
     // stepper position to go
     pub destination: Location<i64>,
     // movement type to move to the desired position
@@ -26,7 +27,7 @@ pub enum CalibrateType {
 }
 #[derive(Debug)]
 pub struct InnerTaskCalibrate {
-    pub start_time: SystemTime,
+    pub start_time: Instant,
     pub from: Location<i64>,
     pub x: CalibrateType,
     pub y: CalibrateType,
@@ -47,14 +48,27 @@ impl InnerTask {
      * - **t** Task to convert
      * - **current_pos** current position in steps
      * - **step_size** mm per step
+     * - **current_a** current A axis position in steps
+     * - **step_size_a** deg per step of the A axis (0.0 without A axis)
      * - **max_speed** mm per sec
+     * - **now** start time of the task on the clock of the motor controller
      */
     pub fn from_task(
         t: Task,
         current_pos: Location<i64>,
         step_sizes: Location<f64>,
+        current_a: i64,
+        step_size_a: f64,
         max_speed: f64,
+        now: Instant,
     ) -> Option<InnerTask> {
+        let steps_a = |deg: f64| {
+            if step_size_a > 0.0 {
+                (deg / step_size_a).round() as i64
+            } else {
+                0
+            }
+        };
         match t {
             Task::Manual(task) => {
                 let input = Location::new(task.move_x_speed, task.move_y_speed, task.move_z_speed);
@@ -69,87 +83,110 @@ impl InnerTask {
                     None
                 } else {
                     Some(InnerTask::Production(InnerTaskProduction {
-                        start_time: SystemTime::now(),
+                        start_time: now,
                         from: current_pos,
+                        from_a: current_a,
                         destination,
                         move_type: SteppedMoveType::Linear(SteppedLinearMovement {
                             delta,
+                            delta_a: 0,
                             distance,
                             speed: task.speed_mm_min,
                         }),
                     }))
                 }
             }
+            Task::ManualStep(ManualStepTask {
+                delta,
+                delta_a,
+                speed_mm_min,
+            }) => {
+                let delta_in_steps: Location<i64> = (delta.clone() / step_sizes).into();
+                let delta_a = steps_a(delta_a);
+                let distance = distance_with_a(&delta, delta_a as f64 * step_size_a);
+
+                if speed_mm_min == 0.0f64 || distance == 0.0f64 {
+                    None
+                } else {
+                    Some(InnerTask::Production(InnerTaskProduction {
+                        start_time: now,
+                        from: current_pos.clone(),
+                        from_a: current_a,
+                        destination: current_pos + delta_in_steps.clone(),
+                        move_type: SteppedMoveType::Linear(SteppedLinearMovement {
+                            delta: delta_in_steps,
+                            delta_a,
+                            distance,
+                            speed: speed_mm_min,
+                        }),
+                    }))
+                }
+            }
             Task::ProgramMovement(Next3dMovement {
                 speed,
                 move_type,
                 to,
+                a,
                 ..
             }) => match move_type {
-                MoveType::Linear(LinearMovement { distance, delta }) => {
+                MoveType::Linear(LinearMovement { distance, .. }) => {
                     if speed == 0.0f64 || distance == 0.0f64 {
                         None
                     } else {
-                        let delta_in_steps: Location<i64> = (delta / step_sizes).into();
+                        // step from the absolute target to avoid summing up rounding errors of short moves (arc chords)
+                        let destination: Location<i64> = (to / step_sizes).into();
+                        let delta_in_steps = destination.clone() - current_pos.clone();
+                        let delta_a = if step_size_a > 0.0 {
+                            steps_a(a) - current_a
+                        } else {
+                            0
+                        };
 
                         Some(InnerTask::Production(InnerTaskProduction {
-                            start_time: SystemTime::now(),
-                            from: current_pos.clone(),
-                            destination: delta_in_steps.clone() - current_pos,
+                            start_time: now,
+                            from: current_pos,
+                            from_a: current_a,
+                            destination,
                             move_type: SteppedMoveType::Linear(SteppedLinearMovement {
                                 delta: delta_in_steps,
+                                delta_a,
                                 distance,
                                 speed: speed.min(max_speed),
                             }),
                         }))
                     }
                 }
-                MoveType::Rapid(LinearMovement { distance, delta }) => {
+                MoveType::Rapid(LinearMovement { distance, .. }) => {
                     if speed == 0.0f64 || distance == 0.0f64 {
                         None
                     } else {
-                        let delta_in_steps: Location<i64> = (delta / step_sizes).into();
+                        // step from the absolute target to avoid summing up rounding errors of short moves (arc chords)
+                        let destination: Location<i64> = (to / step_sizes).into();
+                        let delta_in_steps = destination.clone() - current_pos.clone();
+                        let delta_a = if step_size_a > 0.0 {
+                            steps_a(a) - current_a
+                        } else {
+                            0
+                        };
 
                         Some(InnerTask::Production(InnerTaskProduction {
-                            start_time: SystemTime::now(),
-                            from: current_pos.clone(),
-                            destination: delta_in_steps.clone() - current_pos,
+                            start_time: now,
+                            from: current_pos,
+                            from_a: current_a,
+                            destination,
                             move_type: SteppedMoveType::Rapid(SteppedLinearMovement {
                                 delta: delta_in_steps,
+                                delta_a,
                                 distance,
                                 speed: speed.min(max_speed),
                             }),
                         }))
                     }
                 }
-                MoveType::Circle(CircleMovement {
-                    center,
-                    turn_direction,
-                    radius_sq,
-                }) => {
-                    let destination = to / step_sizes.clone();
-
-                    let step_delay = step_sizes.max() / speed.min(max_speed).max(6.0) / 60.0f64;
-                    let step_center = (center / step_sizes.clone()).into();
-
-                    Some(InnerTask::Production(InnerTaskProduction {
-                        start_time: SystemTime::now(),
-                        from: current_pos,
-                        destination: destination.into(),
-                        move_type: SteppedMoveType::Circle(SteppedCircleMovement {
-                            center: step_center,
-                            radius_sq,
-                            step_sizes,
-                            turn_direction,
-                            speed,
-                            step_delay,
-                        }),
-                    }))
-                }
             },
             Task::ProgramMiscellaneous(t) => Some(InnerTask::Miscellaneous(t)),
             Task::Calibrate(x, y, z) => Some(InnerTask::Calibrate(InnerTaskCalibrate {
-                start_time: SystemTime::now(),
+                start_time: now,
                 from: current_pos,
                 x,
                 y,
@@ -172,8 +209,19 @@ pub struct ManualTask {
     pub speed_mm_min: f64,
 }
 
+#[derive(Debug, Clone)]
+pub struct ManualStepTask {
+    /** exact relative move [mm] */
+    pub delta: Location<f64>,
+    /** exact relative rotation of the A axis [deg] */
+    pub delta_a: f64,
+    /** move speed [mm/min] */
+    pub speed_mm_min: f64,
+}
+
 pub enum ManualInstruction {
     Movement(ManualTask),
+    Step(ManualStepTask),
     Miscellaneous(NextMiscellaneous),
 }
 
@@ -182,6 +230,7 @@ pub enum Task {
     ProgramMovement(Next3dMovement),
     ProgramMiscellaneous(NextMiscellaneous),
     Manual(ManualTask),
+    ManualStep(ManualStepTask),
     Calibrate(CalibrateType, CalibrateType, CalibrateType),
 }
 
@@ -191,6 +240,7 @@ impl Task {
             Task::ProgramMovement(_) => MachineState::ProgramTask,
             Task::ProgramMiscellaneous(_) => MachineState::ProgramTask,
             Task::Manual(_) => MachineState::ManualTask,
+            Task::ManualStep(_) => MachineState::ManualTask,
             Task::Calibrate(_, _, _) => MachineState::Calibrate,
         }
     }
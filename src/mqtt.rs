@@ -0,0 +1,276 @@
+use crate::ui::types::{
+    WsCommandControl, WsCommandProgram, WsCommands, WsCommandsFrom, WsMessages,
+};
+
+use crossbeam_channel::{unbounded, Sender};
+use rumqttc::{Client, Event, Incoming, LastWill, MqttOptions, QoS};
+use serde::{Deserialize, Serialize};
+use std::{thread, time::Duration};
+use uuid::Uuid;
+
+/// requests waiting for the broker, further messages are dropped while it is unreachable
+const QUEUE_SIZE: usize = 64;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    // retained `online` / `offline` (last will)
+    pub availability_topic: String,
+    // retained, JSON like the WS messages
+    pub status_topic: String,
+    pub position_topic: String,
+    pub info_topic: String,
+    // `cancel`, `pause` and `spindleOff`, or the same WS commands as JSON
+    pub command_topic: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        MqttSettings {
+            enabled: false,
+            host: String::from("localhost"),
+            port: 1883,
+            client_id: String::from("cnc-control"),
+            username: None,
+            password: None,
+            availability_topic: String::from("cnc/online"),
+            status_topic: String::from("cnc/status"),
+            position_topic: String::from("cnc/position"),
+            info_topic: String::from("cnc/info"),
+            command_topic: String::from("cnc/command"),
+        }
+    }
+}
+
+/// only commands that stop the machine are accepted from the broker. A resume starts it again
+fn is_safe(cmd: &WsCommands) -> bool {
+    matches!(
+        cmd,
+        WsCommands::Program(WsCommandProgram::Cancel)
+            | WsCommands::Program(WsCommandProgram::Pause { pause: true })
+            | WsCommands::Control(WsCommandControl::OnOff { on: false })
+    )
+}
+
+fn parse_command(payload: &[u8]) -> Option<WsCommands> {
+    let text = String::from_utf8_lossy(payload);
+    let cmd = match text.trim() {
+        "cancel" => WsCommands::Program(WsCommandProgram::Cancel),
+        "pause" => WsCommands::Program(WsCommandProgram::Pause { pause: true }),
+        "spindleOff" => WsCommands::Control(WsCommandControl::OnOff { on: false }),
+        json => serde_json::from_str(json).ok()?,
+    };
+    Some(cmd)
+}
+
+/**
+ * pass a safe command of the broker on to the app
+ *
+ * @return false if the app stopped
+ */
+fn handle_command(payload: &[u8], id: Uuid, commands: &Sender<WsCommandsFrom>) -> bool {
+    match parse_command(payload) {
+        Some(cmd) if is_safe(&cmd) => commands.send(WsCommandsFrom(id, cmd)).is_ok(),
+        _ => {
+            println!(
+                "MQTT command rejected: {}",
+                String::from_utf8_lossy(payload)
+            );
+            true
+        }
+    }
+}
+
+/**
+ * pass a message of the app on to the UI, except the replies to the broker commands
+ *
+ * @return false if the UI stopped
+ */
+fn forward(msg: WsMessages, id: Uuid, ui: &Sender<WsMessages>) -> bool {
+    match msg {
+        WsMessages::Reply { to, .. } if to == id => true,
+        msg => ui.send(msg).is_ok(),
+    }
+}
+
+/// publish a message of the UI, if it has a topic
+fn publish(client: &mut Client, settings: &MqttSettings, msg: &WsMessages) {
+    let (topic, payload) = match msg {
+        WsMessages::Status(status) => (&settings.status_topic, serde_json::to_vec(status)),
+        WsMessages::Position(pos) => (&settings.position_topic, serde_json::to_vec(pos)),
+        WsMessages::Info(info) => (&settings.info_topic, serde_json::to_vec(info)),
+        _ => return,
+    };
+    let retain = !matches!(msg, WsMessages::Info(_));
+    if let Ok(payload) = payload {
+        // a full queue (broker unreachable) must not block the app
+        let _ = client.try_publish(topic.as_str(), QoS::AtMostOnce, retain, payload);
+    }
+}
+
+/**
+ * connect to the broker, if enabled
+ *
+ * @return the sender the app uses for the UI messages. They are published and passed on to `ui`
+ */
+pub fn start(
+    settings: &MqttSettings,
+    ui: Sender<WsMessages>,
+    commands: Sender<WsCommandsFrom>,
+) -> Sender<WsMessages> {
+    if !settings.enabled {
+        return ui;
+    }
+    let settings = settings.clone();
+    let mut options = MqttOptions::new(&settings.client_id, &settings.host, settings.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+        options.set_credentials(username, password);
+    }
+    options.set_last_will(LastWill::new(
+        &settings.availability_topic,
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    let (mut client, mut connection) = Client::new(options, QUEUE_SIZE);
+    // the replies to the broker commands are not passed on to the UI
+    let id = Uuid::new_v4();
+
+    {
+        let settings = settings.clone();
+        let mut client = client.clone();
+        thread::spawn(move || {
+            for event in connection.iter() {
+                match event {
+                    Ok(Event::Incoming(Incoming::ConnAck(_))) => {
+                        println!("MQTT connected to {}:{}", settings.host, settings.port);
+                        let _ = client.try_subscribe(&settings.command_topic, QoS::AtLeastOnce);
+                        let _ = client.try_publish(
+                            &settings.availability_topic,
+                            QoS::AtLeastOnce,
+                            true,
+                            "online",
+                        );
+                    }
+                    Ok(Event::Incoming(Incoming::Publish(p)))
+                        if p.topic == settings.command_topic =>
+                    {
+                        if !handle_command(&p.payload, id, &commands) {
+                            return;
+                        }
+                    }
+                    Ok(_) => (),
+                    Err(e) => {
+                        // the next poll reconnects
+                        println!("MQTT connection failed: {}", e);
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        });
+    }
+
+    let (sender, receiver) = unbounded::<WsMessages>();
+    thread::spawn(move || {
+        for msg in receiver {
+            publish(&mut client, &settings, &msg);
+            if !forward(msg, id, &ui) {
+                return;
+            }
+        }
+    });
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::types::WsReplyMessage;
+
+    fn accepted(payload: &str) -> bool {
+        parse_command(payload.as_bytes()).is_some_and(|cmd| is_safe(&cmd))
+    }
+
+    #[test]
+    fn parse_command_reads_keywords_and_json() {
+        assert!(matches!(
+            parse_command(b" pause\n"),
+            Some(WsCommands::Program(WsCommandProgram::Pause { pause: true }))
+        ));
+        assert!(matches!(
+            parse_command(br#"{"cmd":"program","action":"cancel"}"#),
+            Some(WsCommands::Program(WsCommandProgram::Cancel))
+        ));
+        assert!(parse_command(b"resume").is_none());
+        assert!(parse_command(b"{no json").is_none());
+    }
+
+    #[test]
+    fn is_safe_accepts_only_commands_that_stop() {
+        assert!(accepted("cancel"));
+        assert!(accepted("pause"));
+        assert!(accepted("spindleOff"));
+        assert!(accepted(
+            r#"{"cmd":"program","action":"pause","pause":true}"#
+        ));
+        assert!(!accepted(
+            r#"{"cmd":"program","action":"pause","pause":false}"#
+        ));
+        assert!(!accepted(r#"{"cmd":"control","action":"onOff","on":true}"#));
+        assert!(!accepted(
+            r#"{"cmd":"program","action":"start","programName":"a.nc","invertZ":false,"scale":1.0}"#
+        ));
+    }
+
+    #[test]
+    fn handle_command_forwards_only_safe_commands() {
+        let id = Uuid::new_v4();
+        let (commands, app) = unbounded();
+        assert!(handle_command(b"spindleOff", id, &commands));
+        assert!(handle_command(b"pause", id, &commands));
+        assert!(handle_command(
+            br#"{"cmd":"program","action":"start","programName":"a.nc","invertZ":false,"scale":1.0}"#,
+            id,
+            &commands
+        ));
+        assert!(matches!(
+            app.try_recv(),
+            Ok(WsCommandsFrom(from, WsCommands::Control(WsCommandControl::OnOff { on: false }))) if from == id
+        ));
+        assert!(matches!(
+            app.try_recv(),
+            Ok(WsCommandsFrom(
+                _,
+                WsCommands::Program(WsCommandProgram::Pause { pause: true })
+            ))
+        ));
+        assert!(app.try_recv().is_err());
+
+        drop(app);
+        assert!(!handle_command(b"cancel", id, &commands));
+    }
+
+    #[test]
+    fn forward_drops_the_replies_to_the_bridge() {
+        let id = Uuid::new_v4();
+        let (ui, receiver) = unbounded();
+        let reply = |to| WsMessages::Reply {
+            to,
+            msg: WsReplyMessage::CancelProgram { ok: true },
+        };
+        assert!(forward(reply(id), id, &ui));
+        assert!(receiver.try_recv().is_err());
+        assert!(forward(reply(Uuid::new_v4()), id, &ui));
+        assert!(matches!(receiver.try_recv(), Ok(WsMessages::Reply { to, .. }) if to != id));
+    }
+}
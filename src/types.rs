@@ -1,7 +1,5 @@
 #![allow(dead_code)]
-use std::convert::{From, Into};
 use std::{
-    cmp::PartialOrd,
     fmt::{self, Debug, Display},
     ops::{Add, Div, Mul, Neg, Sub},
 };
@@ -14,9 +12,9 @@ pub enum MachineState {
     Calibrate,
     Unknown,
 }
-impl Into<u32> for MachineState {
-    fn into(self) -> u32 {
-        match self {
+impl From<MachineState> for u32 {
+    fn from(state: MachineState) -> u32 {
+        match state {
             MachineState::Idle => 0,
             MachineState::ManualTask => 1,
             MachineState::ProgramTask => 2,
@@ -25,9 +23,9 @@ impl Into<u32> for MachineState {
         }
     }
 }
-impl Into<MachineState> for u32 {
-    fn into(self) -> MachineState {
-        match self {
+impl From<u32> for MachineState {
+    fn from(state: u32) -> MachineState {
+        match state {
             0 => MachineState::Idle,
             1 => MachineState::ManualTask,
             2 => MachineState::ProgramTask,
@@ -58,9 +56,9 @@ impl Debug for Direction {
         }
     }
 }
-impl Into<Direction> for i64 {
-    fn into(self) -> Direction {
-        if self > 0 {
+impl From<i64> for Direction {
+    fn from(steps: i64) -> Direction {
+        if steps > 0 {
             Direction::Right
         } else {
             Direction::Left
@@ -93,9 +91,9 @@ impl Location<f64> {
 impl Location<i64> {
     pub fn abs(&self) -> Location<u64> {
         Location::<u64> {
-            x: self.x.abs() as u64,
-            y: self.y.abs() as u64,
-            z: self.z.abs() as u64,
+            x: self.x.unsigned_abs(),
+            y: self.y.unsigned_abs(),
+            z: self.z.unsigned_abs(),
         }
     }
     pub fn distance_sq(&self) -> i64 {
@@ -108,9 +106,9 @@ impl Location<i64> {
 impl Location<i32> {
     pub fn abs(&self) -> Location<u32> {
         Location::<u32> {
-            x: self.x.abs() as u32,
-            y: self.y.abs() as u32,
-            z: self.z.abs() as u32,
+            x: self.x.unsigned_abs(),
+            y: self.y.unsigned_abs(),
+            z: self.z.unsigned_abs(),
         }
     }
     pub fn identity() -> Self {
@@ -310,27 +308,27 @@ where
         }
     }
 }
-impl Into<Location<f64>> for Location<i64> {
-    fn into(self) -> Location<f64> {
+impl From<Location<i64>> for Location<f64> {
+    fn from(location: Location<i64>) -> Location<f64> {
         Location {
-            x: self.x as f64,
-            y: self.y as f64,
-            z: self.z as f64,
+            x: location.x as f64,
+            y: location.y as f64,
+            z: location.z as f64,
         }
     }
 }
-impl Into<Location<i64>> for Location<f64> {
-    fn into(self) -> Location<i64> {
+impl From<Location<f64>> for Location<i64> {
+    fn from(location: Location<f64>) -> Location<i64> {
         Location {
-            x: self.x.round() as i64,
-            y: self.y.round() as i64,
-            z: self.z.round() as i64,
+            x: location.x.round() as i64,
+            y: location.y.round() as i64,
+            z: location.z.round() as i64,
         }
     }
 }
-impl Into<Location<u128>> for Location<i64> {
-    fn into(self) -> Location<u128> {
-        let abs = self.abs();
+impl From<Location<i64>> for Location<u128> {
+    fn from(location: Location<i64>) -> Location<u128> {
+        let abs = location.abs();
         Location {
             x: abs.x as u128,
             y: abs.y as u128,
@@ -338,6 +336,8 @@ impl Into<Location<u128>> for Location<i64> {
         }
     }
 }
+// named like the G-code
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum CircleDirection {
     CW,
@@ -348,31 +348,21 @@ pub enum CircleDirection {
 pub struct LinearMovement {
     /** delta move */
     pub delta: Location<f64>,
+    /** delta rotation of the A axis [deg] */
+    pub delta_a: f64,
     /** delta distance */
     pub distance: f64,
 }
 
-#[derive(Debug, Clone)]
-pub struct CircleMovement {
-    /** circle center */
-    pub center: Location<f64>,
-    /** initial radius in mm (²) */
-    pub radius_sq: f64,
-    /** cw or cww direction to mill the circle */
-    pub turn_direction: CircleDirection,
-}
-
 #[derive(Debug, Clone)]
 pub enum MoveType {
     Linear(LinearMovement),
-    Circle(CircleMovement),
     Rapid(LinearMovement),
 }
 impl Display for MoveType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             MoveType::Linear(_) => write!(f, "Move linear"),
-            MoveType::Circle(_) => write!(f, "Move circle"),
             MoveType::Rapid(_) => write!(f, "Move rapid"),
         }
     }
@@ -382,260 +372,24 @@ impl Display for MoveType {
 pub struct SteppedLinearMovement {
     /** delta move */
     pub delta: Location<i64>,
+    /** delta rotation of the A axis in steps */
+    pub delta_a: i64,
     /** delta distance in mm (to calculate speed) */
     pub distance: f64,
     /** speed in mm/sec */
     pub speed: f64,
 }
 
-#[derive(Debug)]
-pub struct SteppedCircleMovement {
-    /** circle center */
-    pub center: Location<i64>,
-    /** initial set radius in steps (float to comp with output of step_sizes) */
-    pub radius_sq: f64,
-    /**
-     * correction value to calculate the radius when the sep sizes differ on the axes
-     * # Example:
-     * ```
-     * let distance: Location<i64> = pos() - center;
-     * let radius_sq = (distance.into() * step_sizes).distance_sq()
-     */
-    pub step_sizes: Location<f64>,
-    /** cw or cww direction to mill the circle */
-    pub turn_direction: CircleDirection,
-
-    /** max speed */
-    pub speed: f64,
-    /** seconds between steps */
-    pub step_delay: f64,
-}
 #[derive(Debug)]
 pub enum SteppedMoveType {
     Linear(SteppedLinearMovement),
-    Circle(SteppedCircleMovement),
     Rapid(SteppedLinearMovement),
 }
 impl Display for SteppedMoveType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             SteppedMoveType::Linear(_) => write!(f, "Move linear"),
-            SteppedMoveType::Circle(_) => write!(f, "Move circle"),
             SteppedMoveType::Rapid(_) => write!(f, "Move rapid"),
         }
     }
 }
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum CircleStepDir {
-    Left,
-    Right,
-    Up,
-    Down,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct CircleStepCW {
-    main: CircleStepDir,
-    opt: CircleStepDir,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct CircleStepCCW {
-    main: CircleStepDir,
-    opt: CircleStepDir,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct CircleStep {
-    pub main: CircleStepDir,
-    pub opt: CircleStepDir,
-}
-
-impl<T> Into<CircleStepCCW> for Location<T>
-where
-    T: Debug
-        + PartialEq
-        + Copy
-        + Neg
-        + Default
-        + From<<T as Neg>::Output>
-        + PartialOrd
-        + Into<<T as Neg>::Output>,
-{
-    fn into(self) -> CircleStepCCW {
-        let turned = self.rot_z_ccw_90();
-
-        let nul = T::default();
-        // go right
-        let abs_x: T = if turned.x < nul {
-            (-turned.x).into()
-        } else {
-            turned.x
-        };
-        let abs_y: T = if turned.y < nul {
-            (-turned.y).into()
-        } else {
-            turned.y
-        };
-
-        // ccw
-        if turned.x >= nul {
-            if turned.y < nul && abs_x <= abs_y {
-                // go down / opt right
-                CircleStepCCW {
-                    main: CircleStepDir::Down,
-                    opt: CircleStepDir::Right,
-                }
-            } else if turned.y > nul && abs_x < abs_y {
-                // go up / opt right
-                CircleStepCCW {
-                    main: CircleStepDir::Up,
-                    opt: CircleStepDir::Right,
-                }
-            } else if turned.y <= nul {
-                // go right / opt down
-                CircleStepCCW {
-                    main: CircleStepDir::Right,
-                    opt: CircleStepDir::Down,
-                }
-            } else {
-                // go right / opt up
-                CircleStepCCW {
-                    main: CircleStepDir::Right,
-                    opt: CircleStepDir::Up,
-                }
-            }
-        } else {
-            // go left
-            if turned.y > nul && abs_x <= abs_y {
-                // go up / opt left
-                CircleStepCCW {
-                    main: CircleStepDir::Up,
-                    opt: CircleStepDir::Left,
-                }
-            } else if turned.y < nul && abs_x < abs_y {
-                // go down / opt left
-                CircleStepCCW {
-                    main: CircleStepDir::Down,
-                    opt: CircleStepDir::Left,
-                }
-            } else if turned.y >= nul {
-                // go left / opt up
-                CircleStepCCW {
-                    main: CircleStepDir::Left,
-                    opt: CircleStepDir::Up,
-                }
-            } else {
-                // go left / opt down
-                CircleStepCCW {
-                    main: CircleStepDir::Left,
-                    opt: CircleStepDir::Down,
-                }
-            }
-        }
-    }
-}
-
-impl<T> Into<CircleStepCW> for Location<T>
-where
-    T: Debug
-        + PartialEq
-        + Copy
-        + Neg
-        + Default
-        + From<<T as Neg>::Output>
-        + PartialOrd
-        + Into<<T as Neg>::Output>,
-{
-    fn into(self) -> CircleStepCW {
-        let turned = self.rot_z_cw_90();
-
-        let nul = T::default();
-        // go right
-        let abs_x: T = if turned.x < nul {
-            (-turned.x).into()
-        } else {
-            turned.x
-        };
-        let abs_y: T = if turned.y < nul {
-            (-turned.y).into()
-        } else {
-            turned.y
-        };
-
-        // cw
-        if turned.x >= nul {
-            // go right
-            if turned.y < nul && abs_x < abs_y {
-                // go down / opt right
-                CircleStepCW {
-                    main: CircleStepDir::Down,
-                    opt: CircleStepDir::Right,
-                }
-            } else if turned.y > nul && abs_x <= abs_y {
-                // go up / opt right
-                CircleStepCW {
-                    main: CircleStepDir::Up,
-                    opt: CircleStepDir::Right,
-                }
-            } else if turned.y < nul {
-                // go right / opt down
-                CircleStepCW {
-                    main: CircleStepDir::Right,
-                    opt: CircleStepDir::Down,
-                }
-            } else {
-                // go right / opt up
-                CircleStepCW {
-                    main: CircleStepDir::Right,
-                    opt: CircleStepDir::Up,
-                }
-            }
-        } else {
-            // go left
-            if turned.y > nul && abs_x < abs_y {
-                // go up / opt left
-                CircleStepCW {
-                    main: CircleStepDir::Up,
-                    opt: CircleStepDir::Left,
-                }
-            } else if turned.y < nul && abs_x <= abs_y {
-                // go down / opt left
-                CircleStepCW {
-                    main: CircleStepDir::Down,
-                    opt: CircleStepDir::Left,
-                }
-            } else if turned.y > nul {
-                // go left / opt up
-                CircleStepCW {
-                    main: CircleStepDir::Left,
-                    opt: CircleStepDir::Up,
-                }
-            } else {
-                // go left / opt down
-                CircleStepCW {
-                    main: CircleStepDir::Left,
-                    opt: CircleStepDir::Down,
-                }
-            }
-        }
-    }
-}
-
-impl Into<CircleStep> for CircleStepCW {
-    fn into(self) -> CircleStep {
-        CircleStep {
-            main: self.main,
-            opt: self.opt,
-        }
-    }
-}
-impl Into<CircleStep> for CircleStepCCW {
-    fn into(self) -> CircleStep {
-        CircleStep {
-            main: self.main,
-            opt: self.opt,
-        }
-    }
-}
@@ -0,0 +1,386 @@
+use actix_web::{
+    cookie::{Cookie, SameSite},
+    dev::Payload,
+    error::{self, BlockingError},
+    get,
+    http::header::AUTHORIZATION,
+    post,
+    web::{self, Data, Json, Query, ServiceConfig},
+    Error, FromRequest, HttpMessage, HttpRequest, HttpResponse,
+};
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// cookie of a logged in browser, sent with the WebSocket handshake
+const COOKIE: &str = "cnc_token";
+/// failed logins of an address before it has to wait
+const MAX_FAILED_LOGINS: u32 = 5;
+/// wait of an address after too many failed logins
+const LOGIN_LOCKOUT: Duration = Duration::from_secs(60);
+
+/// ordered by the permissions, every role may do what the lower roles may do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    /// status, position and the programs
+    Viewer,
+    /// jog, run programs and runtime settings
+    Operator,
+    /// system and gamepad settings
+    Admin,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSettings {
+    pub name: String,
+    // bcrypt hash of the password (e.g. `htpasswd -nbBC 12 "" secret | tr -d ':\n'`)
+    pub password_hash: String,
+    pub role: Role,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenSettings {
+    // tells the tokens apart in the settings
+    pub name: String,
+    pub token: String,
+    pub role: Role,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthSettings {
+    // without auth every client is admin
+    pub enabled: bool,
+    #[serde(default)]
+    pub users: Vec<UserSettings>,
+    // fixed tokens for scripts and the REST API
+    #[serde(default)]
+    pub tokens: Vec<TokenSettings>,
+    // [h] a login of the UI is valid
+    #[serde(default = "default_session_hours")]
+    pub session_hours: u64,
+}
+
+fn default_session_hours() -> u64 {
+    12
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        AuthSettings {
+            enabled: false,
+            users: vec![],
+            tokens: vec![],
+            session_hours: default_session_hours(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Session {
+    name: String,
+    role: Role,
+    expires: Instant,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LoginError {
+    Invalid,
+    /// too many failed logins of the address
+    Throttled,
+}
+
+/// logins of the UI, they are lost on a restart
+pub struct Auth {
+    settings: AuthSettings,
+    sessions: RwLock<HashMap<String, Session>>,
+    /// failed logins and the last one by address
+    failed: Mutex<HashMap<IpAddr, (u32, Instant)>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Credentials {
+    Password { name: String, password: String },
+    Token { token: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+impl Auth {
+    pub fn new(settings: AuthSettings) -> Self {
+        if settings.enabled && settings.users.is_empty() && settings.tokens.is_empty() {
+            println!("auth is enabled without users or tokens, nobody can use the controller");
+        }
+        for user in settings.users.iter() {
+            if !user.password_hash.starts_with("$2") {
+                println!("{} has no bcrypt password hash and can't login", user.name);
+            }
+        }
+        Auth {
+            settings,
+            sessions: RwLock::new(HashMap::new()),
+            failed: Mutex::new(HashMap::new()),
+        }
+    }
+    /**
+     * check a user or a token, an address with too many failed logins has to wait
+     *
+     * @return the token for the following requests and the role
+     */
+    pub fn login(
+        &self,
+        credentials: Credentials,
+        client: IpAddr,
+    ) -> Result<(String, Role), LoginError> {
+        let throttled = self
+            .failed
+            .lock()
+            .unwrap()
+            .get(&client)
+            .is_some_and(|(count, last)| {
+                *count >= MAX_FAILED_LOGINS && last.elapsed() < LOGIN_LOCKOUT
+            });
+        if throttled {
+            return Err(LoginError::Throttled);
+        }
+        let accepted = self.check(credentials);
+
+        let mut failed = self.failed.lock().unwrap();
+        if accepted.is_some() {
+            failed.remove(&client);
+        } else {
+            failed.retain(|_, (_, last)| last.elapsed() < LOGIN_LOCKOUT);
+            let (count, last) = failed.entry(client).or_insert((0, Instant::now()));
+            *count += 1;
+            *last = Instant::now();
+        }
+        accepted.ok_or(LoginError::Invalid)
+    }
+    fn check(&self, credentials: Credentials) -> Option<(String, Role)> {
+        match credentials {
+            Credentials::Password { name, password } => {
+                let user = self.settings.users.iter().find(|u| {
+                    u.name == name && bcrypt::verify(&password, &u.password_hash).unwrap_or(false)
+                })?;
+                let token = Uuid::new_v4().to_simple().to_string();
+                println!("{} logged in as {:?}", user.name, user.role);
+                let now = Instant::now();
+                let mut sessions = self.sessions.write().unwrap();
+                sessions.retain(|_, s| s.expires > now);
+                sessions.insert(
+                    token.clone(),
+                    Session {
+                        name: user.name.clone(),
+                        role: user.role,
+                        expires: now + Duration::from_secs(self.settings.session_hours * 3600),
+                    },
+                );
+                Some((token, user.role))
+            }
+            Credentials::Token { token } => {
+                let role = self.token_role(&token)?;
+                Some((token, role))
+            }
+        }
+    }
+    pub fn logout(&self, token: &str) {
+        if let Some(s) = self.sessions.write().unwrap().remove(token) {
+            println!("{} logged out", s.name);
+        }
+    }
+    fn token_role(&self, token: &str) -> Option<Role> {
+        if let Some(t) = self.settings.tokens.iter().find(|t| t.token == token) {
+            return Some(t.role);
+        }
+        self.sessions
+            .read()
+            .unwrap()
+            .get(token)
+            .filter(|s| s.expires > Instant::now())
+            .map(|s| s.role)
+    }
+    /// token of a request: bearer header, login cookie or `token` query parameter
+    fn request_token(req: &HttpRequest) -> Option<String> {
+        if let Some(header) = req.headers().get(AUTHORIZATION) {
+            if let Some(token) = header.to_str().ok().and_then(|h| h.strip_prefix("Bearer ")) {
+                return Some(token.trim().to_string());
+            }
+        }
+        if let Some(cookie) = req.cookie(COOKIE) {
+            return Some(cookie.value().to_string());
+        }
+        Query::<TokenQuery>::from_query(req.query_string())
+            .ok()
+            .and_then(|q| q.into_inner().token)
+    }
+    /// role of a token outside of http (e.g. a GRBL sender), Admin if auth is disabled
+    pub fn role_of(&self, token: Option<&str>) -> Option<Role> {
+        if !self.settings.enabled {
+            return Some(Role::Admin);
+        }
+        token.and_then(|token| self.token_role(token))
+    }
+    /// role of the caller, None if auth is enabled and the request has no valid token
+    pub fn role(&self, req: &HttpRequest) -> Option<Role> {
+        if !self.settings.enabled {
+            return Some(Role::Admin);
+        }
+        Auth::request_token(req).and_then(|token| self.token_role(&token))
+    }
+}
+
+/// role of an authenticated request, rejects the request with 401 otherwise
+pub struct Caller(pub Role);
+
+impl FromRequest for Caller {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let role = req.app_data::<Data<Auth>>().and_then(|auth| auth.role(req));
+        ready(match role {
+            Some(role) => Ok(Caller(role)),
+            None => Err(error::ErrorUnauthorized("login required")),
+        })
+    }
+}
+
+pub fn forbidden(required: Role) -> HttpResponse {
+    HttpResponse::Forbidden().json(json!({
+        "error": format!("the command requires the role {:?}", required)
+    }))
+}
+
+#[post("/api/login")]
+async fn login(req: HttpRequest, auth: Data<Auth>, credentials: Json<Credentials>) -> HttpResponse {
+    // browsers drop a secure cookie on plain http
+    let secure = req.connection_info().scheme() == "https";
+    let client = req
+        .peer_addr()
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip());
+    let credentials = credentials.into_inner();
+    // bcrypt takes its time, not on the server thread
+    match web::block(move || auth.login(credentials, client)).await {
+        Ok((token, role)) => HttpResponse::Ok()
+            .cookie(
+                Cookie::build(COOKIE, token.clone())
+                    .path("/")
+                    .http_only(true)
+                    .same_site(SameSite::Strict)
+                    .secure(secure)
+                    .finish(),
+            )
+            .json(json!({ "token": token, "role": role })),
+        Err(BlockingError::Error(LoginError::Throttled)) => HttpResponse::TooManyRequests()
+            .json(json!({ "error": "too many failed logins, try again later" })),
+        Err(_) => HttpResponse::Unauthorized().json(json!({ "error": "invalid credentials" })),
+    }
+}
+
+#[post("/api/logout")]
+async fn logout(req: HttpRequest, auth: Data<Auth>) -> HttpResponse {
+    if let Some(token) = Auth::request_token(&req) {
+        auth.logout(&token);
+    }
+    HttpResponse::Ok()
+        .del_cookie(&Cookie::build(COOKIE, "").path("/").finish())
+        .json(json!({ "ok": true }))
+}
+
+/// role of the caller, the UI shows the login if this fails
+#[get("/api/session")]
+async fn session(caller: Caller, auth: Data<Auth>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "enabled": auth.settings.enabled, "role": caller.0 }))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(login).service(logout).service(session);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> Auth {
+        Auth::new(AuthSettings {
+            enabled: true,
+            users: vec![UserSettings {
+                name: "op".to_string(),
+                password_hash: bcrypt::hash("secret", 4).unwrap(),
+                role: Role::Operator,
+            }],
+            tokens: vec![],
+            session_hours: 1,
+        })
+    }
+
+    fn password(password: &str) -> Credentials {
+        Credentials::Password {
+            name: "op".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[test]
+    fn login_checks_the_bcrypt_hash() {
+        let auth = auth();
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let (token, role) = auth.login(password("secret"), client).unwrap();
+        assert_eq!(role, Role::Operator);
+        assert_eq!(auth.role_of(Some(&token)), Some(Role::Operator));
+        assert_eq!(
+            auth.login(password("wrong"), client),
+            Err(LoginError::Invalid)
+        );
+    }
+
+    #[test]
+    fn failed_logins_lock_the_address_out() {
+        let auth = auth();
+        let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert_eq!(
+                auth.login(password("wrong"), client),
+                Err(LoginError::Invalid)
+            );
+        }
+        assert_eq!(
+            auth.login(password("secret"), client),
+            Err(LoginError::Throttled)
+        );
+        // other addresses can still login
+        assert!(auth
+            .login(password("secret"), IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .is_ok());
+    }
+
+    #[test]
+    fn sessions_expire() {
+        let auth = auth();
+        let (token, _) = auth
+            .login(password("secret"), IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .unwrap();
+        auth.sessions
+            .write()
+            .unwrap()
+            .get_mut(&token)
+            .unwrap()
+            .expires = Instant::now();
+        assert_eq!(auth.role_of(Some(&token)), None);
+    }
+}
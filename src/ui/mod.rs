@@ -1,13 +1,24 @@
+pub mod auth;
+pub mod rest;
 pub mod system;
+pub mod tls;
 pub mod types;
+mod upload;
 pub mod ws_connection;
 
+use crate::app::{metrics::Metrics, settings::Settings, simulation::Simulation};
 use actix::Addr;
 use actix_cors::Cors;
 use actix_files::Files;
-use actix_web::{get, web::Data, web::Payload, App, Error, HttpRequest, HttpResponse, HttpServer};
+use actix_web::{
+    error, get, http::header::HOST, web::Data, web::Payload, App, Error, HttpRequest, HttpResponse,
+    HttpServer,
+};
 use actix_web_actors::ws;
+use auth::Auth;
 use crossbeam_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use system::System;
 use types::{WsCommandsFrom, WsControllerMessage, WsMessages, WsPositionMessage, WsStatusMessage};
 use ws_connection::WsConnection;
@@ -15,40 +26,200 @@ use ws_connection::WsConnection;
 type WsReceiver = Receiver<WsMessages>;
 type WsSender = Sender<WsCommandsFrom>;
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpSettings {
+    // without the server only the GRBL emulation and the terminal UI control the machine
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    // build of the web UI
+    #[serde(default = "default_static_dir")]
+    pub static_dir: String,
+    // serve the web UI, otherwise only the WebSocket and the REST API
+    #[serde(default = "default_true")]
+    pub ui: bool,
+    // origins of a UI served elsewhere (e.g. the dev server), the own origin is always allowed
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+fn default_bind_address() -> String {
+    String::from("0.0.0.0")
+}
+fn default_port() -> u16 {
+    1506u16
+}
+fn default_static_dir() -> String {
+    String::from("./static")
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            enabled: true,
+            bind_address: default_bind_address(),
+            port: default_port(),
+            static_dir: default_static_dir(),
+            ui: true,
+            allowed_origins: vec![],
+        }
+    }
+}
+
+impl HttpSettings {
+    /// address to reach the server from this machine
+    pub fn url(&self, tls: bool) -> String {
+        let host = match self.bind_address.as_str() {
+            "0.0.0.0" | "::" => "localhost",
+            address => address,
+        };
+        let scheme = if tls { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, host, self.port)
+    }
+}
+
+/// the UI served by this server or a configured origin, other sites can't use the login cookie
+fn is_allowed_origin(origin: &str, host: Option<&str>, allowed_origins: &[String]) -> bool {
+    let own = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"));
+    (own.is_some() && own == host) || allowed_origins.iter().any(|o| o == origin)
+}
+
+fn cors(allowed_origins: Vec<String>) -> Cors {
+    Cors::default()
+        .allowed_origin_fn(move |origin, req| {
+            let host = req.headers().get(HOST).and_then(|h| h.to_str().ok());
+            origin
+                .to_str()
+                .is_ok_and(|origin| is_allowed_origin(origin, host, &allowed_origins))
+        })
+        .allow_any_method()
+        .allow_any_header()
+        .supports_credentials()
+}
+
 #[get("/ws")]
 pub async fn web_socket(
     req: HttpRequest,
     stream: Payload,
     srv: Data<Addr<System>>,
+    auth: Data<Auth>,
 ) -> Result<HttpResponse, Error> {
-    let ws = WsConnection::new(srv.get_ref().clone());
+    let role = auth
+        .role(&req)
+        .ok_or_else(|| error::ErrorUnauthorized("login required"))?;
+    let ws = WsConnection::new(srv.get_ref().clone(), role);
 
     let resp = ws::start(ws, &req, stream)?;
     Ok(resp)
 }
 
+/// channels and state the web server shares with the app
+pub struct UiState {
+    pub sender: WsSender,
+    pub receiver: WsReceiver,
+    // first messages for new connections, until the app sends updates
+    pub position: WsPositionMessage,
+    pub status: WsStatusMessage,
+    pub controller: WsControllerMessage,
+    pub metrics: Arc<Metrics>,
+    pub simulation: Arc<Simulation>,
+    pub auth: Arc<Auth>,
+}
+
 #[actix_web::main]
-pub async fn ui_main(
-    sender: WsSender,
-    receiver: WsReceiver,
-    position: WsPositionMessage,
-    status: WsStatusMessage,
-    controller: WsControllerMessage,
-) -> std::io::Result<()> {
+pub async fn ui_main(state: UiState, settings: Settings) -> std::io::Result<()> {
+    let UiState {
+        sender,
+        receiver,
+        position,
+        status,
+        controller,
+        metrics,
+        simulation,
+        auth,
+    } = state;
     let system = System::new(sender, receiver, position, status, controller);
+    let auth = Data::from(auth);
+    let metrics = Data::from(metrics);
+    let simulation = Data::from(simulation);
+    let tls = if settings.tls.enabled {
+        Some(tls::server_config(&settings.tls)?)
+    } else {
+        None
+    };
+    let http = settings.http;
+    let address = (http.bind_address.as_str(), http.port);
 
-    HttpServer::new(move || {
+    let static_dir = http.static_dir.clone();
+    let ui = http.ui;
+    let allowed_origins = http.allowed_origins.clone();
+    let server = HttpServer::new(move || {
+        let static_dir = static_dir.clone();
         App::new()
-            .wrap(Cors::permissive())
+            .wrap(cors(allowed_origins.clone()))
             .data(system.clone())
+            .app_data(auth.clone())
+            .app_data(metrics.clone())
+            .app_data(simulation.clone())
             .service(web_socket)
-            .service(
-                Files::new("/", "./static")
-                    .index_file("index.html")
-                    .show_files_listing(),
-            )
-    })
-    .bind("0.0.0.0:1506")?
+            .configure(auth::config)
+            .configure(rest::config)
+            // registered last, the files would shadow the other routes
+            .configure(move |cfg| {
+                if ui {
+                    cfg.service(
+                        Files::new("/", static_dir)
+                            .index_file("index.html")
+                            .show_files_listing(),
+                    );
+                }
+            })
+    });
+    match tls {
+        Some(config) => server.bind_rustls(address, config)?,
+        None => server.bind(address)?,
+    }
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_own_and_configured_origins_are_allowed() {
+        let allowed = vec!["http://localhost:3000".to_string()];
+        assert!(is_allowed_origin(
+            "http://cnc.local:1506",
+            Some("cnc.local:1506"),
+            &[]
+        ));
+        assert!(is_allowed_origin(
+            "https://cnc.local",
+            Some("cnc.local"),
+            &[]
+        ));
+        assert!(is_allowed_origin(
+            "http://localhost:3000",
+            Some("localhost:1506"),
+            &allowed
+        ));
+        assert!(!is_allowed_origin(
+            "http://evil.example",
+            Some("cnc.local:1506"),
+            &allowed
+        ));
+        assert!(!is_allowed_origin("http://cnc.local:1506", None, &[]));
+        assert!(!is_allowed_origin("null", Some("null"), &[]));
+    }
+}
@@ -0,0 +1,370 @@
+use super::auth::{forbidden, Caller, Role};
+use super::system::{GetState, System};
+use super::types::{
+    RestCommand, WorkOffset, WsCommandControl, WsCommandProgram, WsCommandSettings,
+    WsCommandSettingsSetRuntimeSettings, WsCommands, WsReplyMessage,
+};
+use super::upload;
+use crate::app::{metrics::Metrics, simulation::Simulation};
+use actix::Addr;
+use actix_web::{
+    delete, error, get, post, put,
+    rt::time::timeout,
+    web::{Data, Json, Path, Query, ServiceConfig},
+    Error, HttpResponse,
+};
+use futures::channel::oneshot;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// the app answers within a few loop ticks, a missing reply means the command failed
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+const OPENAPI: &str = include_str!("openapi.json");
+
+pub type Srv = Data<Addr<System>>;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartJob {
+    program_name: String,
+    #[serde(default)]
+    invert_z: bool,
+    #[serde(default = "default_scale")]
+    scale: f64,
+    /// resume the program at this line
+    #[serde(default)]
+    line: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateJob {
+    program_name: String,
+    #[serde(default)]
+    invert_z: bool,
+    #[serde(default = "default_scale")]
+    scale: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Export {
+    format: Option<ExportFormat>,
+}
+
+fn default_scale() -> f64 {
+    1.0f64
+}
+
+/// relative move [mm], a [deg]
+#[derive(Debug, Deserialize)]
+pub struct Jog {
+    #[serde(default)]
+    x: f64,
+    #[serde(default)]
+    y: f64,
+    #[serde(default)]
+    z: f64,
+    #[serde(default)]
+    a: f64,
+}
+
+/// send a command without a reply
+async fn command(srv: &Srv, caller: Caller, cmd: WsCommands) -> HttpResponse {
+    let sent = srv.send(RestCommand {
+        cmd,
+        role: caller.0,
+        reply: None,
+    });
+    match sent.await {
+        Ok(Ok(())) => HttpResponse::Accepted().json(json!({ "ok": true })),
+        Ok(Err(required)) => forbidden(required),
+        Err(_) => no_reply(),
+    }
+}
+
+/**
+ * send a command and wait for the reply of the app
+ *
+ * @return the reply, or the error response if the role is missing or the app did not reply
+ */
+pub async fn reply(srv: &Srv, role: Role, cmd: WsCommands) -> Result<WsReplyMessage, HttpResponse> {
+    let (reply, receiver) = oneshot::channel();
+    let sent = srv.send(RestCommand {
+        cmd,
+        role,
+        reply: Some(reply),
+    });
+    match sent.await {
+        Ok(Ok(())) => (),
+        Ok(Err(required)) => return Err(forbidden(required)),
+        Err(_) => return Err(no_reply()),
+    }
+    match timeout(REPLY_TIMEOUT, receiver).await {
+        Ok(Ok(msg)) => Ok(msg),
+        _ => Err(no_reply()),
+    }
+}
+
+pub fn no_reply() -> HttpResponse {
+    HttpResponse::GatewayTimeout().json(json!({ "error": "the controller did not reply" }))
+}
+
+/// send a command and respond with the reply of the app
+async fn request(srv: &Srv, caller: Caller, cmd: WsCommands) -> HttpResponse {
+    match reply(srv, caller.0, cmd).await {
+        Ok(msg @ WsReplyMessage::ProgramRejected { .. }) => HttpResponse::Forbidden().json(msg),
+        Ok(msg) => HttpResponse::Ok().json(msg),
+        Err(response) => response,
+    }
+}
+
+#[get("/api/openapi.json")]
+async fn openapi() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(OPENAPI)
+}
+
+/// Prometheus metrics, scraped with a token if auth is enabled
+#[get("/metrics")]
+async fn metrics(metrics: Data<Metrics>, _: Caller) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render())
+}
+
+#[get("/api/status")]
+async fn status(srv: Srv, _: Caller) -> Result<HttpResponse, Error> {
+    let state = srv
+        .send(GetState)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(state.status))
+}
+
+#[get("/api/position")]
+async fn position(srv: Srv, _: Caller) -> Result<HttpResponse, Error> {
+    let state = srv
+        .send(GetState)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(state.position))
+}
+
+#[get("/api/programs")]
+async fn list_programs(srv: Srv, caller: Caller) -> HttpResponse {
+    request(&srv, caller, WsCommands::Program(WsCommandProgram::Get)).await
+}
+
+#[get("/api/programs/{name:.*}")]
+async fn load_program(srv: Srv, caller: Caller, name: Path<String>) -> HttpResponse {
+    let program_name = name.into_inner();
+    request(
+        &srv,
+        caller,
+        WsCommands::Program(WsCommandProgram::Load { program_name }),
+    )
+    .await
+}
+
+#[put("/api/programs/{name:.*}")]
+async fn save_program(
+    srv: Srv,
+    caller: Caller,
+    name: Path<String>,
+    program: String,
+) -> HttpResponse {
+    let program_name = name.into_inner();
+    request(
+        &srv,
+        caller,
+        WsCommands::Program(WsCommandProgram::Save {
+            program_name,
+            program,
+        }),
+    )
+    .await
+}
+
+#[delete("/api/programs/{name:.*}")]
+async fn delete_program(srv: Srv, caller: Caller, name: Path<String>) -> HttpResponse {
+    let program_name = name.into_inner();
+    request(
+        &srv,
+        caller,
+        WsCommands::Program(WsCommandProgram::Delete { program_name }),
+    )
+    .await
+}
+
+#[get("/api/history")]
+async fn history(srv: Srv, caller: Caller) -> HttpResponse {
+    request(&srv, caller, WsCommands::Program(WsCommandProgram::History)).await
+}
+
+#[post("/api/job")]
+async fn start_job(srv: Srv, caller: Caller, job: Json<StartJob>) -> HttpResponse {
+    let StartJob {
+        program_name,
+        invert_z,
+        scale,
+        line,
+    } = job.into_inner();
+    let cmd = match line {
+        Some(line) => WsCommandProgram::Resume {
+            program_name,
+            invert_z,
+            scale,
+            line,
+            offset: WorkOffset::default(),
+        },
+        None => WsCommandProgram::Start {
+            program_name,
+            invert_z,
+            scale,
+        },
+    };
+    request(&srv, caller, WsCommands::Program(cmd)).await
+}
+
+#[post("/api/job/cancel")]
+async fn cancel_job(srv: Srv, caller: Caller) -> HttpResponse {
+    request(&srv, caller, WsCommands::Program(WsCommandProgram::Cancel)).await
+}
+
+#[post("/api/job/pause")]
+async fn pause_job(srv: Srv, caller: Caller) -> HttpResponse {
+    command(
+        &srv,
+        caller,
+        WsCommands::Program(WsCommandProgram::Pause { pause: true }),
+    )
+    .await
+}
+
+#[post("/api/job/resume")]
+async fn resume_job(srv: Srv, caller: Caller) -> HttpResponse {
+    command(
+        &srv,
+        caller,
+        WsCommands::Program(WsCommandProgram::Pause { pause: false }),
+    )
+    .await
+}
+
+/// dry run in the background, the summary is sent to the WebSocket clients
+#[post("/api/simulation")]
+async fn simulate(srv: Srv, caller: Caller, job: Json<SimulateJob>) -> HttpResponse {
+    let SimulateJob {
+        program_name,
+        invert_z,
+        scale,
+    } = job.into_inner();
+    command(
+        &srv,
+        caller,
+        WsCommands::Program(WsCommandProgram::Simulate {
+            program_name,
+            invert_z,
+            scale,
+        }),
+    )
+    .await
+}
+
+/// step timeline of the last dry run
+#[get("/api/simulation")]
+async fn simulation(
+    simulation: Data<Simulation>,
+    _: Caller,
+    export: Query<Export>,
+) -> HttpResponse {
+    let result = match simulation.get_last() {
+        Some(result) => result,
+        None => return HttpResponse::NotFound().json(json!({ "error": "nothing simulated yet" })),
+    };
+    match export.into_inner().format {
+        Some(ExportFormat::Csv) => HttpResponse::Ok()
+            .content_type("text/csv")
+            .body(result.to_csv()),
+        _ => HttpResponse::Ok().json(result.as_ref()),
+    }
+}
+
+#[post("/api/jog")]
+async fn jog(srv: Srv, caller: Caller, delta: Json<Jog>) -> HttpResponse {
+    let Jog { x, y, z, a } = delta.into_inner();
+    command(
+        &srv,
+        caller,
+        WsCommands::Control(WsCommandControl::MoveRelative { x, y, z, a }),
+    )
+    .await
+}
+
+#[get("/api/settings/runtime")]
+async fn runtime_settings(srv: Srv, caller: Caller) -> HttpResponse {
+    request(
+        &srv,
+        caller,
+        WsCommands::Settings(WsCommandSettings::GetRuntime),
+    )
+    .await
+}
+
+#[put("/api/settings/runtime")]
+async fn set_runtime_settings(
+    srv: Srv,
+    caller: Caller,
+    settings: Json<WsCommandSettingsSetRuntimeSettings>,
+) -> HttpResponse {
+    request(
+        &srv,
+        caller,
+        WsCommands::Settings(WsCommandSettings::SetRuntime(settings.into_inner())),
+    )
+    .await
+}
+
+#[get("/api/settings/system")]
+async fn system_settings(srv: Srv, caller: Caller) -> HttpResponse {
+    request(
+        &srv,
+        caller,
+        WsCommands::Settings(WsCommandSettings::GetSystem),
+    )
+    .await
+}
+
+/// JSON API next to the WebSocket. The commands run through the same handling as the WS commands
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(openapi)
+        .service(metrics)
+        .service(status)
+        .service(position)
+        .service(list_programs)
+        .service(load_program)
+        .service(save_program)
+        .service(delete_program)
+        .service(history)
+        .service(start_job)
+        .service(cancel_job)
+        .service(pause_job)
+        .service(resume_job)
+        .service(simulate)
+        .service(simulation)
+        .service(jog)
+        .service(runtime_settings)
+        .service(set_runtime_settings)
+        .service(system_settings)
+        .service(upload::upload_program)
+        .service(upload::download_program);
+}
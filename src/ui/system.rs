@@ -1,30 +1,47 @@
+use crate::ui::auth::Role;
 use crate::ui::types::{
-    Connect, Disconnect, WsCommandsFrom, WsConnectedMessage, WsControllerMessage, WsMessages,
-    WsPositionMessage, WsStatusMessage,
+    Connect, Disconnect, InfoLvl, RestCommand, WsCommandsFrom, WsConnectedMessage,
+    WsControllerMessage, WsInfoMessage, WsMessages, WsPositionMessage, WsReplyMessage,
+    WsStatusMessage,
 };
-use actix::{Actor, Addr, Context, Handler, Recipient};
+use actix::{Actor, Addr, Context, Handler, Message, MessageResult, Recipient};
 use crossbeam_channel::{Receiver, Sender};
+use futures::channel::oneshot;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::thread;
 use uuid::Uuid;
 
 type Connection = Recipient<WsMessages>;
+
+#[derive(Debug)]
+struct Session {
+    addr: Connection,
+    role: Role,
+}
 type WsReceiver = Receiver<WsMessages>;
 type WsSender = Sender<WsCommandsFrom>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SystemState {
-    position: WsPositionMessage,
-    status: WsStatusMessage,
-    controller: WsControllerMessage,
+    pub position: WsPositionMessage,
+    pub status: WsStatusMessage,
+    pub controller: WsControllerMessage,
 }
 
+/// last broadcasted state, read by the REST API
+#[derive(Message)]
+#[rtype(result = "SystemState")]
+pub struct GetState;
+
 /// Define System actor
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct System {
-    sessions: HashMap<Uuid, Connection>,
+    sessions: HashMap<Uuid, Session>,
+    // REST requests waiting for a reply of the app
+    rest_replies: HashMap<Uuid, oneshot::Sender<WsReplyMessage>>,
     sender: WsSender,
-    receiver: WsReceiver,
     last_state: SystemState,
 }
 
@@ -43,9 +60,9 @@ impl System {
         controller: WsControllerMessage,
     ) -> Addr<System> {
         let sys = System {
-            sessions: HashMap::<Uuid, Connection>::new(),
+            sessions: HashMap::<Uuid, Session>::new(),
+            rest_replies: HashMap::new(),
             sender,
-            receiver: receiver.clone(),
             last_state: SystemState {
                 position,
                 status,
@@ -72,8 +89,8 @@ impl System {
             .for_each(|(id, _)| self.send_message_to(msg.clone(), id))
     }
     fn send_message_to(&self, msg: WsMessages, id_to: &Uuid) {
-        if let Some(socket_recipient) = self.sessions.get(id_to) {
-            socket_recipient.do_send(msg).unwrap();
+        if let Some(session) = self.sessions.get(id_to) {
+            session.addr.do_send(msg).unwrap();
         } else {
             println!("attempting to send message but couldn't find user id.");
         }
@@ -85,9 +102,16 @@ impl Handler<Connect> for System {
 
     fn handle(&mut self, msg: Connect, _: &mut SystemCtx) -> Self::Result {
         println!("add message {}", msg.self_id);
-        self.sessions.insert(msg.self_id, msg.addr);
+        self.sessions.insert(
+            msg.self_id,
+            Session {
+                addr: msg.addr,
+                role: msg.role,
+            },
+        );
         let welcome = WsMessages::Connected(WsConnectedMessage {
             id: msg.self_id.to_string(),
+            role: msg.role,
         });
         self.send_message_to(welcome, &msg.self_id);
         self.send_message_to(
@@ -120,8 +144,12 @@ impl Handler<WsMessages> for System {
 
     fn handle(&mut self, msg: WsMessages, _: &mut SystemCtx) -> Self::Result {
         if let WsMessages::Reply { to, msg } = msg {
-            let new_msg = WsMessages::Reply { to, msg };
-            self.send_message_to(new_msg, &to);
+            if let Some(reply) = self.rest_replies.remove(&to) {
+                let _ = reply.send(msg);
+            } else {
+                let new_msg = WsMessages::Reply { to, msg };
+                self.send_message_to(new_msg, &to);
+            }
         } else {
             match msg {
                 WsMessages::Controller(ref controller) => {
@@ -140,6 +168,164 @@ impl Handler<WsCommandsFrom> for System {
     type Result = ();
 
     fn handle(&mut self, msg: WsCommandsFrom, _: &mut SystemCtx) -> Self::Result {
+        let role = match self.sessions.get(&msg.0) {
+            Some(session) => session.role,
+            None => return,
+        };
+        let required = msg.1.required_role();
+        if role < required {
+            let info = WsInfoMessage::new(
+                InfoLvl::Error,
+                format!("the command requires the role {:?}", required),
+            );
+            self.send_message_to(WsMessages::Info(info), &msg.0);
+            return;
+        }
         self.sender.send(msg).unwrap();
     }
 }
+
+impl Handler<RestCommand> for System {
+    type Result = Result<(), Role>;
+
+    fn handle(&mut self, msg: RestCommand, _: &mut SystemCtx) -> Self::Result {
+        let required = msg.cmd.required_role();
+        if msg.role < required {
+            return Err(required);
+        }
+        // forget requests that timed out without a reply
+        self.rest_replies.retain(|_, reply| !reply.is_canceled());
+        let id = Uuid::new_v4();
+        if let Some(reply) = msg.reply {
+            self.rest_replies.insert(id, reply);
+        }
+        self.sender.send(WsCommandsFrom(id, msg.cmd)).unwrap();
+        Ok(())
+    }
+}
+
+impl Handler<GetState> for System {
+    type Result = MessageResult<GetState>;
+
+    fn handle(&mut self, _: GetState, _: &mut SystemCtx) -> Self::Result {
+        MessageResult(self.last_state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Location;
+    use crate::ui::types::{Mode, WsCommandProgram, WsCommandSettings, WsCommands};
+    use crossbeam_channel::unbounded;
+    use std::time::Duration;
+
+    /// WS connection that keeps the messages of the System
+    struct Client(Sender<WsMessages>);
+
+    impl Actor for Client {
+        type Context = Context<Client>;
+    }
+
+    impl Handler<WsMessages> for Client {
+        type Result = ();
+
+        fn handle(&mut self, msg: WsMessages, _: &mut Context<Client>) -> Self::Result {
+            let _ = self.0.send(msg);
+        }
+    }
+
+    fn start() -> WsCommands {
+        WsCommands::Program(WsCommandProgram::Start {
+            program_name: "a.nc".to_string(),
+            invert_z: false,
+            scale: 1.0,
+        })
+    }
+
+    fn system(sender: WsSender, receiver: WsReceiver) -> Addr<System> {
+        System::new(
+            sender,
+            receiver,
+            WsPositionMessage::new(0.0, 0.0, 0.0, None),
+            WsStatusMessage::new(Mode::Manual, true, false, None, false, 0, 0, false, false),
+            WsControllerMessage::new(&Location::default(), false, false, false, false, false, 1.0),
+        )
+    }
+
+    #[test]
+    fn rest_commands_below_the_required_role_are_refused() {
+        actix::System::new("test").block_on(async {
+            let (sender, commands) = unbounded();
+            let (_ui, receiver) = unbounded();
+            let sys = system(sender, receiver);
+            let rest = |cmd, role| RestCommand {
+                cmd,
+                role,
+                reply: None,
+            };
+
+            let viewer = sys.send(rest(start(), Role::Viewer)).await.unwrap();
+            assert_eq!(viewer, Err(Role::Operator));
+            let settings = WsCommands::Settings(WsCommandSettings::GetSystem);
+            let operator = sys.send(rest(settings, Role::Operator)).await.unwrap();
+            assert_eq!(operator, Err(Role::Admin));
+            assert!(commands.try_recv().is_err());
+
+            let operator = sys.send(rest(start(), Role::Operator)).await.unwrap();
+            assert_eq!(operator, Ok(()));
+            assert!(commands.try_recv().is_ok());
+        });
+    }
+
+    #[test]
+    fn ws_commands_below_the_required_role_are_refused() {
+        actix::System::new("test").block_on(async {
+            let (sender, commands) = unbounded();
+            let (_ui, receiver) = unbounded();
+            let sys = system(sender, receiver);
+            let (client_sender, client) = unbounded();
+            let connect = |role| {
+                let id = Uuid::new_v4();
+                let addr = Client(client_sender.clone()).start().recipient();
+                (
+                    id,
+                    Connect {
+                        addr,
+                        self_id: id,
+                        role,
+                    },
+                )
+            };
+
+            let (viewer, msg) = connect(Role::Viewer);
+            sys.send(msg).await.unwrap();
+            let (operator, msg) = connect(Role::Operator);
+            sys.send(msg).await.unwrap();
+
+            sys.send(WsCommandsFrom(viewer, start())).await.unwrap();
+            let settings = WsCommands::Settings(WsCommandSettings::GetSystem);
+            sys.send(WsCommandsFrom(operator, settings)).await.unwrap();
+            assert!(commands.try_recv().is_err());
+
+            sys.send(WsCommandsFrom(operator, start())).await.unwrap();
+            assert!(matches!(commands.try_recv(), Ok(WsCommandsFrom(id, _)) if id == operator));
+
+            // the refused sessions are told why
+            actix::clock::delay_for(Duration::from_millis(20)).await;
+            let refused = client
+                .try_iter()
+                .filter(|msg| {
+                    matches!(
+                        msg,
+                        WsMessages::Info(WsInfoMessage {
+                            lvl: InfoLvl::Error,
+                            ..
+                        })
+                    )
+                })
+                .count();
+            assert_eq!(refused, 2);
+        });
+    }
+}
@@ -0,0 +1,104 @@
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::{NoClientAuth, ServerConfig};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Error, ErrorKind, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+};
+
+/// used if no certificate is configured, generated on the first start
+const SELF_SIGNED_CERT: &str = "./tls/cert.pem";
+const SELF_SIGNED_KEY: &str = "./tls/key.pem";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsSettings {
+    // serve the UI, the WebSocket and the REST API with https / wss
+    pub enabled: bool,
+    // PEM certificate chain, a self-signed certificate is generated if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert: Option<String>,
+    // PEM private key (PKCS8 or RSA)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// names of this machine for the self-signed certificate
+fn host_names() -> Vec<String> {
+    let mut names = vec!["localhost".to_string()];
+    if let Ok(host) = fs::read_to_string("/etc/hostname") {
+        let host = host.trim();
+        if !host.is_empty() {
+            names.push(host.to_string());
+            names.push(format!("{}.local", host));
+        }
+    }
+    names
+}
+
+fn generate_self_signed(cert_path: &str, key_path: &str) -> io::Result<()> {
+    println!("generate a self-signed certificate {}", cert_path);
+    let cert = rcgen::generate_simple_self_signed(host_names())
+        .map_err(|e| invalid(format!("{:?}", e)))?;
+    let cert_pem = cert
+        .serialize_pem()
+        .map_err(|e| invalid(format!("{:?}", e)))?;
+    for path in [cert_path, key_path].iter() {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+    fs::write(cert_path, cert_pem)?;
+    // the key is only readable by the controller
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(key_path)?
+        .write_all(cert.serialize_private_key_pem().as_bytes())?;
+    Ok(())
+}
+
+/**
+ * read the configured certificate or the self-signed one
+ *
+ * @return the rustls config of the server
+ */
+pub fn server_config(settings: &TlsSettings) -> io::Result<ServerConfig> {
+    let (cert_path, key_path) = match (settings.cert.as_ref(), settings.key.as_ref()) {
+        (Some(cert), Some(key)) => (cert.to_owned(), key.to_owned()),
+        (None, None) => {
+            if !Path::new(SELF_SIGNED_CERT).exists() || !Path::new(SELF_SIGNED_KEY).exists() {
+                generate_self_signed(SELF_SIGNED_CERT, SELF_SIGNED_KEY)?;
+            }
+            (SELF_SIGNED_CERT.to_string(), SELF_SIGNED_KEY.to_string())
+        }
+        _ => return Err(invalid("tls needs both cert and key".to_string())),
+    };
+
+    let cert_chain = certs(&mut BufReader::new(File::open(&cert_path)?))
+        .map_err(|_| invalid(format!("no certificate in {}", cert_path)))?;
+    let mut keys = pkcs8_private_keys(&mut BufReader::new(File::open(&key_path)?))
+        .map_err(|_| invalid(format!("invalid key {}", key_path)))?;
+    if keys.is_empty() {
+        keys = rsa_private_keys(&mut BufReader::new(File::open(&key_path)?))
+            .map_err(|_| invalid(format!("invalid key {}", key_path)))?;
+    }
+    let key = keys
+        .into_iter()
+        .next()
+        .ok_or_else(|| invalid(format!("no private key in {}", key_path)))?;
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(cert_chain, key)
+        .map_err(|e| invalid(format!("{:?}", e)))?;
+    Ok(config)
+}
@@ -1,7 +1,11 @@
 #![allow(clippy::too_many_arguments)]
-use crate::motor::MotorSettings;
+use crate::app::gamepad::GamepadMapping;
+use crate::gnc::ProgramAnalysis;
+use crate::motor::{MotorSettings, PulseBackendType};
 use crate::types::Location;
+use crate::ui::auth::Role;
 use actix::prelude::{Message, Recipient};
+use futures::channel::oneshot;
 use serde::{Deserialize, Serialize};
 use std::{fs, time::SystemTime};
 use uuid::Uuid;
@@ -11,6 +15,7 @@ use uuid::Uuid;
 pub struct Connect {
     pub addr: Recipient<WsMessages>,
     pub self_id: Uuid,
+    pub role: Role,
 }
 
 #[derive(Message)]
@@ -19,6 +24,15 @@ pub struct Disconnect {
     pub id: Uuid,
 }
 
+/// command of the REST API. The reply goes back to the request instead of a WS session
+#[derive(Message)]
+#[rtype(result = "Result<(), Role>")]
+pub struct RestCommand {
+    pub cmd: WsCommands,
+    pub role: Role,
+    pub reply: Option<oneshot::Sender<WsReplyMessage>>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Mode {
@@ -31,6 +45,7 @@ pub enum Mode {
 #[serde(rename_all = "camelCase")]
 pub struct WsConnectedMessage {
     pub id: String,
+    pub role: Role,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -60,10 +75,13 @@ pub struct WsPositionMessage {
     pub x: f64,
     pub y: f64,
     pub z: f64,
+    /// angle of the rotary axis [deg], only sent if the machine has an A axis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub a: Option<f64>,
 }
 impl WsPositionMessage {
-    pub fn new(x: f64, y: f64, z: f64) -> WsPositionMessage {
-        WsPositionMessage { x, y, z }
+    pub fn new(x: f64, y: f64, z: f64, a: Option<f64>) -> WsPositionMessage {
+        WsPositionMessage { x, y, z, a }
     }
 }
 
@@ -76,6 +94,12 @@ pub struct WsControllerMessage {
     pub freeze_x: bool,
     pub freeze_y: bool,
     pub slow: bool,
+    /// a gamepad is connected
+    pub connected: bool,
+    /// incremental jog instead of stick control
+    pub jog: bool,
+    /// jog step size [mm]
+    pub jog_step: f64,
 }
 impl WsControllerMessage {
     pub fn new(
@@ -83,6 +107,9 @@ impl WsControllerMessage {
         freeze_x: bool,
         freeze_y: bool,
         slow: bool,
+        connected: bool,
+        jog: bool,
+        jog_step: f64,
     ) -> WsControllerMessage {
         WsControllerMessage {
             x: pos.x,
@@ -91,6 +118,9 @@ impl WsControllerMessage {
             freeze_x,
             freeze_y,
             slow,
+            connected,
+            jog,
+            jog_step,
         }
     }
 }
@@ -106,6 +136,8 @@ pub struct WsStatusMessage {
     pub steps_todo: i64,
     pub steps_done: i64,
     pub is_switched_on: bool,
+    /// feed hold of the running program
+    pub paused: bool,
 }
 impl WsStatusMessage {
     pub fn new(
@@ -117,6 +149,7 @@ impl WsStatusMessage {
         steps_todo: i64,
         steps_done: i64,
         is_switched_on: bool,
+        paused: bool,
     ) -> WsStatusMessage {
         WsStatusMessage {
             mode,
@@ -127,6 +160,7 @@ impl WsStatusMessage {
             steps_todo,
             steps_done,
             is_switched_on,
+            paused,
         }
     }
 }
@@ -140,6 +174,9 @@ pub struct ProgramInfo {
     pub lines_of_code: u32,
     pub create_date_ts: u64,
     pub modified_date_ts: u64,
+    /// known after the program was selected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<ProgramAnalysis>,
 }
 
 impl ProgramInfo {
@@ -162,6 +199,7 @@ impl ProgramInfo {
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            analysis: None,
         }
     }
 }
@@ -173,8 +211,88 @@ pub struct WsAvailableProgramsMessage {
     pub input_dir: Vec<String>,
 }
 
+/// work offset of a queued job [mm], added to the absolute program coordinates
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkOffset {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedJob {
+    pub id: u32,
+    pub program_name: String,
+    pub invert_z: bool,
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: WorkOffset,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueState {
+    #[default]
+    Stopped,
+    /// the first job of the queue runs
+    Running,
+    /// a job is done, the next one starts when the operator confirms (e.g. after a tool change)
+    WaitingForConfirm,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsQueueMessage {
+    pub state: QueueState,
+    pub jobs: Vec<QueuedJob>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobOutcome {
+    Running,
+    Completed,
+    Cancelled,
+    /// the controller stopped while the job ran (crash, power loss)
+    Alarm,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRecord {
+    pub program_name: String,
+    /// sha256 of the program as hex
+    pub hash: String,
+    pub started_ts: u64,
+    pub ended_ts: Option<u64>,
+    pub outcome: JobOutcome,
+    /// last line the machine started to execute
+    pub line: usize,
+    pub scale: f64,
+    pub invert_z: bool,
+    /// work offset the job ran with, a resume uses it again
+    #[serde(default)]
+    pub offset: WorkOffset,
+}
+
+/// result of a dry run. The step timeline is exported by the REST API
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsSimulationMessage {
+    pub program_name: String,
+    /// [s] on the virtual clock
+    pub duration: f64,
+    pub steps: u64,
+    /// lowest motor positions [x, y, z] in mm
+    pub min: [f64; 3],
+    /// highest motor positions [x, y, z] in mm
+    pub max: [f64; 3],
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
+#[allow(clippy::large_enum_variant)]
 pub enum WsReplyMessage {
     AvailablePrograms(WsAvailableProgramsMessage),
     #[serde(rename_all = "camelCase")]
@@ -202,6 +320,14 @@ pub enum WsReplyMessage {
         ok: bool,
     },
     #[serde(rename_all = "camelCase")]
+    ProgramRejected {
+        program_name: String,
+        reason: String,
+    },
+    History {
+        jobs: Vec<JobRecord>,
+    },
+    #[serde(rename_all = "camelCase")]
     RuntimeSettings {
         input_dir: Vec<String>,
         input_update_reduce: u32,
@@ -211,6 +337,9 @@ pub enum WsReplyMessage {
         invert_z: bool,
         show_console_output: bool,
         console_pos_update_reduce: u32,
+        arc_tolerance: f64,
+        jog_timeout: f64,
+        max_upload_size: u64,
     },
     RuntimeSettingsSaved {
         ok: bool,
@@ -222,19 +351,33 @@ pub enum WsReplyMessage {
         motor_y: MotorSettings,
         motor_z: MotorSettings,
         #[serde(skip_serializing_if = "Option::is_none")]
+        motor_x_slave: Option<MotorSettings>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        motor_y_slave: Option<MotorSettings>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        motor_a: Option<MotorSettings>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         calibrate_z_gpio: Option<u8>,
         #[serde(skip_serializing_if = "Option::is_none")]
         on_off_gpio: Option<u8>,
         switch_on_off_delay: f64,
+        pulse_backend: PulseBackendType,
+        pulse_latency: f64,
     },
     SystemSettingsSaved {
         ok: bool,
     },
+    GamepadSettings(GamepadMapping),
+    GamepadSettingsSaved {
+        ok: bool,
+    },
+    Queue(WsQueueMessage),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Message)]
 #[serde(rename_all = "camelCase", tag = "type")]
 #[rtype(result = "()")]
+#[allow(clippy::large_enum_variant)]
 pub enum WsMessages {
     Connected(WsConnectedMessage),
     Info(WsInfoMessage),
@@ -242,6 +385,8 @@ pub enum WsMessages {
     ProgsUpdate(WsAvailableProgramsMessage),
     Controller(WsControllerMessage),
     Status(WsStatusMessage),
+    Queue(WsQueueMessage),
+    Simulation(WsSimulationMessage),
     Reply { to: Uuid, msg: WsReplyMessage },
 }
 
@@ -251,11 +396,32 @@ pub struct WsCommandsFrom(pub Uuid, pub WsCommands);
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "cmd")]
+#[allow(clippy::large_enum_variant)]
 pub enum WsCommands {
     Program(WsCommandProgram),
     Control(WsCommandControl),
     Controller(WsCommandController),
     Settings(WsCommandSettings),
+    Queue(WsCommandQueue),
+}
+
+impl WsCommands {
+    /// lowest role allowed to send the command
+    pub fn required_role(&self) -> Role {
+        match self {
+            WsCommands::Program(WsCommandProgram::Get)
+            | WsCommands::Program(WsCommandProgram::Load { .. })
+            | WsCommands::Program(WsCommandProgram::History)
+            | WsCommands::Queue(WsCommandQueue::Get) => Role::Viewer,
+            WsCommands::Program(_)
+            | WsCommands::Control(_)
+            | WsCommands::Controller(_)
+            | WsCommands::Queue(_) => Role::Operator,
+            WsCommands::Settings(WsCommandSettings::GetRuntime)
+            | WsCommands::Settings(WsCommandSettings::GetGamepad) => Role::Operator,
+            WsCommands::Settings(_) => Role::Admin,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -282,31 +448,120 @@ pub enum WsCommandProgram {
         scale: f64,
     },
     Cancel,
+    /// read the input directories again, e.g. after an upload
+    Refresh,
+    /// feed hold of the running program
+    Pause {
+        pause: bool,
+    },
+    /// started programs, the newest last
+    History,
+    /// start a program at a line (e.g. after a broken bit) with a safe approach from above
+    #[serde(rename_all = "camelCase")]
+    Resume {
+        program_name: String,
+        invert_z: bool,
+        scale: f64,
+        line: usize,
+        #[serde(default)]
+        offset: WorkOffset,
+    },
+    /// dry run of a program on a virtual clock, the machine does not move
+    #[serde(rename_all = "camelCase")]
+    Simulate {
+        program_name: String,
+        invert_z: bool,
+        scale: f64,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "action")]
+pub enum WsCommandQueue {
+    Get,
+    #[serde(rename_all = "camelCase")]
+    Add {
+        program_name: String,
+        invert_z: bool,
+        scale: f64,
+        #[serde(default)]
+        offset: WorkOffset,
+    },
+    Remove {
+        id: u32,
+    },
+    /// move a job to the given position of the queue
+    Move {
+        id: u32,
+        index: usize,
+    },
+    /// remove all jobs that are not running
+    Clear,
+    /// run the first job
+    Start,
+    /// run the next job after the pause between two jobs
+    Confirm,
+    /// do not start further jobs. A running job is finished
+    Stop,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "action")]
 pub enum WsCommandControl {
     // Move { direction: string, speed: f64},
-    OnOff { on: bool },
+    OnOff {
+        on: bool,
+    },
+    /// move exactly x, y, z [mm] and a [deg] relative to the current position
+    MoveRelative {
+        x: f64,
+        y: f64,
+        z: f64,
+        #[serde(default)]
+        a: f64,
+    },
+    /// drive x and y into their min end switches
+    Home,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "action")]
 pub enum WsCommandController {
-    FreezeX { freeze: bool },
-    FreezeY { freeze: bool },
-    Slow { slow: bool },
+    FreezeX {
+        freeze: bool,
+    },
+    FreezeY {
+        freeze: bool,
+    },
+    Slow {
+        slow: bool,
+    },
+    Jog {
+        jog: bool,
+    },
+    /// virtual joystick. Speed factors from -1.0 to 1.0, repeated by the UI while the stick is held
+    Move {
+        x: f64,
+        y: f64,
+        z: f64,
+    },
+    /// jog step size [mm]
+    JogStep {
+        step: f64,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "action")]
+#[allow(clippy::large_enum_variant)]
 pub enum WsCommandSettings {
     GetSystem,
     #[serde(rename_all = "camelCase")]
     SetSystem(WsCommandSettingsSetSystemSettings),
     GetRuntime,
     SetRuntime(WsCommandSettingsSetRuntimeSettings),
+    GetGamepad,
+    SetGamepad(GamepadMapping),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -316,11 +571,25 @@ pub struct WsCommandSettingsSetSystemSettings {
     pub motor_x: MotorSettings,
     pub motor_y: MotorSettings,
     pub motor_z: MotorSettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motor_x_slave: Option<MotorSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motor_y_slave: Option<MotorSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motor_a: Option<MotorSettings>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub calibrate_z_gpio: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub on_off_gpio: Option<u8>,
     pub switch_on_off_delay: f64,
+    #[serde(default)]
+    pub pulse_backend: PulseBackendType,
+    #[serde(default = "default_pulse_latency")]
+    pub pulse_latency: f64,
+}
+
+fn default_pulse_latency() -> f64 {
+    0.02f64
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -344,4 +613,40 @@ pub struct WsCommandSettingsSetRuntimeSettings {
     pub console_pos_update_reduce: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_input_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arc_tolerance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jog_timeout: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_upload_size: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> WsCommands {
+        WsCommands::Program(WsCommandProgram::Start {
+            program_name: "a.nc".to_string(),
+            invert_z: false,
+            scale: 1.0,
+        })
+    }
+
+    #[test]
+    fn required_role_orders_the_commands() {
+        let viewer = WsCommands::Program(WsCommandProgram::Get);
+        let jog = WsCommands::Control(WsCommandControl::OnOff { on: true });
+        let runtime = WsCommands::Settings(WsCommandSettings::GetRuntime);
+        let system = WsCommands::Settings(WsCommandSettings::GetSystem);
+
+        assert_eq!(viewer.required_role(), Role::Viewer);
+        assert_eq!(start().required_role(), Role::Operator);
+        assert_eq!(jog.required_role(), Role::Operator);
+        assert_eq!(runtime.required_role(), Role::Operator);
+        assert_eq!(system.required_role(), Role::Admin);
+
+        assert!(Role::Viewer < start().required_role());
+        assert!(Role::Operator < system.required_role());
+    }
 }
@@ -0,0 +1,198 @@
+use super::auth::Caller;
+use super::rest::{no_reply, reply, Srv};
+use super::system::GetState;
+use super::types::{Mode, WsCommandProgram, WsCommandSettings, WsCommands, WsReplyMessage};
+use crate::app::{is_program, PROGRAM_EXTENSIONS};
+use actix_files::NamedFile;
+use actix_multipart::Multipart;
+use actix_web::{
+    error, get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    post,
+    web::{self, Path, Query},
+    Error, HttpResponse,
+};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path as FsPath, PathBuf},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct UploadTarget {
+    /// one of the input directories, the first one if missing
+    dir: Option<String>,
+    /// replace programs with the same name
+    #[serde(default)]
+    overwrite: bool,
+}
+
+fn bad_request(msg: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({ "error": msg }))
+}
+
+/// multipart upload of one or more programs into an input directory
+#[post("/api/upload")]
+pub async fn upload_program(
+    srv: Srv,
+    caller: Caller,
+    target: Query<UploadTarget>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, Error> {
+    let runtime = reply(
+        &srv,
+        caller.0,
+        WsCommands::Settings(WsCommandSettings::GetRuntime),
+    )
+    .await;
+    let (input_dir, max_upload_size) = match runtime {
+        Ok(WsReplyMessage::RuntimeSettings {
+            input_dir,
+            max_upload_size,
+            ..
+        }) => (input_dir, max_upload_size),
+        Ok(_) => return Ok(no_reply()),
+        Err(response) => return Ok(response),
+    };
+    let UploadTarget { dir, overwrite } = target.into_inner();
+    let dir = match dir {
+        Some(dir) if input_dir.contains(&dir) => dir,
+        Some(dir) => return Ok(bad_request(format!("{} is no input directory", dir))),
+        None => match input_dir.first() {
+            Some(dir) => dir.to_owned(),
+            None => return Ok(bad_request("no input directory configured".to_string())),
+        },
+    };
+    let max_bytes = max_upload_size * 1024 * 1024;
+
+    // the file of the running program must not change under the motors
+    let status = srv
+        .send(GetState)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .status;
+    let running = match status.current_prog {
+        Some(program) if status.mode == Mode::Program => {
+            web::block(move || fs::canonicalize(program)).await.ok()
+        }
+        _ => None,
+    };
+
+    let mut saved = vec![];
+    while let Some(mut field) = payload.try_next().await? {
+        // form fields without a file are skipped
+        let file_name = match field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename().map(|name| name.to_string()))
+        {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+        // only the name is used, a path in the upload can't leave the input directory
+        let file_name = match FsPath::new(&file_name).file_name().and_then(|n| n.to_str()) {
+            Some(file_name) => file_name.to_string(),
+            None => return Ok(bad_request(format!("invalid file name {}", file_name))),
+        };
+        if !is_program(&file_name) {
+            return Ok(HttpResponse::UnsupportedMediaType().json(json!({
+                "error": format!("{} is no program ({})", file_name, PROGRAM_EXTENSIONS.join(", "))
+            })));
+        }
+
+        // written to a temporary file, the watcher and the program list see it once it is complete
+        let path = FsPath::new(&dir).join(&file_name);
+        let existing = {
+            let path = path.clone();
+            web::block(move || fs::canonicalize(path)).await.ok()
+        };
+        if let Some(existing) = existing {
+            if running.as_ref() == Some(&existing) {
+                return Ok(HttpResponse::Conflict().json(json!({
+                    "error": format!("{} is the running program", file_name)
+                })));
+            }
+            if !overwrite {
+                return Ok(HttpResponse::Conflict().json(json!({
+                    "error": format!("{} exists already", file_name),
+                    "exists": true
+                })));
+            }
+        }
+        let part = PathBuf::from(format!("{}.part", path.display()));
+        let mut file = {
+            let part = part.clone();
+            web::block(move || File::create(part))
+                .await
+                .map_err(error::ErrorInternalServerError)?
+        };
+        let mut size = 0u64;
+        while let Some(chunk) = field.try_next().await? {
+            size += chunk.len() as u64;
+            if size > max_bytes {
+                drop(file);
+                let _ = web::block(move || fs::remove_file(part)).await;
+                return Ok(HttpResponse::PayloadTooLarge().json(json!({
+                    "error": format!("{} is larger than {} MB", file_name, max_upload_size)
+                })));
+            }
+            file = web::block(move || file.write_all(&chunk).map(|_| file))
+                .await
+                .map_err(error::ErrorInternalServerError)?;
+        }
+        saved.push(path.to_string_lossy().to_string());
+        web::block(move || fs::rename(part, path))
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+    if saved.is_empty() {
+        return Ok(bad_request("no file in the upload".to_string()));
+    }
+
+    // list the programs right away instead of waiting for the file watcher
+    let _ = reply(
+        &srv,
+        caller.0,
+        WsCommands::Program(WsCommandProgram::Refresh),
+    )
+    .await;
+    Ok(HttpResponse::Created().json(json!({ "programs": saved })))
+}
+
+/// download a listed program as attachment
+#[get("/api/download/{name:.*}")]
+pub async fn download_program(
+    srv: Srv,
+    caller: Caller,
+    name: Path<String>,
+) -> Result<NamedFile, Error> {
+    let program_name = name.into_inner();
+    // the client may have normalized the listed path (e.g. `./`)
+    let requested = fs::canonicalize(&program_name).ok();
+    let listed = match reply(&srv, caller.0, WsCommands::Program(WsCommandProgram::Get)).await {
+        Ok(WsReplyMessage::AvailablePrograms(programs)) => programs
+            .progs
+            .iter()
+            .any(|p| requested.is_some() && fs::canonicalize(&p.name).ok() == requested),
+        _ => false,
+    };
+    if !listed {
+        return Err(error::ErrorNotFound(format!(
+            "{} is no program",
+            program_name
+        )));
+    }
+
+    let file_name = FsPath::new(&program_name)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(
+        NamedFile::open(&program_name)?.set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(file_name)],
+        }),
+    )
+}
@@ -1,3 +1,4 @@
+use super::auth::Role;
 use super::system::System;
 use crate::ui::types::{Connect, Disconnect, WsCommands, WsCommandsFrom, WsMessages};
 use actix::{
@@ -19,6 +20,7 @@ pub struct WsConnection {
     id: Uuid,
     system_addr: Addr<System>,
     hb: Instant,
+    role: Role,
 }
 
 impl Actor for WsConnection {
@@ -32,6 +34,7 @@ impl Actor for WsConnection {
             .send(Connect {
                 addr: addr.recipient(),
                 self_id: self.id,
+                role: self.role,
             })
             .into_actor(self)
             .then(|res, _, ctx| {
@@ -51,11 +54,12 @@ impl Actor for WsConnection {
 }
 
 impl WsConnection {
-    pub fn new(system: Addr<System>) -> WsConnection {
+    pub fn new(system: Addr<System>, role: Role) -> WsConnection {
         WsConnection {
             id: uuid::Uuid::new_v4(),
             hb: Instant::now(),
             system_addr: system,
+            role,
         }
     }
 
@@ -99,7 +103,10 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsConnection {
                     println!("got unknown message {}", s);
                 }
             }
-            Err(e) => panic!(e),
+            Err(e) => {
+                println!("ws protocol error {:?}", e);
+                ctx.stop();
+            }
         }
     }
 }
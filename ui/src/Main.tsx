@@ -16,18 +16,31 @@ import { MainView } from './views/MainView'
 import { ProgramView } from './views/ProgramView'
 import { SettingsView } from './views/SettingsView'
 import { CalibrateView } from './views/CalibrateView'
+import { Login } from './widget/Login'
 import './responsive.css'
+import { apiUrl, wsUrl } from './services/url'
 
 export const Main = () => {
   const [service, setService] = useState<Service>()
   const [mode, setMode] = useState<Mode>('main')
+  const [login, setLogin] = useState(false)
 
-  const connect = () => {
-    const ws = new WebSocket(`ws://${window.location.hostname}:1506/ws`)
-    ws.onopen = (_) => setService(mkServiceCtx(ws))
-    ws.addEventListener('close', () => setTimeout(connect, 1000))
-  }
-  useEffect(() => connect(), [])
+  // the WebSocket needs a valid session if the controller has auth enabled
+  const connect = () =>
+    fetch(`${apiUrl}/session`, { credentials: 'include' })
+      .then((res) => {
+        setLogin(res.status === 401)
+        if (res.status === 401) {
+          return
+        }
+        const ws = new WebSocket(wsUrl)
+        ws.onopen = (_) => setService(mkServiceCtx(ws))
+        ws.addEventListener('close', () => setTimeout(connect, 1000))
+      })
+      .catch(() => setTimeout(connect, 1000))
+  useEffect(() => {
+    connect()
+  }, [])
 
   const View = () => {
     switch (mode) {
@@ -51,7 +64,7 @@ export const Main = () => {
           <AlertBox />
           <Menu mode={mode} onChanged={setMode} />
           <div style={{ width: '100%', height: '100vh' }}>
-            <View />
+            {login ? <Login onLoggedIn={connect} /> : <View />}
             <InfoBar />
           </div>
         </div>
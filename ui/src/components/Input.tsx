@@ -5,16 +5,18 @@ import { createUseStyles } from 'react-jss'
 type Props = {
   width?: string | number
   value: string | number
+  type?: string
   onChanged: (value: string) => void
   onBlur?: (value: string) => void
 }
 
-export const Input = ({ width, value, onChanged, onBlur }: Props) => {
+export const Input = ({ width, value, type, onChanged, onBlur }: Props) => {
   const { inputStyle } = useStyle()
   return (
     <div className={inputStyle}>
       <input
         width={width}
+        type={type}
         value={value}
         onChange={(e) => onChanged(e.target.value)}
         onBlur={(e) => onBlur && onBlur(e.target.value)}
@@ -112,6 +112,18 @@ export const MotorBox = ({
           })
         }
       />
+      <InputField
+        title="Backlash (mm)"
+        type="number"
+        value={motor.backlash}
+        defaultValue={0}
+        onChanged={(value) =>
+          onChange({
+            ...motor,
+            backlash: value
+          })
+        }
+      />
       <MotorRampUp
         stepSize={motor.stepSize}
         freeRunSpeed={motor.freeStepSpeed}
@@ -4,12 +4,17 @@ import { Observable, of, BehaviorSubject, ReplaySubject } from 'rxjs'
 
 // -------------- Messages
 
-export const positionMessageC = t.type({
-  type: t.literal('position'),
-  x: t.number,
-  y: t.number,
-  z: t.number
-})
+export const positionMessageC = t.intersection([
+  t.type({
+    type: t.literal('position'),
+    x: t.number,
+    y: t.number,
+    z: t.number
+  }),
+  t.partial({
+    a: t.number
+  })
+])
 export type PositionMessage = t.TypeOf<typeof positionMessageC>
 
 export const isPositionMessage = (msg: object): msg is PositionMessage =>
@@ -24,7 +29,8 @@ export const statusMessageC = t.type({
   calibrated: t.boolean,
   stepsTodo: t.number,
   stepsDone: t.number,
-  isSwitchedOn: t.boolean
+  isSwitchedOn: t.boolean,
+  paused: t.boolean
 })
 export type StatusMessage = t.TypeOf<typeof statusMessageC>
 
@@ -84,7 +90,8 @@ const broadcastServiceMock: BroadcastService = {
     currentProg: null,
     stepsDone: 0,
     stepsTodo: 1,
-    isSwitchedOn: false
+    isSwitchedOn: false,
+    paused: false
   }),
   info$: of({ type: 'info', lvl: 'warning', message: 'testMessage' })
 }
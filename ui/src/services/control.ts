@@ -8,6 +8,18 @@ export type OnOffCommand = {
   action: 'onOff'
   on: boolean
 }
-export type ControlCommand = OnOffCommand
+export type MoveRelativeCommand = {
+  cmd: 'control'
+  action: 'moveRelative'
+  x: number
+  y: number
+  z: number
+  a?: number
+}
+export type HomeCommand = {
+  cmd: 'control'
+  action: 'home'
+}
+export type ControlCommand = OnOffCommand | MoveRelativeCommand | HomeCommand
 
 // -------------- Services
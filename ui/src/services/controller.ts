@@ -11,7 +11,10 @@ export const ControllerMessageC = t.type({
   z: t.number,
   freezeX: t.boolean,
   freezeY: t.boolean,
-  slow: t.boolean
+  slow: t.boolean,
+  connected: t.boolean,
+  jog: t.boolean,
+  jogStep: t.number
 })
 export type ControllerMessage = t.TypeOf<typeof ControllerMessageC>
 
@@ -35,8 +38,30 @@ export type SlowControlCommand = {
   action: 'slow'
   slow: boolean
 }
+export type JogCommand = {
+  cmd: 'controller'
+  action: 'jog'
+  jog: boolean
+}
+export type JogStepCommand = {
+  cmd: 'controller'
+  action: 'jogStep'
+  step: number
+}
+export type MoveCommand = {
+  cmd: 'controller'
+  action: 'move'
+  x: number
+  y: number
+  z: number
+}
 export type FreezeCommand = FreezeXCommand | FreezeYCommand
-export type ControllerCommand = FreezeCommand | SlowControlCommand
+export type ControllerCommand =
+  | FreezeCommand
+  | MoveCommand
+  | SlowControlCommand
+  | JogCommand
+  | JogStepCommand
 
 // -------------- Services
 
@@ -59,7 +84,7 @@ const controllerServiceLive = (ws: WebSocket): ControllerService => {
 }
 
 const controllerServiceMock: ControllerService = {
-  controller$: of({ type: 'controller', x: 0, y: 0, z: 0, freezeX: false, freezeY: false, slow: false })
+  controller$: of({ type: 'controller', x: 0, y: 0, z: 0, freezeX: false, freezeY: false, slow: false, connected: true, jog: false, jogStep: 1 })
 }
 
 export const controllerService = {
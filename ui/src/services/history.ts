@@ -0,0 +1,77 @@
+import { isRight } from 'fp-ts/lib/Either'
+import * as t from 'io-ts'
+import { BehaviorSubject, Observable } from 'rxjs'
+import { WorkOffset, workOffsetC } from './queue'
+
+// -------------- Messages
+
+export const jobOutcomeC = t.union([
+  t.literal('running'),
+  t.literal('completed'),
+  t.literal('cancelled'),
+  t.literal('alarm')
+])
+export type JobOutcome = t.TypeOf<typeof jobOutcomeC>
+
+export const jobRecordC = t.type({
+  programName: t.string,
+  hash: t.string,
+  startedTs: t.number,
+  endedTs: t.union([t.null, t.number]),
+  outcome: jobOutcomeC,
+  line: t.number,
+  scale: t.number,
+  invertZ: t.boolean,
+  offset: workOffsetC
+})
+export type JobRecord = t.TypeOf<typeof jobRecordC>
+
+export const historyReplyC = t.type({
+  type: t.literal('reply'),
+  msg: t.type({
+    type: t.literal('history'),
+    jobs: t.array(jobRecordC)
+  }),
+  to: t.string
+})
+export type HistoryReply = t.TypeOf<typeof historyReplyC>
+
+export const isHistoryReplyMessage = (msg: object): msg is HistoryReply =>
+  isRight(historyReplyC.decode(msg))
+
+// -------------- Commands
+
+export type HistoryCommand =
+  | { cmd: 'program'; action: 'history' }
+  | {
+      cmd: 'program'
+      action: 'resume'
+      programName: string
+      invertZ: boolean
+      scale: number
+      line: number
+      offset: WorkOffset
+    }
+
+// -------------- Service
+
+export type HistoryService = {
+  history$: Observable<JobRecord[]>
+}
+
+const historyServiceLive = (ws: WebSocket): HistoryService => {
+  const historySub = new BehaviorSubject<JobRecord[]>([])
+  ws.addEventListener('message', ({ data }) => {
+    const msg = JSON.parse(data)
+    if (isHistoryReplyMessage(msg)) {
+      historySub.next(msg.msg.jobs)
+    }
+  })
+  return {
+    history$: historySub.asObservable()
+  }
+}
+
+export const historyService = {
+  live: historyServiceLive
+}
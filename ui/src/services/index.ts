@@ -5,12 +5,21 @@ import { broadcastService, BroadcastService } from './broadcast'
 import { Observable, OperatorFunction, Subject } from 'rxjs'
 import { programService, ProgramService } from './program'
 import { SettingsService, settingsService } from './settings'
+import { QueueService, queueService } from './queue'
+import { HistoryService, historyService } from './history'
+import { SimulationService, simulationService } from './simulation'
 
 export * from './broadcast'
 export * from './controller'
 export * from './types'
 
-type Services = BroadcastService & ControllerService & ProgramService & SettingsService
+type Services = BroadcastService &
+  ControllerService &
+  ProgramService &
+  SettingsService &
+  QueueService &
+  HistoryService &
+  SimulationService
 export type Service = {
   sendCommand: (cmd: CncCommand) => void
 } & Services
@@ -31,7 +40,10 @@ export const mkServiceCtx = (ws: WebSocket): Service => ({
   ...broadcastService.live(ws),
   ...controllerService.live(ws),
   ...programService.live(ws),
-  ...settingsService.live(ws)
+  ...settingsService.live(ws),
+  ...queueService.live(ws),
+  ...historyService.live(ws),
+  ...simulationService.live(ws)
 })
 
 export type AlertMsg = {
@@ -2,14 +2,31 @@ import { isRight } from 'fp-ts/lib/Either'
 import * as t from 'io-ts'
 import { BehaviorSubject, Observable, Subject } from 'rxjs'
 
-export const programInfoC = t.type({
-  name: t.string,
-  path: t.string,
-  size: t.number,
+export const programAnalysisC = t.type({
   linesOfCode: t.number,
-  createDateTs: t.number,
-  modifiedDateTs: t.number
+  min: t.array(t.number),
+  max: t.array(t.number),
+  cutDistance: t.number,
+  rapidDistance: t.number,
+  duration: t.number,
+  tools: t.array(t.number),
+  unsupported: t.array(t.string)
 })
+export type ProgramAnalysis = t.TypeOf<typeof programAnalysisC>
+
+export const programInfoC = t.intersection([
+  t.type({
+    name: t.string,
+    path: t.string,
+    size: t.number,
+    linesOfCode: t.number,
+    createDateTs: t.number,
+    modifiedDateTs: t.number
+  }),
+  t.partial({
+    analysis: programAnalysisC
+  })
+])
 export type ProgramInfo = t.TypeOf<typeof programInfoC>
 
 export const availableProgramsMessageC = t.type({
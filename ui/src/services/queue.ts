@@ -0,0 +1,92 @@
+import { isRight } from 'fp-ts/lib/Either'
+import * as t from 'io-ts'
+import { BehaviorSubject, Observable } from 'rxjs'
+
+// -------------- Messages
+
+export const workOffsetC = t.type({
+  x: t.number,
+  y: t.number,
+  z: t.number
+})
+export type WorkOffset = t.TypeOf<typeof workOffsetC>
+
+export const queuedJobC = t.type({
+  id: t.number,
+  programName: t.string,
+  invertZ: t.boolean,
+  scale: t.number,
+  offset: workOffsetC
+})
+export type QueuedJob = t.TypeOf<typeof queuedJobC>
+
+export const queueStateC = t.union([
+  t.literal('stopped'),
+  t.literal('running'),
+  t.literal('waitingForConfirm')
+])
+export type QueueState = t.TypeOf<typeof queueStateC>
+
+export const queueMessageC = t.type({
+  type: t.literal('queue'),
+  state: queueStateC,
+  jobs: t.array(queuedJobC)
+})
+export type QueueMessage = t.TypeOf<typeof queueMessageC>
+
+export const isQueueMessage = (msg: object): msg is QueueMessage =>
+  isRight(queueMessageC.decode(msg))
+
+export const queueReplyC = t.type({
+  type: t.literal('reply'),
+  msg: queueMessageC,
+  to: t.string
+})
+export type QueueReply = t.TypeOf<typeof queueReplyC>
+
+export const isQueueReplyMessage = (msg: object): msg is QueueReply =>
+  isRight(queueReplyC.decode(msg))
+
+// -------------- Commands
+
+export type QueueCommand =
+  | { cmd: 'queue'; action: 'get' }
+  | {
+      cmd: 'queue'
+      action: 'add'
+      programName: string
+      invertZ: boolean
+      scale: number
+      offset: WorkOffset
+    }
+  | { cmd: 'queue'; action: 'remove'; id: number }
+  | { cmd: 'queue'; action: 'move'; id: number; index: number }
+  | { cmd: 'queue'; action: 'clear' }
+  | { cmd: 'queue'; action: 'start' }
+  | { cmd: 'queue'; action: 'confirm' }
+  | { cmd: 'queue'; action: 'stop' }
+
+// -------------- Service
+
+export type QueueService = {
+  queue$: Observable<QueueMessage | undefined>
+}
+
+const queueServiceLive = (ws: WebSocket): QueueService => {
+  const queueSub = new BehaviorSubject<QueueMessage | undefined>(undefined)
+  ws.addEventListener('message', ({ data }) => {
+    const msg = JSON.parse(data)
+    if (isQueueReplyMessage(msg)) {
+      queueSub.next(msg.msg)
+    } else if (isQueueMessage(msg)) {
+      queueSub.next(msg)
+    }
+  })
+  return {
+    queue$: queueSub.asObservable()
+  }
+}
+
+export const queueService = {
+  live: queueServiceLive
+}
@@ -17,7 +17,8 @@ export const motorSettingsC = t.intersection([
   t.partial({
     enaGpio: t.number,
     endLeftGpio: t.number,
-    endRightGpio: t.number
+    endRightGpio: t.number,
+    backlash: t.number
   })
 ], 'MotorSettings')
 export type MotorSettings = t.TypeOf<typeof motorSettingsC>
@@ -32,8 +33,13 @@ export const systemC = t.intersection([
     switchOnOffDelay: t.number
   }),
   t.partial({
+    motorXSlave: motorSettingsC,
+    motorYSlave: motorSettingsC,
+    motorA: motorSettingsC,
     calibrateZGpio: t.number,
-    onOffGpio: t.number
+    onOffGpio: t.number,
+    pulseBackend: t.union([t.literal('direct'), t.literal('threaded')]),
+    pulseLatency: t.number
   })
 ], 'System')
 export type System = t.TypeOf<typeof systemC>
@@ -54,7 +60,10 @@ export const runtimeC = t.intersection([
     invertZ: t.boolean,
     showConsoleOutput: t.boolean,
     consolePosUpdateReduce: t.number,
-    externalInputEnabled: t.boolean
+    externalInputEnabled: t.boolean,
+    arcTolerance: t.number,
+    jogTimeout: t.number,
+    maxUploadSize: t.number
   })
 ])
 export type Runtime = t.TypeOf<typeof runtimeC>
@@ -80,10 +89,58 @@ export type SystemSaved = t.TypeOf<typeof systemSavedC>
 export const isSystemSavedMessage = (msg: object): msg is SystemSaved =>
   isRight(systemSavedC.decode(msg))
 
+export const gamepadButtons = [
+  'South', 'East', 'North', 'West', 'C', 'Z',
+  'LeftTrigger', 'LeftTrigger2', 'RightTrigger', 'RightTrigger2',
+  'Select', 'Start', 'Mode', 'LeftThumb', 'RightThumb',
+  'DPadUp', 'DPadDown', 'DPadLeft', 'DPadRight'
+]
+export const gamepadAxes = [
+  'LeftStickX', 'LeftStickY', 'LeftZ',
+  'RightStickX', 'RightStickY', 'RightZ',
+  'DPadX', 'DPadY'
+]
+export const buttonActions = [
+  'quit', 'startProgram', 'cancel', 'resetPosition', 'calibrate',
+  'previousProgram', 'nextProgram', 'selectProgram', 'toggleSlow',
+  'toggleJog', 'jogXPlus', 'jogXMinus', 'jogYPlus', 'jogYMinus',
+  'jogZPlus', 'jogZMinus', 'jogStepUp', 'jogStepDown'
+]
+export const axisActions = ['moveX', 'moveY', 'moveZ']
+
+export const buttonBindingC = t.type({
+  button: t.string,
+  action: t.string
+})
+export type ButtonBinding = t.TypeOf<typeof buttonBindingC>
+
+export const axisBindingC = t.type({
+  axis: t.string,
+  action: t.string,
+  deadzone: t.number,
+  curve: t.number,
+  invert: t.boolean
+})
+export type AxisBinding = t.TypeOf<typeof axisBindingC>
+
+export const gamepadC = t.type({
+  type: t.literal('gamepadSettings'),
+  buttons: t.array(buttonBindingC),
+  axes: t.array(axisBindingC),
+  jogButtons: t.array(buttonBindingC)
+}, 'Gamepad')
+export type Gamepad = t.TypeOf<typeof gamepadC>
+
+export const gamepadSavedC = t.type({
+  type: t.literal('gamepadSettingsSaved'),
+  ok: t.boolean
+})
+export type GamepadSaved = t.TypeOf<typeof gamepadSavedC>
+
 export const programReplyC = t.type({
   type: t.literal('reply'),
   to: t.string,
-  msg: t.union([systemC, runtimeC, runtimeSavedC, systemSavedC])
+  msg: t.union([systemC, runtimeC, runtimeSavedC, systemSavedC, gamepadC, gamepadSavedC])
 })
 export type ProgramReply = t.TypeOf<typeof programReplyC>
 
@@ -103,9 +160,14 @@ export type SetSystemSettingsCommand = {
   motorX: MotorSettings,
   motorY: MotorSettings,
   motorZ: MotorSettings,
+  motorXSlave?: MotorSettings,
+  motorYSlave?: MotorSettings,
+  motorA?: MotorSettings,
   switchOnOffDelay: number
   calibrateZGpio?: number,
   onOffGpio?: number
+  pulseBackend?: 'direct' | 'threaded'
+  pulseLatency?: number
 }
 export type GetRuntimeSettingsCommand = {
   cmd: 'settings'
@@ -123,6 +185,21 @@ export type SetRuntimeSettingsCommand = {
   showConsoleOutput?: boolean
   consolePosUpdateReduce?: number
   externalInputEnabled?:boolean
+  arcTolerance?: number
+  jogTimeout?: number
+  maxUploadSize?: number
+}
+
+export type GetGamepadSettingsCommand = {
+  cmd: 'settings'
+  action: 'getGamepad'
+}
+export type SetGamepadSettingsCommand = {
+  cmd: 'settings'
+  action: 'setGamepad'
+  buttons: ButtonBinding[]
+  axes: AxisBinding[]
+  jogButtons: ButtonBinding[]
 }
 
 export type SettingsCommand =
@@ -130,6 +207,8 @@ export type SettingsCommand =
   | SetSystemSettingsCommand
   | GetRuntimeSettingsCommand
   | SetRuntimeSettingsCommand
+  | GetGamepadSettingsCommand
+  | SetGamepadSettingsCommand
 
 // -------------- Services
 
@@ -138,6 +217,8 @@ export type SettingsService = {
   systemSaved$: Observable<SystemSaved>
   runtime$: Observable<Runtime | undefined>
   runtimeSaved$: Observable<RuntimeSaved>
+  gamepad$: Observable<Gamepad | undefined>
+  gamepadSaved$: Observable<GamepadSaved>
 }
 
 const settingsServiceLive = (ws: WebSocket): SettingsService => {
@@ -145,6 +226,8 @@ const settingsServiceLive = (ws: WebSocket): SettingsService => {
   const runtimeSavedSub = new Subject<RuntimeSaved>()
   const runtimeSub = new BehaviorSubject<Runtime | undefined>(undefined)
   const systemSavedSub = new Subject<SystemSaved>()
+  const gamepadSub = new BehaviorSubject<Gamepad | undefined>(undefined)
+  const gamepadSavedSub = new Subject<GamepadSaved>()
   ws.addEventListener('message', ({ data }) => {
     const reply = JSON.parse(data)
     console.log(reply)
@@ -163,6 +246,12 @@ const settingsServiceLive = (ws: WebSocket): SettingsService => {
         case 'systemSettingsSaved':
           systemSavedSub.next(msg)
           break
+        case 'gamepadSettings':
+          gamepadSub.next(msg)
+          break
+        case 'gamepadSettingsSaved':
+          gamepadSavedSub.next(msg)
+          break
       }
     }
   })
@@ -171,7 +260,9 @@ const settingsServiceLive = (ws: WebSocket): SettingsService => {
     system$: systemSub.asObservable(),
     systemSaved$: systemSavedSub.asObservable(),
     runtime$: runtimeSub.asObservable(),
-    runtimeSaved$: runtimeSavedSub.asObservable()
+    runtimeSaved$: runtimeSavedSub.asObservable(),
+    gamepad$: gamepadSub.asObservable(),
+    gamepadSaved$: gamepadSavedSub.asObservable()
   }
 }
 
@@ -0,0 +1,51 @@
+import { isRight } from 'fp-ts/lib/Either'
+import * as t from 'io-ts'
+import { BehaviorSubject, Observable } from 'rxjs'
+
+// -------------- Messages
+
+export const simulationMessageC = t.type({
+  type: t.literal('simulation'),
+  programName: t.string,
+  duration: t.number,
+  steps: t.number,
+  min: t.array(t.number),
+  max: t.array(t.number)
+})
+export type SimulationMessage = t.TypeOf<typeof simulationMessageC>
+
+export const isSimulationMessage = (msg: object): msg is SimulationMessage =>
+  isRight(simulationMessageC.decode(msg))
+
+// -------------- Commands
+
+export type SimulationCommand = {
+  cmd: 'program'
+  action: 'simulate'
+  programName: string
+  invertZ: boolean
+  scale: number
+}
+
+// -------------- Service
+
+export type SimulationService = {
+  simulation$: Observable<SimulationMessage | undefined>
+}
+
+const simulationServiceLive = (ws: WebSocket): SimulationService => {
+  const simulationSub = new BehaviorSubject<SimulationMessage | undefined>(undefined)
+  ws.addEventListener('message', ({ data }) => {
+    const msg = JSON.parse(data)
+    if (isSimulationMessage(msg)) {
+      simulationSub.next(msg)
+    }
+  })
+  return {
+    simulation$: simulationSub.asObservable()
+  }
+}
+
+export const simulationService = {
+  live: simulationServiceLive
+}
@@ -1,6 +1,16 @@
 import { ControlCommand } from './control'
 import { ControllerCommand } from './controller'
+import { HistoryCommand } from './history'
 import { ProgramCommand } from './program'
+import { QueueCommand } from './queue'
 import { SettingsCommand } from './settings'
+import { SimulationCommand } from './simulation'
 
-export type CncCommand = ControllerCommand | ProgramCommand | SettingsCommand | ControlCommand
+export type CncCommand =
+  | ControllerCommand
+  | ProgramCommand
+  | SettingsCommand
+  | ControlCommand
+  | QueueCommand
+  | HistoryCommand
+  | SimulationCommand
@@ -0,0 +1,10 @@
+// the controller serves http / ws or https / wss, depending on its tls settings
+const secure = window.location.protocol === 'https:'
+// the release build is served by the controller on its configured port, the dev server runs next to it
+const host =
+  process.env.NODE_ENV === 'production'
+    ? window.location.host
+    : `${window.location.hostname}:1506`
+
+export const apiUrl = `${secure ? 'https' : 'http'}://${host}/api`
+export const wsUrl = `${secure ? 'wss' : 'ws'}://${host}/ws`
@@ -1,11 +1,16 @@
 // eslint-disable-next-line no-use-before-define
-import React, { useState } from 'react'
-import { obs, StatusMessage } from '../services'
+import React, { useContext, useState } from 'react'
+import { Button } from '../components/Button'
+import { obs, ServiceCtx, StatusMessage } from '../services'
+import { Backlash } from '../widget/Backlash'
 
 export const CalibrateView = () => {
   const [status, setStatus] = useState<StatusMessage>()
+  const service = useContext(ServiceCtx)
   obs('status$', setStatus)
 
+  const home = () => service?.sendCommand({ cmd: 'control', action: 'home' })
+
   return (
     <div className="viewMain">
       {status && status.devMode && (
@@ -15,7 +20,16 @@ export const CalibrateView = () => {
         Calibrate
       </div>
       <div className="viewCardBox">
-        <div>coming soon</div>
+        <div>
+          <div className="card">
+            <div className="header">Home</div>
+            <div className="content">
+              <div>Drive X and Y into the min end switches. Squares a gantry with two motors.</div>
+              <Button onClick={home}>Home X / Y</Button>
+            </div>
+          </div>
+          <Backlash />
+        </div>
       </div>
     </div>
   )
@@ -2,8 +2,10 @@
 import React, { useState } from 'react'
 import { obs, StatusMessage } from '../services'
 import { Controller } from '../widget/Controller'
+import { Jog } from '../widget/Jog'
 import { Mode } from '../widget/Mode'
 import { Position } from '../widget/Position'
+import { VirtualJoystick } from '../widget/VirtualJoystick'
 
 export const MainView = () => {
   const [status, setStatus] = useState<StatusMessage>()
@@ -19,6 +21,8 @@ export const MainView = () => {
         <div>
           <Position />
           <Controller />
+          <Jog />
+          <VirtualJoystick />
         </div>
         <div>
           <Mode />
@@ -1,10 +1,13 @@
 // eslint-disable-next-line no-use-before-define
 import React, { useState } from 'react'
 import { obs, StatusMessage } from '../services'
+import { JobHistory } from '../widget/JobHistory'
+import { JobQueue } from '../widget/JobQueue'
 import { Mode } from '../widget/Mode'
 import { ProgramEditor } from '../widget/ProgramEditor'
 import { ProgramMetaData } from '../widget/ProgramMetaData'
 import { ProgramSelect } from '../widget/ProgramSelect'
+import { Simulation } from '../widget/Simulation'
 
 export const ProgramView = () => {
   const [status, setStatus] = useState<StatusMessage>()
@@ -23,6 +26,9 @@ export const ProgramView = () => {
         <div className="ProgViewInfo">
           <Mode />
           <ProgramMetaData />
+          <JobQueue />
+          <JobHistory />
+          <Simulation />
         </div>
       </div>
     </div>
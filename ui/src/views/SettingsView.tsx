@@ -1,6 +1,7 @@
 // eslint-disable-next-line no-use-before-define
 import React, { useState } from 'react'
 import { obs, StatusMessage } from '../services'
+import { Gamepad } from '../widget/Gamepad'
 import { Settings } from '../widget/Settings'
 import { System } from '../widget/System'
 
@@ -19,6 +20,7 @@ export const SettingsView = () => {
         <div>
           <Settings />
           <System />
+          <Gamepad />
         </div>
       </div>
     </div>
@@ -0,0 +1,119 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useContext, useState } from 'react'
+import { Button } from '../components/Button'
+import { InputField } from '../components/form'
+import { ToggleButton } from '../components/ToggleButton'
+import { AlertCtx, obs, ServiceCtx } from '../services'
+import { System as SystemSettings } from '../services/settings'
+
+type Axis = 'x' | 'y' | 'z'
+const motorKey = (axis: Axis) =>
+  axis === 'x' ? 'motorX' : axis === 'y' ? 'motorY' : 'motorZ'
+
+export const Backlash = () => {
+  const service = useContext(ServiceCtx)
+  const { publish } = useContext(AlertCtx)
+  const [system, setSystem] = useState<SystemSettings | undefined>()
+  const [axis, setAxis] = useState<Axis>('x')
+  const [distance, setDistance] = useState(1)
+  const [measured, setMeasured] = useState(1)
+  const [step, setStep] = useState(0)
+
+  obs('system$', (p) => setSystem(p))
+  obs('systemSaved$', (res) =>
+    publish({
+      message: res.ok
+        ? 'Backlash is saved. Please restart the System'
+        : 'Save backlash failed'
+    })
+  )
+
+  React.useEffect(() => {
+    service?.sendCommand({ cmd: 'settings', action: 'getSystem' })
+  }, [])
+
+  const move = (d: number) =>
+    service?.sendCommand({
+      cmd: 'control',
+      action: 'moveRelative',
+      x: axis === 'x' ? d : 0,
+      y: axis === 'y' ? d : 0,
+      z: axis === 'z' ? d : 0
+    })
+
+  // the active compensation is part of the move, only the rest is measured
+  const current = (system && system[motorKey(axis)].backlash) || 0
+  const backlash = Math.max(0, current + distance - measured)
+
+  const apply = () => {
+    if (system) {
+      const key = motorKey(axis)
+      service?.sendCommand({
+        cmd: 'settings',
+        action: 'setSystem',
+        ...system,
+        [key]: { ...system[key], backlash }
+      })
+    }
+    setStep(0)
+  }
+
+  return (
+    <div className="card">
+      <div className="header">Measure backlash</div>
+      <div className="content">
+        <div className="row">
+          {(['x', 'y', 'z'] as Axis[]).map((a) => (
+            <ToggleButton key={a} value={axis === a} onClick={() => setAxis(a)}>
+              {a.toUpperCase()}
+            </ToggleButton>
+          ))}
+        </div>
+        <div>
+          current: {system ? current : '---'} mm
+        </div>
+        <InputField
+          type="number"
+          title="Test distance (mm)"
+          value={distance}
+          defaultValue={1}
+          onChanged={setDistance}
+        />
+        {step === 0 && (
+          <>
+            <div>1. Place a dial indicator against the {axis.toUpperCase()} axis.</div>
+            <Button onClick={() => { move(distance); setStep(1) }}>
+              Move +{distance} mm
+            </Button>
+          </>
+        )}
+        {step === 1 && (
+          <>
+            <div>2. Zero the dial indicator, then reverse the axis.</div>
+            <Button onClick={() => { move(-distance); setStep(2) }}>
+              Move -{distance} mm
+            </Button>
+          </>
+        )}
+        {step === 2 && (
+          <>
+            <div>3. Enter the distance shown on the dial indicator.</div>
+            <InputField
+              type="number"
+              title="Measured (mm)"
+              value={measured}
+              defaultValue={distance}
+              onChanged={setMeasured}
+            />
+            <div>Backlash: {backlash.toFixed(3)} mm</div>
+            <div style={{ display: 'flex', justifyContent: 'space-between' }}>
+              <Button onClick={() => setStep(0)}>Restart</Button>
+              <Button onClick={apply}>Save</Button>
+            </div>
+          </>
+        )}
+      </div>
+    </div>
+  )
+}
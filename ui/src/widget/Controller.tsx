@@ -22,6 +22,11 @@ export const Controller = () => {
     <div className="card">
       <div className="header" style={{ display: 'flex' }}>
         <span>Controller</span>
+        {controller && (
+          <span style={{ marginLeft: 10, opacity: 0.6 }}>
+            {controller.connected ? 'connected' : 'not connected'}
+          </span>
+        )}
         <span style={{ flex: 1 }}></span>{' '}
         <div style={{ width: 250, display: 'inline-block', margin: '-20px' }}>
           {controller && (
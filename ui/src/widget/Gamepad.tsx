@@ -0,0 +1,197 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useContext, useState } from 'react'
+import { Button } from '../components/Button'
+import { InputField } from '../components/form'
+import { ToggleButton } from '../components/ToggleButton'
+import { AlertCtx, obs, ServiceCtx } from '../services'
+import {
+  axisActions,
+  AxisBinding,
+  buttonActions,
+  ButtonBinding,
+  Gamepad as GamepadSettings,
+  gamepadAxes,
+  gamepadButtons
+} from '../services/settings'
+
+const Select = ({
+  value,
+  options,
+  onChanged
+}: {
+  value: string
+  options: string[]
+  onChanged: (v: string) => void
+}) => (
+  <select value={value} onChange={(e) => onChanged(e.target.value)}>
+    {options.map((o) => (
+      <option key={o} value={o}>
+        {o}
+      </option>
+    ))}
+  </select>
+)
+
+const ButtonList = ({
+  buttons,
+  onChanged
+}: {
+  buttons: ButtonBinding[]
+  onChanged: (buttons: ButtonBinding[]) => void
+}) => {
+  const setButton = (i: number, b: Partial<ButtonBinding>) =>
+    onChanged(buttons.map((v, j) => (i === j ? { ...v, ...b } : v)))
+
+  return (
+    <>
+      {buttons.map((b, i) => (
+        <div className="row" key={i}>
+          <Select
+            value={b.button}
+            options={gamepadButtons}
+            onChanged={(button) => setButton(i, { button })}
+          />
+          <Select
+            value={b.action}
+            options={buttonActions}
+            onChanged={(action) => setButton(i, { action })}
+          />
+          <Button onClick={() => onChanged(buttons.filter((_, j) => i !== j))}>
+            Remove
+          </Button>
+        </div>
+      ))}
+      <Button
+        onClick={() =>
+          onChanged([...buttons, { button: 'South', action: 'selectProgram' }])
+        }
+      >
+        Add button
+      </Button>
+    </>
+  )
+}
+
+export const Gamepad = () => {
+  const service = useContext(ServiceCtx)
+  const { publish } = useContext(AlertCtx)
+  const [settings, setSettings] = useState<GamepadSettings | undefined>()
+
+  obs('gamepad$', (p) => setSettings(p))
+  obs('gamepadSaved$', (res) =>
+    publish({
+      message: res.ok ? 'Gamepad mapping is applied' : 'Apply gamepad mapping failed'
+    })
+  )
+
+  const reload = () =>
+    service?.sendCommand({ cmd: 'settings', action: 'getGamepad' })
+  const save = () =>
+    settings &&
+    service?.sendCommand({
+      cmd: 'settings',
+      action: 'setGamepad',
+      buttons: settings.buttons,
+      axes: settings.axes,
+      jogButtons: settings.jogButtons
+    })
+
+  React.useEffect(() => {
+    reload()
+  }, [])
+
+  const setAxis = (i: number, a: Partial<AxisBinding>) =>
+    settings &&
+    setSettings({
+      ...settings,
+      axes: settings.axes.map((v, j) => (i === j ? { ...v, ...a } : v))
+    })
+
+  return (
+    <div className="cardStretch">
+      <div className="header">Gamepad mapping</div>
+      <div className="content">
+        {(settings && (
+          <>
+            <div>Buttons</div>
+            <ButtonList
+              buttons={settings.buttons}
+              onChanged={(buttons) => setSettings({ ...settings, buttons })}
+            />
+            <div>Buttons in jog mode</div>
+            <ButtonList
+              buttons={settings.jogButtons}
+              onChanged={(jogButtons) => setSettings({ ...settings, jogButtons })}
+            />
+            <div>Axes</div>
+            {settings.axes.map((a, i) => (
+              <div key={i}>
+                <div className="row">
+                  <Select
+                    value={a.axis}
+                    options={gamepadAxes}
+                    onChanged={(axis) => setAxis(i, { axis })}
+                  />
+                  <Select
+                    value={a.action}
+                    options={axisActions}
+                    onChanged={(action) => setAxis(i, { action })}
+                  />
+                  <ToggleButton
+                    value={a.invert}
+                    onClick={(invert) => setAxis(i, { invert })}
+                  >
+                    Invert
+                  </ToggleButton>
+                  <Button
+                    onClick={() =>
+                      setSettings({
+                        ...settings,
+                        axes: settings.axes.filter((_, j) => i !== j)
+                      })
+                    }
+                  >
+                    Remove
+                  </Button>
+                </div>
+                <InputField
+                  type="number"
+                  title="Deadzone (0.15)"
+                  value={a.deadzone}
+                  defaultValue={0.15}
+                  onChanged={(deadzone) => setAxis(i, { deadzone })}
+                />
+                <InputField
+                  type="number"
+                  title="Curve exponent (1.0 = linear)"
+                  value={a.curve}
+                  defaultValue={1}
+                  onChanged={(curve) => setAxis(i, { curve })}
+                />
+              </div>
+            ))}
+            <Button
+              onClick={() =>
+                setSettings({
+                  ...settings,
+                  axes: [
+                    ...settings.axes,
+                    { axis: 'LeftStickX', action: 'moveX', deadzone: 0.15, curve: 1, invert: false }
+                  ]
+                })
+              }
+            >
+              Add axis
+            </Button>
+            <div style={{ display: 'flex', justifyContent: 'space-between' }}>
+              <Button onClick={reload}>Reload</Button>
+              <Button onClick={save}>Save</Button>
+            </div>
+          </>
+        )) ||
+          'loading'}
+      </div>
+    </div>
+  )
+}
@@ -0,0 +1,83 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useContext, useState } from 'react'
+import { createUseStyles } from 'react-jss'
+import { Button } from '../components/Button'
+import { obs, ServiceCtx } from '../services'
+import { JobRecord } from '../services/history'
+
+const duration = (job: JobRecord) =>
+  job.endedTs === null ? '---' : `${Math.round((job.endedTs - job.startedTs) / 60)} min`
+
+export const JobHistory = () => {
+  const [jobs, setJobs] = useState<JobRecord[]>([])
+  const { jobList } = useStyle()
+  const service = useContext(ServiceCtx)
+
+  obs('history$', setJobs)
+
+  const refresh = () => service?.sendCommand({ cmd: 'program', action: 'history' })
+  const resume = (job: JobRecord) => {
+    const line = Number(prompt(`resume ${job.programName} at line`, `${job.line}`))
+    if (line > 0) {
+      const { programName, invertZ, scale, offset } = job
+      service?.sendCommand({
+        cmd: 'program',
+        action: 'resume',
+        programName,
+        invertZ,
+        scale,
+        line,
+        offset
+      })
+    }
+  }
+
+  React.useEffect(() => {
+    refresh()
+  }, [])
+
+  return (
+    <div className="card" style={{ width: 560 }}>
+      <div className="header" style={{ display: 'flex', justifyContent: 'space-between' }}>
+        Job History
+        <div style={{ width: 130 }}>
+          <Button onClick={refresh}>Refresh</Button>
+        </div>
+      </div>
+      <div className={`content ${jobList}`}>
+        {jobs
+          .slice()
+          .reverse()
+          .map((job) => (
+            <div key={`${job.startedTs}-${job.hash}`} title={`sha256 ${job.hash}`}>
+              <span>{new Date(job.startedTs * 1000).toLocaleString()}</span>
+              <span style={{ flex: '1' }}>{job.programName}</span>
+              <span>{job.outcome}</span>
+              <span>line {job.line}</span>
+              <span>{duration(job)}</span>
+              {job.outcome !== 'completed' && job.outcome !== 'running' && (
+                <a style={{ cursor: 'pointer' }} onClick={() => resume(job)}>
+                  resume
+                </a>
+              )}
+            </div>
+          ))}
+      </div>
+    </div>
+  )
+}
+
+const useStyle = createUseStyles({
+  jobList: {
+    maxHeight: '30vh',
+    overflow: 'auto',
+    '& > div': {
+      display: 'flex',
+      padding: '4px 7px',
+      '& > span': {
+        marginRight: 10
+      }
+    }
+  }
+})
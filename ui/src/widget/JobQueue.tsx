@@ -0,0 +1,116 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useContext, useState } from 'react'
+import { createUseStyles } from 'react-jss'
+import { Button } from '../components/Button'
+import { Input } from '../components/Input'
+import { obs, ServiceCtx } from '../services'
+import { QueueMessage } from '../services/queue'
+
+const stateText = {
+  stopped: 'stopped',
+  running: 'running',
+  waitingForConfirm: 'waiting for confirmation (tool change?)'
+}
+
+export const JobQueue = () => {
+  const [queue, setQueue] = useState<QueueMessage>()
+  const [programName, setProgramName] = useState<string>()
+  const [scale, setScale] = useState('1')
+  const [offset, setOffset] = useState({ x: '0', y: '0', z: '0' })
+  const { jobList, row } = useStyle()
+  const service = useContext(ServiceCtx)
+
+  obs('queue$', setQueue)
+  obs('loadProg$', (p) => p && setProgramName(p.programName))
+
+  React.useEffect(() => {
+    service?.sendCommand({ cmd: 'queue', action: 'get' })
+  }, [])
+
+  const num = (v: string) => Number(v) || 0
+  const add = () =>
+    programName &&
+    service?.sendCommand({
+      cmd: 'queue',
+      action: 'add',
+      programName,
+      invertZ: false,
+      scale: num(scale) || 1,
+      offset: { x: num(offset.x), y: num(offset.y), z: num(offset.z) }
+    })
+  const send = (action: 'start' | 'confirm' | 'stop' | 'clear') => () =>
+    service?.sendCommand({ cmd: 'queue', action })
+  const remove = (id: number) => service?.sendCommand({ cmd: 'queue', action: 'remove', id })
+  const move = (id: number, index: number) =>
+    service?.sendCommand({ cmd: 'queue', action: 'move', id, index })
+
+  return (
+    <div className="card" style={{ width: 560 }}>
+      <div className="header">Job Queue: {queue ? stateText[queue.state] : '---'}</div>
+      <div className="content">
+        <div className={row}>
+          <span>{programName || 'select a program'}</span>
+          <div style={{ width: 130 }}>
+            <Button onClick={add}>Add</Button>
+          </div>
+        </div>
+        <div className={row}>
+          scale <Input width={60} value={scale} onChanged={setScale} />
+        </div>
+        <div className={row}>
+          offset
+          {(['x', 'y', 'z'] as const).map((axis) => (
+            <Input
+              key={axis}
+              width={60}
+              value={offset[axis]}
+              onChanged={(v) => setOffset({ ...offset, [axis]: v })}
+            />
+          ))}
+        </div>
+        <div className={jobList}>
+          {queue?.jobs.map((job, i) => (
+            <div key={job.id} className={row}>
+              <span style={{ flex: '1' }}>
+                {job.programName} (x{job.scale}, {job.offset.x}/{job.offset.y}/{job.offset.z})
+              </span>
+              <a onClick={() => move(job.id, Math.max(0, i - 1))}>up</a>
+              <a onClick={() => move(job.id, i + 1)}>down</a>
+              <a onClick={() => remove(job.id)}>remove</a>
+            </div>
+          ))}
+        </div>
+        <div className={row}>
+          {queue?.state === 'waitingForConfirm' && (
+            <Button onClick={send('confirm')}>Confirm next job</Button>
+          )}
+          {queue?.state === 'stopped' && <Button onClick={send('start')}>Start</Button>}
+          <Button onClick={send('stop')}>Stop</Button>
+          <Button onClick={send('clear')}>Clear</Button>
+        </div>
+      </div>
+    </div>
+  )
+}
+
+const useStyle = createUseStyles({
+  jobList: {
+    fontSize: '1.2em',
+    lineHeight: '1.6em',
+    backgroundColor: 'white',
+    margin: '10px 0px',
+    maxHeight: '30vh',
+    overflow: 'auto'
+  },
+  row: {
+    display: 'flex',
+    alignItems: 'center',
+    justifyContent: 'space-between',
+    padding: '4px 7px',
+    '& > a': {
+      cursor: 'pointer',
+      marginLeft: 10
+    }
+  }
+})
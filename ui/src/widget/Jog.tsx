@@ -0,0 +1,73 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useContext, useState } from 'react'
+import { Button } from '../components/Button'
+import { ToggleButton } from '../components/ToggleButton'
+import { obs, ServiceCtx, ControllerMessage } from '../services'
+
+const steps = [0.01, 0.1, 1, 10]
+type Axis = 'x' | 'y' | 'z'
+
+export const Jog = () => {
+  const service = useContext(ServiceCtx)
+  const [controller, setController] = useState<ControllerMessage>()
+
+  obs('controller$', setController)
+
+  const setJog = (jog: boolean) =>
+    service?.sendCommand({ cmd: 'controller', action: 'jog', jog })
+  const setStep = (step: number) =>
+    service?.sendCommand({ cmd: 'controller', action: 'jogStep', step })
+  const jog = (axis: Axis, dir: number) =>
+    controller &&
+    service?.sendCommand({
+      cmd: 'control',
+      action: 'moveRelative',
+      x: axis === 'x' ? dir * controller.jogStep : 0,
+      y: axis === 'y' ? dir * controller.jogStep : 0,
+      z: axis === 'z' ? dir * controller.jogStep : 0
+    })
+
+  return (
+    <div className="card">
+      <div className="header" style={{ display: 'flex' }}>
+        <span>Jog</span>
+        <span style={{ flex: 1 }}></span>{' '}
+        <div style={{ width: 250, display: 'inline-block', margin: '-20px' }}>
+          {controller && (
+            <ToggleButton value={controller.jog} onClick={setJog}>
+              Gamepad jog
+            </ToggleButton>
+          )}
+        </div>
+      </div>
+      <div className="content">
+        {controller && (
+          <>
+            <div className="row">
+              {steps.map((s) => (
+                <ToggleButton
+                  key={s}
+                  value={controller.jogStep === s}
+                  onClick={() => setStep(s)}
+                >
+                  {s} mm
+                </ToggleButton>
+              ))}
+            </div>
+            {(['x', 'y', 'z'] as Axis[]).map((axis) => (
+              <div className="row" key={axis}>
+                <Button onClick={() => jog(axis, -1)}>
+                  {axis.toUpperCase()} -
+                </Button>
+                <Button onClick={() => jog(axis, 1)}>
+                  {axis.toUpperCase()} +
+                </Button>
+              </div>
+            ))}
+          </>
+        )}
+      </div>
+    </div>
+  )
+}
@@ -0,0 +1,55 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useState } from 'react'
+import { Button } from '../components/Button'
+import { Input } from '../components/Input'
+import { apiUrl } from '../services/url'
+
+type Props = {
+  onLoggedIn: () => void
+}
+
+export const Login = ({ onLoggedIn }: Props) => {
+  const [name, setName] = useState('')
+  const [password, setPassword] = useState('')
+  const [error, setError] = useState<string>()
+
+  // the controller sets the session cookie, it is sent with the WebSocket handshake
+  const login = () =>
+    fetch(`${apiUrl}/login`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ name, password }),
+      credentials: 'include'
+    })
+      .then((res) =>
+        res.ok
+          ? onLoggedIn()
+          : setError(
+              res.status === 429
+                ? 'too many failed logins, try again in a minute'
+                : 'invalid name or password'
+            )
+      )
+      .catch((e) => setError(`${e}`))
+
+  return (
+    <div className="card">
+      <div className="header">Login</div>
+      <div className="content">
+        <div className="inputToggleMain">
+          Name
+          <Input value={name} onChanged={setName} />
+        </div>
+        <div className="inputToggleMain">
+          Password
+          <Input type="password" value={password} onChanged={setPassword} />
+        </div>
+        {error && <div>{error}</div>}
+        <div style={{ width: 130 }}>
+          <Button onClick={login}>Login</Button>
+        </div>
+      </div>
+    </div>
+  )
+}
@@ -22,6 +22,11 @@ export const Position = () => {
             <div className="posValue">
               Z<div>{(pos.z / 10).toFixed(2)} cm</div>
             </div>
+            {pos.a !== undefined && (
+              <div className="posValue">
+                A<div>{pos.a.toFixed(2)} °</div>
+              </div>
+            )}
           </div>
         )}
       </div>
@@ -3,7 +3,16 @@ import * as React from 'react'
 import { useState } from 'react'
 import { createUseStyles } from 'react-jss'
 import { obs } from '../services'
-import { ProgramInfo } from '../services/program'
+import { ProgramAnalysis, ProgramInfo } from '../services/program'
+
+const duration = (sec: number) => {
+  const d = new Date(0)
+  d.setSeconds(sec)
+  return d.toISOString().substr(11, 8)
+}
+
+const range = (a: ProgramAnalysis, i: number) =>
+  `${a.min[i].toFixed(2)} .. ${a.max[i].toFixed(2)}`
 
 export const ProgramMetaData = () => {
   const [programName, setProgramName] = useState<string>()
@@ -15,6 +24,33 @@ export const ProgramMetaData = () => {
 
   obs('availableProgs$', (p) => p && setPrograms(p.progs))
 
+  const Analysis = ({ analysis }: { analysis: ProgramAnalysis }) => (
+    <>
+      {['x', 'y', 'z'].map((axis, i) => (
+        <div key={axis} className={value}>
+          <span>Range {axis.toUpperCase()}:</span> <div>{range(analysis, i)} mm</div>
+        </div>
+      ))}
+      <div className={value}>
+        <span>Cut / Rapid:</span>{' '}
+        <div>
+          {analysis.cutDistance.toFixed(0)} mm / {analysis.rapidDistance.toFixed(0)} mm
+        </div>
+      </div>
+      <div className={value}>
+        <span>Estimated Time:</span> <div>{duration(analysis.duration)}</div>
+      </div>
+      <div className={value}>
+        <span>Tools:</span> <div>{analysis.tools.join(', ') || '---'}</div>
+      </div>
+      {analysis.unsupported.length > 0 && (
+        <div className={value}>
+          <span>Unsupported:</span> <div>{analysis.unsupported.join(', ')}</div>
+        </div>
+      )}
+    </>
+  )
+
   const Program = ({ prog }: { prog?: ProgramInfo }) => {
     if (!prog) {
       return <></>
@@ -38,6 +74,7 @@ export const ProgramMetaData = () => {
         <div className={value}>
           <span>Lines Of Code:</span> <div>{prog.linesOfCode}</div>
         </div>
+        {prog.analysis && <Analysis analysis={prog.analysis} />}
       </div>
     )
   }
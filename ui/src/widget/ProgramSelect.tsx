@@ -5,6 +5,7 @@ import { createUseStyles } from 'react-jss'
 import { Button } from '../components/Button'
 import { obs, ServiceCtx } from '../services'
 import { AvailableProgramsMessage } from '../services/program'
+import { apiUrl } from '../services/url'
 
 export const ProgramSelect = () => {
   const [programs, setPrograms] = useState<AvailableProgramsMessage>()
@@ -21,6 +22,31 @@ export const ProgramSelect = () => {
   const refresh = () => service?.sendCommand({ cmd: 'program', action: 'get' })
   const load = (programName: string) =>
     service?.sendCommand({ cmd: 'program', action: 'load', programName })
+  // the controller refreshes the program list after the upload
+  const upload = (files: FileList | null, overwrite = false) => {
+    if (!files || files.length === 0) {
+      return
+    }
+    const form = new FormData()
+    Array.from(files).forEach((file) => form.append('file', file))
+    fetch(`${apiUrl}/upload?overwrite=${overwrite}`, {
+      method: 'POST',
+      body: form,
+      credentials: 'include',
+    })
+      .then(
+        (res) =>
+          res.ok ||
+          res.json().then((e) => {
+            if (e.exists && confirm(`${e.error}, replace it?`)) {
+              upload(files, true)
+            } else {
+              alert(e.error)
+            }
+          })
+      )
+      .catch((e) => alert(e))
+  }
 
   return (
     <div className="cardStretch ProgSelectCardBox">
@@ -44,6 +70,15 @@ export const ProgramSelect = () => {
             <Button onClick={refresh}>Refresh</Button>
           </div>
         </div>
+        <div>
+          Upload:{' '}
+          <input
+            type="file"
+            multiple
+            accept=".gcode,.ngc,.nc"
+            onChange={(e) => upload(e.target.files)}
+          />
+        </div>
 
         <div className={progList}>
           {programs
@@ -64,6 +99,13 @@ export const ProgramSelect = () => {
                   <span>
                     ({new Date(p.modifiedDateTs * 1000).toLocaleDateString()})
                   </span>
+                  <a
+                    href={`${apiUrl}/download/${p.name}`}
+                    style={{ marginLeft: 10 }}
+                    onClick={(e) => e.stopPropagation()}
+                  >
+                    download
+                  </a>
                 </div>
               ))
             : 'loading'}
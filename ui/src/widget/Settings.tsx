@@ -96,6 +96,42 @@ export const Settings = () => {
                 })
               }
             />
+            <InputField
+              type="number"
+              title="Arc chord tolerance (0.01 mm)"
+              value={settings.arcTolerance}
+              defaultValue={0.01}
+              onChanged={(value) =>
+                setSettings({
+                  ...settings,
+                  arcTolerance: value
+                })
+              }
+            />
+            <InputField
+              type="number"
+              title="Virtual joystick timeout (0.5 sec)"
+              value={settings.jogTimeout}
+              defaultValue={0.5}
+              onChanged={(value) =>
+                setSettings({
+                  ...settings,
+                  jogTimeout: value
+                })
+              }
+            />
+            <InputField
+              type="number"
+              title="Max upload size (50 MB)"
+              value={settings.maxUploadSize}
+              defaultValue={50}
+              onChanged={(value) =>
+                setSettings({
+                  ...settings,
+                  maxUploadSize: value
+                })
+              }
+            />
             <ToggleField
               title="Show system output on console"
               value={settings.showConsoleOutput}
@@ -0,0 +1,80 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useContext, useState } from 'react'
+import { createUseStyles } from 'react-jss'
+import { Button } from '../components/Button'
+import { obs, ServiceCtx } from '../services'
+import { SimulationMessage } from '../services/simulation'
+import { apiUrl } from '../services/url'
+
+const duration = (sec: number) => {
+  const d = new Date(0)
+  d.setSeconds(sec)
+  return d.toISOString().substr(11, 8)
+}
+
+const range = (sim: SimulationMessage, i: number) =>
+  `${sim.min[i].toFixed(2)} .. ${sim.max[i].toFixed(2)}`
+
+export const Simulation = () => {
+  const [simulation, setSimulation] = useState<SimulationMessage>()
+  const [programName, setProgramName] = useState<string>()
+  const { row } = useStyle()
+  const service = useContext(ServiceCtx)
+
+  obs('simulation$', setSimulation)
+  obs('loadProg$', (p) => p && setProgramName(p.programName))
+
+  const simulate = () =>
+    programName &&
+    service?.sendCommand({
+      cmd: 'program',
+      action: 'simulate',
+      programName,
+      invertZ: false,
+      scale: 1
+    })
+
+  return (
+    <div className="card" style={{ width: 560 }}>
+      <div className="header">Simulation</div>
+      <div className="content">
+        <div className={row}>
+          <span>{programName || 'select a program'}</span>
+          <div style={{ width: 130 }}>
+            <Button onClick={simulate}>Simulate</Button>
+          </div>
+        </div>
+        {simulation && (
+          <>
+            <div className={row}>
+              {simulation.programName}: {duration(simulation.duration)}, {simulation.steps} steps
+            </div>
+            {['x', 'y', 'z'].map((axis, i) => (
+              <div key={axis} className={row}>
+                {axis}: {range(simulation, i)} mm
+              </div>
+            ))}
+            <div className={row}>
+              <a href={`${apiUrl}/simulation?format=csv`}>timeline (CSV)</a>
+              <a href={`${apiUrl}/simulation?format=json`}>timeline (JSON)</a>
+            </div>
+          </>
+        )}
+      </div>
+    </div>
+  )
+}
+
+const useStyle = createUseStyles({
+  row: {
+    display: 'flex',
+    alignItems: 'center',
+    justifyContent: 'space-between',
+    padding: '4px 7px',
+    '& > a': {
+      cursor: 'pointer',
+      marginLeft: 10
+    }
+  }
+})
@@ -89,6 +89,80 @@ export const System = () => {
                   })
                 }
               />
+              <ToggleField
+                title="Second X motor (gantry)"
+                defaultValue={false}
+                value={settings.motorXSlave !== undefined}
+                onChanged={(value) =>
+                  setSettings({
+                    ...settings,
+                    motorXSlave: value ? settings.motorX : undefined
+                  })
+                }
+              />
+              {settings.motorXSlave && (
+                <MotorBox
+                  title="Motor X slave"
+                  motor={settings.motorXSlave}
+                  defaultSettings={{
+                    maxStepSpeed: 1500,
+                    pull: 17,
+                    dir: 4,
+                    invertDir: false,
+                    ena: 1,
+                    minStop: 12,
+                    maxStop: 7,
+                    stepSize: 0.004,
+                    acceleration: 5.7,
+                    freeStepSpeed: 25,
+                    accelerationDamping: 0.0008,
+                    accelerationTimeScale: 2
+                  }}
+                  onChange={(m) =>
+                    setSettings({
+                      ...settings,
+                      motorXSlave: m
+                    })
+                  }
+                />
+              )}
+              <ToggleField
+                title="Second Y motor (gantry)"
+                defaultValue={false}
+                value={settings.motorYSlave !== undefined}
+                onChanged={(value) =>
+                  setSettings({
+                    ...settings,
+                    motorYSlave: value ? settings.motorY : undefined
+                  })
+                }
+              />
+              {settings.motorYSlave && (
+                <MotorBox
+                  title="Motor Y slave"
+                  motor={settings.motorYSlave}
+                  defaultSettings={{
+                    maxStepSpeed: 1500,
+                    pull: 17,
+                    dir: 4,
+                    invertDir: false,
+                    ena: 1,
+                    minStop: 12,
+                    maxStop: 7,
+                    stepSize: 0.004,
+                    acceleration: 5.7,
+                    freeStepSpeed: 25,
+                    accelerationDamping: 0.0008,
+                    accelerationTimeScale: 2
+                  }}
+                  onChange={(m) =>
+                    setSettings({
+                      ...settings,
+                      motorYSlave: m
+                    })
+                  }
+                />
+              )}
               <MotorBox
                 title="Motor Z"
                 motor={settings.motorZ}
@@ -113,6 +187,53 @@ export const System = () => {
                   })
                 }
               />
+              <ToggleField
+                title="Rotary A axis"
+                defaultValue={false}
+                value={settings.motorA !== undefined}
+                onChanged={(value) =>
+                  setSettings({
+                    ...settings,
+                    motorA: value
+                      ? {
+                          ...settings.motorZ,
+                          pullGpio: 22,
+                          dirGpio: 23,
+                          endLeftGpio: undefined,
+                          endRightGpio: undefined,
+                          stepSize: 0.1125,
+                          backlash: 0
+                        }
+                      : undefined
+                  })
+                }
+              />
+              {settings.motorA && (
+                <MotorBox
+                  title="Motor A (step size in deg)"
+                  motor={settings.motorA}
+                  defaultSettings={{
+                    maxStepSpeed: 1500,
+                    pull: 22,
+                    dir: 23,
+                    invertDir: false,
+                    ena: 1,
+                    minStop: 20,
+                    maxStop: 26,
+                    stepSize: 0.1125,
+                    acceleration: 5.7,
+                    freeStepSpeed: 25,
+                    accelerationDamping: 0.0008,
+                    accelerationTimeScale: 2
+                  }}
+                  onChange={(m) =>
+                    setSettings({
+                      ...settings,
+                      motorA: m
+                    })
+                  }
+                />
+              )}
             </div>
             <div className="SystemGeneralSettings">
               <InputToggle
@@ -152,6 +273,29 @@ export const System = () => {
                   })
                 }
               />
+              <ToggleField
+                title="Play steps on a dedicated pulse thread"
+                defaultValue={false}
+                value={settings.pulseBackend === 'threaded'}
+                onChanged={(value) =>
+                  setSettings({
+                    ...settings,
+                    pulseBackend: value ? 'threaded' : 'direct'
+                  })
+                }
+              />
+              <InputField
+                type="number"
+                title="Pulse buffer latency [sec]"
+                value={settings.pulseLatency}
+                defaultValue={0.02}
+                onChanged={(value) =>
+                  setSettings({
+                    ...settings,
+                    pulseLatency: value
+                  })
+                }
+              />
               <ToggleField
                 title="Developer mode"
                 defaultValue={false}
@@ -0,0 +1,105 @@
+// eslint-disable-next-line no-use-before-define
+import * as React from 'react'
+import { useContext, useEffect, useRef, useState } from 'react'
+import { ServiceCtx } from '../services'
+
+// resend the stick while it is held, the controller stops if the updates are missing
+const UPDATE_INTERVAL_MS = 100
+const SIZE = 200
+
+type Stick = { x: number, y: number, z: number }
+const center: Stick = { x: 0, y: 0, z: 0 }
+
+const clamp = (v: number) => Math.max(-1, Math.min(1, v))
+
+export const VirtualJoystick = () => {
+  const service = useContext(ServiceCtx)
+  const [stick, setStick] = useState<Stick>(center)
+  const [active, setActive] = useState(false)
+  const padRef = useRef<HTMLDivElement>(null)
+
+  const send = (s: Stick) =>
+    service?.sendCommand({ cmd: 'controller', action: 'move', ...s })
+
+  useEffect(() => {
+    if (!active) {
+      return () => undefined
+    }
+    send(stick)
+    const timer = setInterval(() => send(stick), UPDATE_INTERVAL_MS)
+    return () => clearInterval(timer)
+  }, [active, stick])
+
+  const release = () => {
+    setActive(false)
+    setStick(center)
+    send(center)
+  }
+
+  const movePad = (e: React.PointerEvent) => {
+    const rect = padRef.current?.getBoundingClientRect()
+    if (!rect || !active) {
+      return
+    }
+    setStick({
+      ...stick,
+      x: clamp(((e.clientX - rect.left) / rect.width) * 2 - 1),
+      y: clamp(1 - ((e.clientY - rect.top) / rect.height) * 2)
+    })
+  }
+
+  return (
+    <div className="card">
+      <div className="header">Virtual joystick</div>
+      <div className="content">
+        <div className="row">
+          <div
+            ref={padRef}
+            style={{
+              position: 'relative',
+              width: SIZE,
+              height: SIZE,
+              borderRadius: SIZE / 2,
+              backgroundColor: '#f0f0f0',
+              touchAction: 'none'
+            }}
+            onPointerDown={(e) => {
+              e.currentTarget.setPointerCapture(e.pointerId)
+              setActive(true)
+            }}
+            onPointerMove={movePad}
+            onPointerUp={release}
+            onPointerCancel={release}
+          >
+            <div
+              style={{
+                position: 'absolute',
+                width: 40,
+                height: 40,
+                borderRadius: 20,
+                backgroundColor: '#b0b0f8',
+                left: ((stick.x + 1) / 2) * SIZE - 20,
+                top: ((1 - stick.y) / 2) * SIZE - 20
+              }}
+            />
+          </div>
+          <div className="posValue">
+            Z
+            <input
+              type="range"
+              min={-1}
+              max={1}
+              step={0.05}
+              value={stick.z}
+              style={{ writingMode: 'vertical-lr', direction: 'rtl', height: SIZE }}
+              onPointerDown={() => setActive(true)}
+              onChange={(e) => setStick({ ...stick, z: +e.target.value })}
+              onPointerUp={release}
+              onPointerCancel={release}
+            />
+          </div>
+        </div>
+      </div>
+    </div>
+  )
+}